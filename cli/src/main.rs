@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 fn main() {
     let mut parser = markdown_it::MarkdownIt::new();
     markdown_it::plugins::cmark::add(&mut parser);
@@ -8,6 +6,7 @@ fn main() {
     markdown_it::plugins::sourcepos::add(&mut parser);
     markdown_it::plugins::gfm::add(&mut parser);
     markdown_it::plugins::pandoc::add(&mut parser);
+    markdown_it::plugins::jotdown::add(&mut parser);
     println!(
         "{}",
         parser
@@ -22,6 +21,6 @@ flowchart TD
 ````
         "#
             )
-            .render(&HashMap::new())
+            .render(&Default::default())
     );
 }
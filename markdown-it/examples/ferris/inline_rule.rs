@@ -1,7 +1,6 @@
 // Replaces `(\/)` with `🦀`.
 
-use std::collections::HashMap;
-
+use markdown_it::parser::extset::RenderOptionsExtSet;
 use markdown_it::parser::inline::{InlineRule, InlineState};
 use markdown_it::{MarkdownIt, Node, NodeValue, Renderer};
 
@@ -13,7 +12,7 @@ pub struct InlineFerris;
 
 // This defines how your custom node should be rendered.
 impl NodeValue for InlineFerris {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, _options: &HashMap<String, String>) {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, _options: &RenderOptionsExtSet) {
         // `node.attrs` are custom attributes added by other plugins
         // (for example, source mapping information)
         let mut attrs = node.attrs.clone();
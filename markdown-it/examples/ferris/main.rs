@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 // Adds three custom plugins and runs them.
 mod block_rule;
 mod core_rule;
@@ -21,7 +19,7 @@ fn main() {
     let html = md.parse(r#"
 (\/) hello world (\/)
 (\/)-------------(\/)
-    "#).render(&HashMap::new());
+    "#).render(&Default::default());
 
     print!("{html}");
 
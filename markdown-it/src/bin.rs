@@ -1,18 +1,22 @@
 use markdown_it::parser::inline::{Text, TextSpecial};
+use markdown_it::parser::renderer::ansi::{AnsiRenderer, AnsiRendererOptions};
+use markdown_it::parser::renderer::latex::{LatexRenderer, LatexRendererOptions};
+use markdown_it::parser::renderer::markdown::{MarkdownRenderer, MarkdownRendererOptions};
 use std::io::{Read, Write};
 
 #[cfg(not(tarpaulin_include))]
 fn main() {
-    use std::collections::HashMap;
-
     let mut input = "-".to_owned();
     let mut output = "-".to_owned();
     let mut no_html = false;
+    let mut sanitize = false;
     #[cfg(feature = "linkify")]
     let mut linkify = false;
     let mut typographer = false;
     let mut sourcepos = false;
     let mut show_tree = false;
+    let mut format = "html".to_owned();
+    let mut wrap = 0;
 
     {
         let mut cli = argparse::ArgumentParser::new();
@@ -38,6 +42,12 @@ fn main() {
             "Disable embedded HTML",
         );
 
+        cli.refer(&mut sanitize).add_option(
+            &["--sanitize"],
+            argparse::StoreTrue,
+            "Only keep safe tags and attributes in embedded HTML",
+        );
+
         #[cfg(feature = "linkify")]
         cli.refer(&mut linkify).add_option(
             &["-l", "--linkify"],
@@ -51,6 +61,18 @@ fn main() {
             "Enable smartquotes and other typographic replacements",
         );
 
+        cli.refer(&mut format).add_option(
+            &["-f", "--format"],
+            argparse::Store,
+            "Output format: html (default), markdown, latex, ansi or json (with serde feature)",
+        );
+
+        cli.refer(&mut wrap).add_option(
+            &["--wrap"],
+            argparse::Store,
+            "Wrap markdown or ansi output at this width",
+        );
+
         cli.refer(&mut show_tree).add_option(
             &["--tree"],
             argparse::StoreTrue,
@@ -63,6 +85,11 @@ fn main() {
         cli.parse_args_or_exit();
     }
 
+    if !["html", "markdown", "latex", "ansi", "json"].contains(&format.as_str()) {
+        eprintln!("unknown output format: {format}");
+        std::process::exit(2);
+    }
+
     let vec = if input == "-" {
         let mut vec = Vec::new();
         std::io::stdin().read_to_end(&mut vec).unwrap();
@@ -74,13 +101,22 @@ fn main() {
     let source = String::from_utf8_lossy(&vec);
     let md = &mut markdown_it::MarkdownIt::new();
     markdown_it::plugins::cmark::add(md);
+    // other formats need the tree as it is in the source
+    let html = format == "html";
     #[cfg(feature = "syntect")]
-    markdown_it::plugins::extra::syntect::add(md);
+    if html {
+        markdown_it::plugins::extra::syntect::add(md);
+    }
     markdown_it::plugins::extra::tables::add(md);
     markdown_it::plugins::extra::strikethrough::add(md);
-    markdown_it::plugins::extra::beautify_links::add(md);
+    if html {
+        markdown_it::plugins::extra::beautify_links::add(md);
+    }
     if !no_html {
         markdown_it::plugins::html::add(md);
+        if sanitize {
+            markdown_it::plugins::html::sanitize::add(md);
+        }
     }
     if sourcepos {
         markdown_it::plugins::sourcepos::add(md);
@@ -111,11 +147,59 @@ fn main() {
         return;
     }
 
-    let result = ast.render(&HashMap::new());
+    if !html {
+        let result = if format == "json" {
+            json(&ast)
+        } else if format == "ansi" {
+            // fill the terminal unless width is given
+            let width = if wrap > 0 {
+                wrap
+            } else {
+                std::env::var("COLUMNS").ok().and_then(|columns| columns.parse().ok()).unwrap_or(80)
+            };
+            let mut fmt = AnsiRenderer::with_options(AnsiRendererOptions { width: Some(width), ..Default::default() });
+            fmt.render(&ast);
+            String::from(fmt)
+        } else if format == "latex" {
+            let mut fmt = LatexRenderer::with_options(LatexRendererOptions { standalone: true, ..Default::default() });
+            fmt.render(&ast);
+            String::from(fmt)
+        } else {
+            let options = MarkdownRendererOptions {
+                wrap: if wrap > 0 { Some(wrap) } else { None },
+                ..MarkdownRendererOptions::formatter()
+            };
+            let mut fmt = MarkdownRenderer::with_options(options);
+            fmt.render(&ast);
+            String::from(fmt)
+        };
+
+        if output == "-" {
+            std::io::stdout().write_all(result.as_bytes()).unwrap();
+        } else {
+            std::fs::write(output, result).unwrap();
+        }
+        return;
+    }
 
     if output == "-" {
-        std::io::stdout().write_all(result.as_bytes()).unwrap();
+        let mut out = std::io::BufWriter::new(std::io::stdout().lock());
+        ast.render_to(&mut out, &Default::default()).unwrap();
+        out.flush().unwrap();
     } else {
-        std::fs::write(output, &result).unwrap();
+        let mut out = std::io::BufWriter::new(std::fs::File::create(output).unwrap());
+        ast.render_to(&mut out, &Default::default()).unwrap();
+        out.flush().unwrap();
     }
 }
+
+#[cfg(feature = "serde")]
+fn json(ast: &markdown_it::Node) -> String {
+    ast.to_json() + "\n"
+}
+
+#[cfg(not(feature = "serde"))]
+fn json(_: &markdown_it::Node) -> String {
+    eprintln!("json output requires serde feature");
+    std::process::exit(1);
+}
@@ -15,6 +15,9 @@ static DIGITAL_ENTITY_TEST_RE : Lazy<Regex> = Lazy::new(||
 static UNESCAPE_ALL_RE        : Lazy<Regex> = Lazy::new(||
     Regex::new(&format!("{UNESCAPE_MD_RE}|{ENTITY_RE}")).unwrap()
 );
+static ENTITY_ALL_RE          : Lazy<Regex> = Lazy::new(||
+    Regex::new(ENTITY_RE).unwrap()
+);
 
 #[allow(clippy::manual_range_contains)]
 /// Return true if a `code` you got from `&#xHHHH;` entity is a valid charcode.
@@ -108,6 +111,21 @@ pub fn unescape_all(str: &str) -> Cow<str> {
     })
 }
 
+/// Unescape entities only (`&quot; -> "`), for text taken from raw html.
+/// ```
+/// # use markdown_it::common::utils::unescape_entities;
+/// assert_eq!(unescape_entities("&amp;"), "&");
+/// assert_eq!(unescape_entities("\\&amp;"), "\\&");
+/// ```
+pub fn unescape_entities(str: &str) -> Cow<'_, str> {
+    if !str.contains('&') { return Cow::Borrowed(str); }
+
+    ENTITY_ALL_RE.replace_all(str, |captures: &regex::Captures| {
+        let s = captures.get(0).unwrap().as_str();
+        replace_entity_pattern(s).unwrap_or_else(|| s.to_owned())
+    })
+}
+
 /// Escape `" < > &` with corresponding HTML entities;
 /// ```
 /// # use markdown_it::common::utils::escape_html;
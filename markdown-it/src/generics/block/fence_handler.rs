@@ -0,0 +1,193 @@
+//! Custom handlers for code fences, keyed by language.
+//!
+//! Many plugins want to take over a code fence of a particular language,
+//! e.g. ```` ```mermaid ```` for diagrams, ```` ```csv ```` for tables or
+//! ```` ```math ```` for display math. Instead of each of them walking
+//! the tree separately, they register a handler here, and all fences are
+//! transformed in a single pass right after block parsing.
+//!
+//! Language is the first word of the (unescaped) [CodeFence::info] string.
+//! Handlers registered for the same language are tried in order of decreasing
+//! priority (and in order of registration when priorities are equal), until
+//! one of them returns a node. Fences nobody claimed stay as [CodeFence],
+//! so they are still picked up by syntax highlighting.
+//!
+//! ```rust
+//! use markdown_it::generics::block::fence_handler;
+//! use markdown_it::parser::inline::Text;
+//! use markdown_it::plugins::cmark::block::fence::CodeFence;
+//! use markdown_it::{MarkdownIt, Node};
+//!
+//! fn shout(fence: &CodeFence, _: &MarkdownIt) -> Option<Node> {
+//!     Some(Node::new(Text { content: fence.content.to_uppercase() }))
+//! }
+//!
+//! let md = &mut MarkdownIt::new();
+//! markdown_it::plugins::cmark::add(md);
+//! fence_handler::add(md, "shout", shout);
+//!
+//! let html = md.parse("```shout\nhello\n```").render(&Default::default());
+//! assert_eq!(html, "HELLO\n");
+//! ```
+use std::collections::HashMap;
+
+use crate::common::utils::unescape_all;
+use crate::parser::block::builtin::BlockParserRule;
+use crate::parser::core::CoreRule;
+use crate::parser::extset::MarkdownItExt;
+use crate::parser::inline::builtin::InlineParserRule;
+use crate::plugins::cmark::block::fence::CodeFence;
+use crate::{MarkdownIt, Node};
+
+/// Function that converts a code fence into a custom node,
+/// or returns `None` to let the next handler try.
+pub type FenceHandler = fn (fence: &CodeFence, md: &MarkdownIt) -> Option<Node>;
+
+#[derive(Debug, Default)]
+/// Registered fence handlers, stored in [MarkdownIt::ext].
+pub struct FenceHandlers {
+    handlers: HashMap<String, Vec<(i32, FenceHandler)>>,
+}
+
+impl MarkdownItExt for FenceHandlers {}
+
+impl FenceHandlers {
+    /// Handlers for `language`, highest priority first.
+    pub fn get(&self, language: &str) -> &[(i32, FenceHandler)] {
+        self.handlers.get(language).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+}
+
+/// Language of a code fence, first word of its info string.
+pub fn get_language(info: &str) -> String {
+    let info = unescape_all(info);
+    info.split_whitespace().next().unwrap_or("").to_owned()
+}
+
+/// Register handler `f` for fences with given `language`, with default priority (0).
+pub fn add(md: &mut MarkdownIt, language: &str, f: FenceHandler) {
+    add_with_priority(md, language, 0, f);
+}
+
+/// Register handler `f` for fences with given `language`.
+///
+/// Handlers with higher priority are tried first.
+pub fn add_with_priority(md: &mut MarkdownIt, language: &str, priority: i32, f: FenceHandler) {
+    let handlers = md.ext.get_or_insert_default::<FenceHandlers>();
+    let list = handlers.handlers.entry(language.to_owned()).or_default();
+    // insert after all handlers with the same priority, so order is stable
+    let idx = list.iter().position(|(p, _)| *p < priority).unwrap_or(list.len());
+    list.insert(idx, (priority, f));
+
+    if !md.has_rule::<FenceHandlerRule>() {
+        md.add_rule::<FenceHandlerRule>()
+            .after::<BlockParserRule>()
+            .before::<InlineParserRule>();
+    }
+}
+
+/// Remove all handlers registered for `language`.
+pub fn remove(md: &mut MarkdownIt, language: &str) {
+    if let Some(handlers) = md.ext.get_mut::<FenceHandlers>() {
+        handlers.handlers.remove(language);
+    }
+}
+
+#[doc(hidden)]
+pub struct FenceHandlerRule;
+impl CoreRule for FenceHandlerRule {
+    fn run(root: &mut Node, md: &MarkdownIt) {
+        let Some(handlers) = md.ext.get::<FenceHandlers>() else { return };
+        if handlers.is_empty() { return; }
+
+        root.walk_mut(|node, _| {
+            let Some(fence) = node.cast::<CodeFence>() else { return };
+
+            let language = get_language(&fence.info);
+            let Some(mut result) = handlers.get(&language).iter().find_map(|(_, f)| f(fence, md)) else { return };
+
+            result.srcmap = node.srcmap;
+            let mut attrs = std::mem::take(&mut node.attrs);
+            attrs.append(&mut result.attrs);
+            result.attrs = attrs;
+            if result.ext.is_empty() {
+                result.ext = std::mem::take(&mut node.ext);
+            }
+
+            *node = result;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::inline::Text;
+    use crate::plugins::cmark;
+
+    use super::*;
+
+    fn first(_: &CodeFence, _: &MarkdownIt) -> Option<Node> {
+        Some(Node::new(Text { content: "first\n".into() }))
+    }
+
+    fn second(_: &CodeFence, _: &MarkdownIt) -> Option<Node> {
+        Some(Node::new(Text { content: "second\n".into() }))
+    }
+
+    fn decline(_: &CodeFence, _: &MarkdownIt) -> Option<Node> {
+        None
+    }
+
+    fn render(md: &MarkdownIt, src: &str) -> String {
+        md.parse(src).render(&Default::default())
+    }
+
+    #[test]
+    fn priority() {
+        let md = &mut MarkdownIt::new();
+        cmark::add(md);
+        add(md, "x", first);
+        add(md, "x", second);
+        assert_eq!(render(md, "```x\n```"), "first\n");
+
+        add_with_priority(md, "x", 10, second);
+        assert_eq!(render(md, "```x\n```"), "second\n");
+    }
+
+    #[test]
+    fn decline_falls_through() {
+        let md = &mut MarkdownIt::new();
+        cmark::add(md);
+        add_with_priority(md, "x", 10, decline);
+        add(md, "x", first);
+        add(md, "y", decline);
+        assert_eq!(render(md, "```x\n```"), "first\n");
+        assert_eq!(render(md, "```y\na\n```"), "<pre><code class=\"language-y\">a\n</code></pre>\n");
+        assert_eq!(render(md, "```z\na\n```"), "<pre><code class=\"language-z\">a\n</code></pre>\n");
+    }
+
+    #[test]
+    fn remove_handlers() {
+        let md = &mut MarkdownIt::new();
+        cmark::add(md);
+        add(md, "x", first);
+        remove(md, "x");
+        assert_eq!(render(md, "```x\n```"), "<pre><code class=\"language-x\"></code></pre>\n");
+    }
+
+    #[test]
+    fn keeps_srcmap() {
+        let md = &mut MarkdownIt::new();
+        cmark::add(md);
+        add(md, "x", first);
+
+        let ast = md.parse("> ```x\n> ```");
+        let node = &ast.children[0].children[0];
+        assert!(node.is::<Text>());
+        assert_eq!(node.srcmap.map(|m| m.get_byte_offsets()), Some((2, 12)));
+    }
+}
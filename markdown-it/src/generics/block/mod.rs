@@ -0,0 +1,2 @@
+//! Generic block-level structures.
+pub mod fence_handler;
@@ -13,21 +13,22 @@
 //!
 //! ```rust
 //! use markdown_it::generics::inline::code_pair;
+//! use markdown_it::parser::extset::RenderOptionsExtSet;
 //! use markdown_it::{MarkdownIt, Node, NodeValue, Renderer};
 //!
 //! #[derive(Debug)]
 //! struct Ferris;
 //! impl NodeValue for Ferris {
-//!     fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+//!     fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
 //!         fmt.text("🦀");
-//!         fmt.contents(&node.children);
+//!         fmt.contents(&node.children, options);
 //!         fmt.text("🦀");
 //!     }
 //! }
 //!
 //! let md = &mut MarkdownIt::new();
 //! code_pair::add_with::<'%'>(md, |_| Node::new(Ferris));
-//! let html = md.parse("hello %world%").render();
+//! let html = md.parse("hello %world%").render(&Default::default());
 //! assert_eq!(html.trim(), "hello 🦀world🦀");
 //! ```
 //!
@@ -24,14 +24,15 @@
 //!
 //! ```rust
 //! use markdown_it::generics::inline::emph_pair;
+//! use markdown_it::parser::extset::RenderOptionsExtSet;
 //! use markdown_it::{MarkdownIt, Node, NodeValue, Renderer};
 //!
 //! #[derive(Debug)]
 //! struct Superscript;
 //! impl NodeValue for Superscript {
-//!     fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+//!     fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
 //!         fmt.open("sup", &node.attrs);
-//!         fmt.contents(&node.children);
+//!         fmt.contents(&node.children, options);
 //!         fmt.close("sup");
 //!     }
 //! }
@@ -39,7 +40,7 @@
 //! let md = &mut MarkdownIt::new();
 //! emph_pair::add_with::<'^', 1, true>(md, || Node::new(Superscript));
 //!
-//! let html = md.parse("e^iπ^+1=0").render();
+//! let html = md.parse("e^iπ^+1=0").render(&Default::default());
 //! assert_eq!(html.trim(), "e<sup>iπ</sup>+1=0");
 //! ```
 //!
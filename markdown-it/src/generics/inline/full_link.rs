@@ -141,11 +141,11 @@ struct LinkLabelScanCache(HashMap<(usize, bool), Option<usize>>);
 impl InlineRootExt for LinkLabelScanCache {}
 
 
-// Parse link label
-//
-// this function assumes that first character ("[") already matches;
-// returns the end of the label
-fn parse_link_label(state: &mut InlineState, start: usize, enable_nested: bool) -> Option<usize> {
+/// Parse link label
+///
+/// This function assumes that first character (`[`) already matches;
+/// returns the position of closing `]`.
+pub fn parse_link_label(state: &mut InlineState, start: usize, enable_nested: bool) -> Option<usize> {
     let cache = state.inline_ext.get_or_insert_default::<LinkLabelScanCache>();
     if let Some(&cached) = cache.0.get(&(start, enable_nested)) {
         return cached;
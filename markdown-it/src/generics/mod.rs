@@ -9,4 +9,5 @@
 //! `=this=` or `++that++`, you only need to specify a character marker
 //! and a renderer function, these rules will figure out the rest.
 //!
+pub mod block;
 pub mod inline;
@@ -1,4 +1,7 @@
+use std::ops::Range;
+
 use crate::parser::core::{CoreRule, Root};
+use crate::parser::extset::RootExt;
 use crate::{MarkdownIt, Node};
 
 pub fn add(md: &mut MarkdownIt) {
@@ -6,6 +9,11 @@ pub fn add(md: &mut MarkdownIt) {
         .before_all();
 }
 
+#[derive(Debug)]
+/// Byte range of the source to parse, set by incremental parsing.
+pub(crate) struct BlockParseRange(pub Range<usize>);
+impl RootExt for BlockParseRange {}
+
 pub struct BlockParserRule;
 impl CoreRule for BlockParserRule {
     fn run(root: &mut Node, md: &MarkdownIt) {
@@ -14,7 +22,10 @@ impl CoreRule for BlockParserRule {
         let source = std::mem::take(&mut data.content);
         let mut ext = std::mem::take(&mut data.ext);
 
-        node = md.block.parse(source.as_str(), node, md, &mut ext);
+        node = match ext.remove::<BlockParseRange>() {
+            Some(BlockParseRange(range)) => md.block.parse_range(source.as_str(), range, node, md, &mut ext),
+            None => md.block.parse(source.as_str(), node, md, &mut ext),
+        };
         let data = node.cast_mut::<Root>().unwrap();
         data.content = source;
         data.ext = ext;
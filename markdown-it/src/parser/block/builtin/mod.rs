@@ -3,6 +3,7 @@ use crate::MarkdownIt;
 pub(super) mod block_parser;
 
 pub use block_parser::BlockParserRule;
+pub(crate) use block_parser::BlockParseRange;
 
 pub fn add(md: &mut MarkdownIt) {
     block_parser::add(md);
@@ -8,6 +8,8 @@ pub use rule::*;
 #[doc(hidden)]
 pub mod builtin;
 
+use std::ops::Range;
+
 use crate::common::ruler::Ruler;
 use crate::common::TypeKey;
 use crate::parser::extset::RootExtSet;
@@ -112,6 +114,17 @@ impl BlockParser {
         state.node
     }
 
+    /// Process lines of input string from the one containing byte `range.start`
+    /// to the one containing byte `range.end - 1`, used for incremental parsing
+    ///
+    pub fn parse_range(&self, src: &str, range: Range<usize>, node: Node, md: &MarkdownIt, root_ext: &mut RootExtSet) -> Node {
+        let mut state = BlockState::new(src, md, root_ext, node);
+        state.line = state.line_offsets.partition_point(|l| l.line_start <= range.start).saturating_sub(1);
+        state.line_max = state.line_offsets.partition_point(|l| l.line_start < range.end);
+        self.tokenize(&mut state);
+        state.node
+    }
+
     pub fn add_rule<T: BlockRule>(&mut self) -> RuleBuilder<RuleFns> {
         let item = self.ruler.add(TypeKey::of::<T>(), (T::check, T::run));
         RuleBuilder::new(item)
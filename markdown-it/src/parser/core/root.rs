@@ -1,6 +1,4 @@
-use std::collections::HashMap;
-
-use crate::parser::extset::RootExtSet;
+use crate::parser::extset::{RenderOptionsExtSet, RootExtSet};
 use crate::{Node, NodeValue, Renderer};
 
 #[derive(Debug)]
@@ -17,7 +15,7 @@ impl Root {
 }
 
 impl NodeValue for Root {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &HashMap<String, String>) {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
         fmt.contents(&node.children, options);
     }
 }
@@ -30,6 +30,12 @@ pub trait RenderExt : Debug + Downcast + Send + Sync {}
 impl_downcast!(RenderExt);
 extension_set!(RenderExtSet, RenderExt);
 
+/// Extension set member for render options, passed by the caller
+/// to every node rendered (only readable while rendering).
+pub trait RenderOptionsExt : Debug + Downcast + Send + Sync {}
+impl_downcast!(RenderOptionsExt);
+extension_set!(RenderOptionsExtSet, RenderOptionsExt);
+
 // see https://github.com/malobre/erased_set for inspiration and API
 // see https://lucumr.pocoo.org/2022/1/7/as-any-hack/ for additional impl details
 macro_rules! extension_set {
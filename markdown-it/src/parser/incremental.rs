@@ -0,0 +1,363 @@
+use std::ops::Range;
+
+use crate::common::sourcemap::SourcePos;
+use crate::parser::block::builtin::BlockParseRange;
+use crate::parser::core::Root;
+use crate::plugins::cmark::block::heading::ATXHeading;
+use crate::plugins::cmark::block::lheading::SetextHeader;
+use crate::plugins::cmark::block::reference::Definition;
+use crate::plugins::cmark::inline::image::Image;
+use crate::plugins::cmark::inline::link::Link;
+use crate::plugins::extra::base_url::BaseUrlOptions;
+use crate::plugins::gfm::heading_anchors::HeadingAnchorOptions;
+use crate::plugins::jotdown::katex::block::MathBlock;
+use crate::plugins::jotdown::katex::equations::{EquationLabels, EquationNumber, EquationRef};
+use crate::plugins::jotdown::katex::inline::InlineMath;
+use crate::plugins::jotdown::katex::PreparedTex;
+use crate::plugins::pandoc::footnote::definitions::FootnoteDefinition;
+use crate::plugins::pandoc::footnote::inline::InlineFootnote;
+use crate::plugins::pandoc::footnote::references::FootnoteReference;
+use crate::plugins::sourcepos;
+use crate::{MarkdownIt, Node};
+
+impl MarkdownIt {
+    /// Parse document again after replacing `range` (in bytes) of its source
+    /// with `replacement`, `prev` is the tree returned by previous parse.
+    ///
+    /// This is meant for live preview in editors. Only top-level blocks
+    /// around the edit are parsed, starting two blocks before the edited
+    /// lines and stopping at the first block after them that starts at
+    /// the same place as before. The rest of the blocks are moved
+    /// from `prev`, with their source maps shifted.
+    ///
+    /// Full parse is done instead if the edit touches reference definitions,
+    /// footnotes, equation labels and references or math macro definitions,
+    /// because they affect the whole document. Same goes for links and images
+    /// with [base_url](crate::plugins::extra::base_url) (list of rewritten urls)
+    /// and headings with [heading_anchors](crate::plugins::gfm::heading_anchors)
+    /// (unique ids), core rules only see re-parsed blocks.
+    ///
+    /// ```rust
+    /// let md = &mut markdown_it::MarkdownIt::new();
+    /// markdown_it::plugins::cmark::add(md);
+    ///
+    /// let ast = md.parse("# title\n\nfirst\n\nsecond");
+    /// let ast = md.parse_incremental(ast, 10..14, "irst *para*");
+    ///
+    /// assert_eq!(ast.render(&Default::default()), md.parse("# title\n\nfirst *para*\n\nsecond").render(&Default::default()));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If `prev` isn't a root node, or `range` isn't a valid range
+    /// of its source (same as [String::replace_range]).
+    pub fn parse_incremental(&self, mut prev: Node, range: Range<usize>, replacement: &str) -> Node {
+        let data = prev.cast_mut::<Root>().expect("previous tree must start with a root node");
+        let mut source = std::mem::take(&mut data.content);
+        let mut ext = std::mem::take(&mut data.ext);
+
+        // lines touched by the edit, in old source
+        let line_start = source[..range.start].rfind(['\n', '\r']).map_or(0, |i| i + 1);
+        let line_end = source[range.end..].find(['\n', '\r']).map_or(source.len(), |i| range.end + i);
+        let delta = replacement.len() as isize - range.len() as isize;
+        source.replace_range(range, replacement);
+
+        let mut children = std::mem::take(&mut prev.children);
+        if children.iter().any(|node| node.srcmap.is_none()) {
+            return self.parse(&source);
+        }
+        let spans = children.iter().map(|node| node.srcmap.unwrap().get_byte_offsets()).collect::<Vec<_>>();
+
+        // edit may join the line with blocks above it (lazy continuation,
+        // setext underline, etc.), so two blocks before it are parsed again
+        let first = spans.iter().take_while(|(_, end)| *end < line_start).count().saturating_sub(2);
+        // parsing stops at this block if it's found at the same place
+        let next = spans.iter().position(|(start, _)| *start > line_end);
+
+        if first == 0 && next.is_none() {
+            return self.parse(&source);
+        }
+
+        let mut suffix = match next {
+            Some(next) => children.split_off(next),
+            None => Vec::new(),
+        };
+        let removed = children.split_off(first);
+        let prefix = children;
+
+        if has_global_nodes(self, &removed) {
+            return self.parse(&source);
+        }
+
+        let parse_start = if first == 0 { 0 } else { spans[first].0 };
+        let parse_end = match next {
+            // include whole next block, so blocks before it are terminated
+            // the same way as with the full source
+            Some(next) => spans[next].1.saturating_add_signed(delta),
+            None => source.len(),
+        };
+
+        // labels can't change without full parse, but the rule
+        // numbering equations only sees re-parsed blocks
+        let labels = ext.remove::<EquationLabels>();
+
+        let len = source.len();
+        let mut root = Node::new(Root { content: source, ext });
+        root.srcmap = Some(SourcePos::new(0, len));
+        root.cast_mut::<Root>().unwrap().ext.insert(BlockParseRange(parse_start..parse_end));
+        self.run_rules(&mut root);
+
+        let mut blocks = std::mem::take(&mut root.children);
+        let data = root.cast_mut::<Root>().unwrap();
+
+        if let Some(next) = next {
+            let start = spans[next].0.saturating_add_signed(delta);
+            let Some(idx) = blocks.iter().position(|node| node.srcmap.map(|map| map.get_byte_offsets().0) == Some(start)) else {
+                // edit changed where blocks end (e.g. opened a code fence),
+                // everything after it may be different
+                return self.parse(&data.content);
+            };
+            blocks.truncate(idx);
+
+            for node in suffix.iter_mut() {
+                shift_srcmap(node, delta);
+            }
+            sourcepos::update_moved(&mut suffix, &data.content);
+        }
+
+        if has_global_nodes(self, &blocks) {
+            return self.parse(&data.content);
+        }
+
+        if let Some(labels) = labels {
+            data.ext.insert(labels);
+        }
+
+        root.children = prefix;
+        root.children.append(&mut blocks);
+        root.children.append(&mut suffix);
+        root
+    }
+}
+
+// reference definitions, footnotes, equation labels and math macros
+// change other blocks of the document; links and headings are collected
+// across the whole document by base url and heading anchor rules
+fn has_global_nodes(md: &MarkdownIt, nodes: &[Node]) -> bool {
+    let base_url = md.ext.contains::<BaseUrlOptions>();
+    let anchors = md.ext.contains::<HeadingAnchorOptions>();
+
+    let mut found = false;
+    for node in nodes {
+        node.walk(|node, _| {
+            found |= node.is::<Definition>() ||
+                node.is::<FootnoteDefinition>() ||
+                node.is::<FootnoteReference>() ||
+                node.is::<InlineFootnote>() ||
+                node.is::<EquationRef>() ||
+                has_global_math(node) ||
+                base_url && (node.is::<Link>() || node.is::<Image>()) ||
+                anchors && (node.is::<ATXHeading>() || node.is::<SetextHeader>());
+        });
+    }
+    found
+}
+
+fn has_global_math(node: &Node) -> bool {
+    const COMMANDS: [&str; 6] = ["\\label", "\\eqref", "\\def", "\\newcommand", "\\renewcommand", "\\providecommand"];
+
+    let content = if let Some(data) = node.cast::<MathBlock>() {
+        &data.content
+    } else if let Some(data) = node.cast::<InlineMath>() {
+        &data.content
+    } else {
+        return false;
+    };
+
+    // labels and references may also come from macros, so check expanded tex too
+    node.ext.contains::<EquationNumber>() ||
+        [content.as_str(), node.ext.get::<PreparedTex>().map_or("", |tex| tex.0.as_str())].iter()
+            .any(|tex| COMMANDS.iter().any(|command| tex.contains(command)))
+}
+
+fn shift_srcmap(node: &mut Node, delta: isize) {
+    node.walk_mut(|node, _| {
+        if let Some(map) = node.srcmap {
+            let (start, end) = map.get_byte_offsets();
+            node.srcmap = Some(SourcePos::new(start.saturating_add_signed(delta), end.saturating_add_signed(delta)));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::parser::core::{CoreRule, Root};
+    use crate::parser::extset::MarkdownItExt;
+    use crate::{MarkdownIt, Node};
+
+    fn parser() -> MarkdownIt {
+        let mut md = MarkdownIt::new();
+        crate::plugins::cmark::add(&mut md);
+        crate::plugins::extra::add(&mut md);
+        crate::plugins::html::add(&mut md);
+        crate::plugins::sourcepos::add(&mut md);
+        crate::plugins::pandoc::footnote::add(&mut md);
+        crate::plugins::jotdown::katex::add(&mut md);
+        md
+    }
+
+    fn dump(node: &Node) -> Vec<String> {
+        let mut result = vec![node.cast::<Root>().unwrap().content.clone()];
+        node.walk(|node, depth| result.push(format!("{}{} {:?}", " ".repeat(depth as usize), node.name(), node.srcmap)));
+        result.push(node.render(&Default::default()));
+        result
+    }
+
+    const DOC: &str = "# Title\n\nfirst paragraph\nsecond line\n\n- item\n- item 2\n\n  nested\n\n> quote\n\n```rust\nfn main() {}\n```\n\nlast [link][ref]\n\n[ref]: /url\n";
+    const MATH: &str = "$$a \\label{x}$$\n\ntext\n\n1\n\n2\n\n$$b \\label{y}$$\n\n3\n\n4\n\nsee \\eqref{y}\n\n5\n\n6\n\n\
+        $$\\newcommand{\\R}{\\mathbb{R}}$$\n\n7\n\n8\n\n$\\R$\n\n9\n\n10\n\n$$\\def\\l{\\label{z}} c \\l$$\n\n11\n\n12\n\nend\n";
+
+    #[rstest]
+    #[case(DOC, "first", "1st")]
+    #[case(DOC, "second line", "second *line*\nthird line")]
+    #[case(DOC, "\n\n- item\n", "\n- item\n")]
+    #[case(DOC, "item 2", "item 2\n\n- item 3")]
+    #[case(DOC, "nested", "  nested\n")]
+    #[case(DOC, "> quote\n", "> quote\nlazy\n")]
+    #[case(DOC, "```rust", "~~~rust")]
+    #[case(DOC, "```\n\nlast", "```\n\n```\nlast")]
+    #[case(DOC, "second line\n", "second line\n===\n")]
+    #[case(DOC, "# Title", "Title\n-----")]
+    #[case(DOC, "/url", "/other")]
+    #[case(DOC, "\n[ref]: /url\n", "")]
+    #[case(DOC, "last", "[ref]: /new\n\nlast")]
+    #[case(DOC, "last", "last[^1]\n\n[^1]: note")]
+    #[case(DOC, "first paragraph", "| a | b |\n|---|---|\n| c | d |")]
+    #[case(DOC, "quote", "quote ▶ юникод")]
+    #[case("a\n\nb\n\nc\n\nd\n\ne\n\nf", "c", "")]
+    #[case("a\n\nb\n\nc\n\nd\n\ne\n\nf", "\n\nd", "d")]
+    #[case("a\n\nb\n\nc\n\nd\n\ne\n\nf", "e\n\nf", "e\n\nf\n\ng")]
+    #[case("a\n\nb\n\nc\n\nd\n\ne\n\nf", "a", "<div>\n")]
+    #[case("a\n\nb\n\nc\n\nd\n\ne\n\nf", "c\n\nd", "<!--\nc\n\nd")]
+    #[case("a\n\nb\n\nc\n\nd\n\ne\n\nf", "a\n\nb\n\nc\n\nd\n\ne\n\nf", "")]
+    #[case("", "", "hello")]
+    #[case(MATH, "b \\label{y}", "b \\label{w}")]
+    #[case(MATH, "a \\label{x}", "a")]
+    #[case(MATH, "text", "$$t \\label{t}$$")]
+    #[case(MATH, "text", "$\\eqref{y}$")]
+    #[case(MATH, "see \\eqref{y}", "see")]
+    #[case(MATH, "\\mathbb{R}", "\\mathbb{C}")]
+    #[case(MATH, "$\\R$", "$\\def\\R{r}$")]
+    #[case(MATH, "c \\l", "c")]
+    #[case(MATH, "end", "$\\l$")]
+    #[case(MATH, "text", "$\\R$")]
+    fn matches_full_parse(#[case] source: &str, #[case] find: &str, #[case] replacement: &str) {
+        let md = parser();
+        let start = source.find(find).unwrap_or(source.len());
+        let range = start..(start + find.len()).min(source.len());
+
+        let mut expected = source.to_owned();
+        expected.replace_range(range.clone(), replacement);
+
+        let ast = md.parse_incremental(md.parse(source), range, replacement);
+        assert_eq!(dump(&ast), dump(&md.parse(&expected)));
+    }
+
+    #[test]
+    fn sequential_edits() {
+        let md = parser();
+        let mut source = String::new();
+        let mut ast = md.parse(&source);
+
+        for ch in "# Notes\n\nSome *text*\nand more.\n\n1. one\n2. two\n\n```\ncode\n```\n\ndone".chars() {
+            let pos = source.len();
+            source.push(ch);
+            ast = md.parse_incremental(ast, pos..pos, ch.encode_utf8(&mut [0; 4]));
+            assert_eq!(dump(&ast), dump(&md.parse(&source)));
+        }
+
+        while !source.is_empty() {
+            let pos = source.len() / 2;
+            source.remove(pos);
+            ast = md.parse_incremental(ast, pos..pos + 1, "");
+            assert_eq!(dump(&ast), dump(&md.parse(&source)));
+        }
+    }
+
+    #[test]
+    fn keeps_equation_labels() {
+        use crate::plugins::jotdown::katex::equations::EquationLabels;
+
+        let md = parser();
+        let start = MATH.find("text").unwrap();
+        let ast = md.parse_incremental(md.parse(MATH), start..start + 4, "other");
+        let labels = &ast.cast::<Root>().unwrap().ext.get::<EquationLabels>().unwrap().labels;
+        assert_eq!(labels.len(), 3);
+        assert_eq!(labels.get("y"), Some(&2));
+    }
+
+    #[rstest]
+    #[case("first", "1st")]
+    #[case("![a](/n/a.png)", "![b](/n/b.png)")]
+    #[case("second\n", "second [link](x.md)\n")]
+    #[case("# Intro\n", "")]
+    #[case("third", "# Title")]
+    #[case("last", "# Intro")]
+    fn matches_full_parse_root_exts(#[case] find: &str, #[case] replacement: &str) {
+        use crate::plugins::extra::base_url::{self, RewrittenUrls};
+
+        const SOURCE: &str = "# Title\n\n![a](/n/a.png)\n\n# Intro\n\nfirst\n\nsecond\n\nthird\n\n[doc](doc.md)\n\n# Title\n\nlast\n";
+
+        let md = &mut parser();
+        base_url::add_with_options(md, base_url::BaseUrlOptions { base: "/notes/".into(), ..Default::default() });
+        crate::plugins::gfm::heading_anchors::add(md);
+
+        let start = SOURCE.find(find).unwrap();
+        let range = start..start + find.len();
+        let mut expected = SOURCE.to_owned();
+        expected.replace_range(range.clone(), replacement);
+
+        let urls = |ast: &Node| ast.cast::<Root>().unwrap().ext.get::<RewrittenUrls>().map(|ext| ext.urls.clone());
+        let ast = md.parse_incremental(md.parse(SOURCE), range, replacement);
+        let full = md.parse(&expected);
+        assert_eq!(urls(&ast), urls(&full));
+        assert_eq!(dump(&ast), dump(&full));
+    }
+
+    #[derive(Debug)]
+    struct Generation(u32);
+    impl MarkdownItExt for Generation {}
+
+    struct GenerationRule;
+    impl CoreRule for GenerationRule {
+        fn run(root: &mut Node, md: &MarkdownIt) {
+            let generation = md.ext.get::<Generation>().unwrap().0;
+            for node in root.children.iter_mut() {
+                node.attrs.push(("data-gen", generation.to_string()));
+            }
+        }
+    }
+
+    #[test]
+    fn reuses_blocks() {
+        let md = &mut MarkdownIt::new();
+        crate::plugins::cmark::add(md);
+        md.add_rule::<GenerationRule>();
+
+        md.ext.insert(Generation(1));
+        let ast = md.parse("a\n\nb\n\nc\n\nd\n\ne\n\nf\n\ng");
+        md.ext.insert(Generation(2));
+        let ast = md.parse_incremental(ast, 9..10, "x\nx");
+
+        assert_eq!(ast.render(&Default::default()), "\
+            <p data-gen=\"1\">a</p>\n\
+            <p data-gen=\"2\">b</p>\n\
+            <p data-gen=\"2\">c</p>\n\
+            <p data-gen=\"2\">x\nx</p>\n\
+            <p data-gen=\"1\">e</p>\n\
+            <p data-gen=\"1\">f</p>\n\
+            <p data-gen=\"1\">g</p>\n");
+    }
+}
@@ -1,26 +1,33 @@
 //! Skip text characters for text token, place those to pending buffer
 //! and increment current pos
 //!
-use std::collections::HashMap;
 
 use regex::{self, Regex};
 
+use crate::parser::extset::RenderOptionsExtSet;
 use crate::parser::inline::{InlineRule, InlineState};
 use crate::{MarkdownIt, Node, NodeValue, Renderer};
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Plain text AST node.
 pub struct Text {
     pub content: String,
 }
 
 impl NodeValue for Text {
-    fn render(&self, _: &Node, fmt: &mut dyn Renderer, _options: &HashMap<String, String>) {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, _: &Node, fmt: &mut dyn Renderer, _options: &RenderOptionsExtSet) {
         fmt.text(&self.content);
     }
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 /// Escaped text AST node (backslash escapes and entities).
 pub struct TextSpecial {
     pub content: String,
@@ -29,7 +36,12 @@ pub struct TextSpecial {
 }
 
 impl NodeValue for TextSpecial {
-    fn render(&self, _: &Node, fmt: &mut dyn Renderer, _options: &HashMap<String, String>) {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, _: &Node, fmt: &mut dyn Renderer, _options: &RenderOptionsExtSet) {
         fmt.text(&self.content);
     }
 }
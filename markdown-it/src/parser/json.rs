@@ -0,0 +1,709 @@
+//! JSON representation of the syntax tree, requires `serde` feature.
+//!
+//! Every node is written as an object with its type name, source position,
+//! attributes, node-specific data and children (empty ones are omitted):
+//!
+//! ```json
+//! {
+//!   "type": "ATXHeading",
+//!   "srcmap": { "start": 0, "end": 7, "start_line": 1, "start_column": 1, "end_line": 1, "end_column": 7 },
+//!   "attrs": [["id", "title"]],
+//!   "data": { "level": 1 },
+//!   "children": [{ "type": "Text", "data": { "content": "Title" } }]
+//! }
+//! ```
+//!
+//! Type name is the name of [NodeValue](crate::NodeValue) struct without
+//! module path, data is what [NodeValue::to_json_data](crate::NodeValue::to_json_data)
+//! returns. Lines and columns are only available if source is known, which is
+//! the case for the root node returned by the parser.
+//!
+//! ```rust
+//! let md = &mut markdown_it::MarkdownIt::new();
+//! markdown_it::plugins::cmark::add(md);
+//!
+//! let json = markdown_it::parser::json::to_value(&md.parse("# hello"));
+//! assert_eq!(json["children"][0]["type"], "ATXHeading");
+//! assert_eq!(json["children"][0]["data"]["level"], 1);
+//! assert_eq!(json["children"][0]["srcmap"]["end_column"], 7);
+//! ```
+//!
+//! Trees can be read back with [NodeRegistry], which maps type names to node
+//! constructors. Built-in nodes are registered by default, plugin nodes deriving
+//! `serde::Deserialize` are added with [NodeRegistry::add]. Raw html nodes
+//! ([HtmlBlock](crate::plugins::html::html_block::HtmlBlock),
+//! [HtmlInline](crate::plugins::html::html_inline::HtmlInline)) are not registered by default, add them only
+//! if input is trusted. Attribute names are limited to the names parser
+//! plugins produce, others are added with [NodeRegistry::add_attribute]:
+//!
+//! ```rust
+//! use markdown_it::parser::json::NodeRegistry;
+//!
+//! let json = r#"{ "type": "Root", "children": [
+//!     { "type": "ATXHeading", "data": { "level": 2 }, "children": [
+//!         { "type": "Text", "data": { "content": "hello" } }
+//!     ] }
+//! ] }"#;
+//!
+//! let node = NodeRegistry::new().deserialize(json).unwrap();
+//! assert_eq!(node.render(&Default::default()), "<h2>hello</h2>\n");
+//!
+//! let error = NodeRegistry::new().deserialize(r#"{ "type": "Foo" }"#).unwrap_err();
+//! assert_eq!(error.to_string(), "unknown node type `Foo` at /");
+//! ```
+//!
+//! Trees from untrusted sources should be read with [NodeRegistry::deserialize_with],
+//! which applies the same checks to urls and raw html as the parser does:
+//!
+//! ```rust
+//! use markdown_it::parser::json::NodeRegistry;
+//!
+//! let md = &mut markdown_it::MarkdownIt::new();
+//! markdown_it::plugins::cmark::add(md);
+//!
+//! let json = r#"{ "type": "Root", "children": [
+//!     { "type": "Link", "data": { "url": "javascript:alert(1)" }, "children": [
+//!         { "type": "Text", "data": { "content": "hello" } }
+//!     ] }
+//! ] }"#;
+//!
+//! let node = NodeRegistry::new().deserialize_with(json, md).unwrap();
+//! assert_eq!(node.render(&Default::default()), "hello");
+//! ```
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::de::{DeserializeOwned, Error as _};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::common::sourcemap::{SourcePos, SourceWithLineStarts};
+use crate::parser::core::Root;
+use crate::parser::inline::{Text, TextSpecial};
+use crate::plugins::cmark::block::blockquote::Blockquote;
+use crate::plugins::cmark::block::code::CodeBlock;
+use crate::plugins::cmark::block::fence::CodeFence;
+use crate::plugins::cmark::block::heading::ATXHeading;
+use crate::plugins::cmark::block::hr::ThematicBreak;
+use crate::plugins::cmark::block::lheading::SetextHeader;
+use crate::plugins::cmark::block::list::{BulletList, ListItem, OrderedList};
+use crate::plugins::cmark::block::paragraph::Paragraph;
+use crate::plugins::cmark::block::reference::Definition;
+use crate::plugins::cmark::inline::autolink::Autolink;
+use crate::plugins::cmark::inline::backticks::CodeInline;
+use crate::plugins::cmark::inline::emphasis::{Em, Strong};
+use crate::plugins::cmark::inline::image::Image;
+use crate::plugins::cmark::inline::link::Link;
+use crate::parser::core::CoreRule;
+use crate::plugins::cmark::inline::newline::{Hardbreak, Softbreak};
+use crate::plugins::extra::strikethrough::Strikethrough;
+use crate::plugins::extra::tables::{Table, TableBody, TableCell, TableHead, TableRow};
+use crate::plugins::gfm::heading_anchors::HeadingAnchor;
+use crate::plugins::gfm::tasklist::TodoCheckbox;
+use crate::plugins::jotdown::diagrams::Diagram;
+use crate::plugins::jotdown::katex::block::MathBlock;
+use crate::plugins::jotdown::katex::equations::EquationRef;
+use crate::plugins::jotdown::katex::inline::InlineMath;
+use crate::plugins::html::sanitize::SanitizeRule;
+use crate::plugins::pandoc::attrs::{BracketedSpan, DEFAULT_ALLOWED, URL_ATTRIBUTES};
+use crate::plugins::pandoc::deflist::{DefinitionDescription, DefinitionList, DefinitionTerm};
+use crate::plugins::pandoc::footnote::back_refs::FootnoteRefAnchor;
+use crate::plugins::pandoc::footnote::collect::FootnotesContainerNode;
+use crate::plugins::pandoc::footnote::definitions::FootnoteDefinition;
+use crate::plugins::pandoc::footnote::inline::InlineFootnote;
+use crate::plugins::pandoc::footnote::references::FootnoteReference;
+use crate::{MarkdownIt, Node, NodeValue};
+
+/// Serialize node and its children, lines and columns are calculated
+/// if `node` is the root of the document.
+pub fn to_value(node: &Node) -> Value {
+    match node.cast::<Root>() {
+        Some(root) => serialize(node, Some(&SourceWithLineStarts::new(&root.content))),
+        None => serialize(node, None),
+    }
+}
+
+/// Serialize node and its children, calculating lines and columns
+/// from the document `source`.
+pub fn to_value_with_source(node: &Node, source: &str) -> Value {
+    serialize(node, Some(&SourceWithLineStarts::new(source)))
+}
+
+/// Short type name of the node used in JSON, e.g. `Paragraph`
+/// (`std::any::type_name` without module paths).
+pub fn type_name(node: &Node) -> String {
+    short_name(node.name())
+}
+
+fn short_name(name: &str) -> String {
+    let mut result = String::new();
+    let mut rest = name;
+
+    // strip paths from generic arguments as well, `a::B<c::D>` -> `B<D>`
+    while let Some(pos) = rest.find(['<', '>', ',']) {
+        let (path, tail) = rest.split_at(pos + 1);
+        result.push_str(strip_path(path));
+        rest = tail;
+    }
+    result.push_str(strip_path(rest));
+    result
+}
+
+fn strip_path(path: &str) -> &str {
+    let path = path.trim_start();
+    &path[path.rfind("::").map(|x| x + 2).unwrap_or_default()..]
+}
+
+fn serialize(node: &Node, source: Option<&SourceWithLineStarts>) -> Value {
+    let mut result = Map::new();
+    result.insert("type".into(), type_name(node).into());
+
+    if let Some(srcmap) = node.srcmap {
+        let (start, end) = srcmap.get_byte_offsets();
+        let mut map = Map::new();
+        map.insert("start".into(), start.into());
+        map.insert("end".into(), end.into());
+        if let Some(source) = source {
+            let ((start_line, start_column), (end_line, end_column)) = srcmap.get_positions(source);
+            map.insert("start_line".into(), start_line.into());
+            map.insert("start_column".into(), start_column.into());
+            map.insert("end_line".into(), end_line.into());
+            map.insert("end_column".into(), end_column.into());
+        }
+        result.insert("srcmap".into(), map.into());
+    }
+
+    if !node.attrs.is_empty() {
+        let attrs = node.attrs.iter().map(|(name, value)| Value::from(vec![*name, value.as_str()]));
+        result.insert("attrs".into(), attrs.collect());
+    }
+
+    if let Some(data) = node.node_value.to_json_data() {
+        result.insert("data".into(), data);
+    }
+
+    if !node.children.is_empty() {
+        let children = node.children.iter().map(|child| {
+            stacker::maybe_grow(64 * 1024, 1024 * 1024, || serialize(child, source))
+        });
+        result.insert("children".into(), children.collect());
+    }
+
+    result.into()
+}
+
+/// Function creating a node from its JSON data (`null` if there is none),
+/// srcmap, attributes and children are filled in later.
+pub type NodeConstructor = fn (data: Value) -> Result<Node, serde_json::Error>;
+
+/// Attribute names put on nodes by built-in plugins, in addition to
+/// [DEFAULT_ALLOWED] names of attribute blocks.
+pub const NODE_ATTRIBUTES: &[&str] = &["class", "id", "data-sourcepos", "data-gen"];
+
+#[derive(Debug)]
+/// Registry of node types and attribute names that can be read from JSON.
+pub struct NodeRegistry {
+    constructors: HashMap<String, NodeConstructor>,
+    attributes: Vec<&'static str>,
+}
+
+impl NodeRegistry {
+    /// Create registry with all built-in node types.
+    pub fn new() -> Self {
+        let mut result = Self {
+            constructors: HashMap::new(),
+            attributes: NODE_ATTRIBUTES.iter().chain(DEFAULT_ALLOWED).copied().collect(),
+        };
+
+        result.insert("Root", |_| Ok(Node::new(Root::new(String::new()))));
+        result.add::<Text>();
+        result.insert("TextSpecial", |data| {
+            #[derive(Deserialize)]
+            struct Data { content: String, markup: String, info: String }
+
+            let data = serde_json::from_value::<Data>(data)?;
+            let info = match data.info.as_str() {
+                "autolink" => "autolink",
+                "entity" => "entity",
+                "escape" => "escape",
+                other => return Err(serde_json::Error::custom(format!("unknown info `{other}`"))),
+            };
+            Ok(Node::new(TextSpecial { content: data.content, markup: data.markup, info }))
+        });
+
+        // commonmark
+        result.insert("ATXHeading", |data| {
+            let value = serde_json::from_value::<ATXHeading>(data)?;
+            check_heading_level(value.level)?;
+            Ok(Node::new(value))
+        });
+        result.add::<Blockquote>();
+        result.add::<BulletList>();
+        result.add::<CodeBlock>();
+        result.insert("CodeFence", |data| {
+            #[derive(Deserialize)]
+            struct Data { info: String, marker: char, marker_len: usize, content: String, lang_prefix: String }
+
+            let data = serde_json::from_value::<Data>(data)?;
+            // custom prefixes need a custom constructor
+            let lang_prefix = match data.lang_prefix.as_str() {
+                "language-" => "language-",
+                "" => "",
+                other => return Err(serde_json::Error::custom(format!("unknown lang_prefix `{other}`"))),
+            };
+            Ok(Node::new(CodeFence {
+                info: data.info,
+                marker: data.marker,
+                marker_len: data.marker_len,
+                content: data.content,
+                lang_prefix,
+            }))
+        });
+        result.add::<Definition>();
+        result.add::<ListItem>();
+        result.add::<OrderedList>();
+        result.add::<Paragraph>();
+        result.insert("SetextHeader", |data| {
+            let value = serde_json::from_value::<SetextHeader>(data)?;
+            check_heading_level(value.level)?;
+            Ok(Node::new(value))
+        });
+        result.add::<ThematicBreak>();
+        result.add::<Autolink>();
+        result.add::<CodeInline>();
+        result.add::<Em>();
+        result.add::<Hardbreak>();
+        result.add::<Image>();
+        result.add::<Link>();
+        result.add::<Softbreak>();
+        result.add::<Strong>();
+
+        // extensions
+        result.add::<Strikethrough>();
+        result.add::<Table>();
+        result.add::<TableHead>();
+        result.add::<TableBody>();
+        result.add::<TableRow>();
+        result.add::<TableCell>();
+        #[cfg(feature = "linkify")]
+        result.add::<crate::plugins::extra::linkify::Linkified>();
+        result.add::<HeadingAnchor>();
+        result.add::<TodoCheckbox>();
+        result.add::<BracketedSpan>();
+        result.add::<DefinitionList>();
+        result.add::<DefinitionTerm>();
+        result.add::<DefinitionDescription>();
+        result.add::<FootnoteReference>();
+        result.add::<FootnoteDefinition>();
+        result.add::<FootnoteRefAnchor>();
+        result.add::<FootnotesContainerNode>();
+        result.add::<InlineFootnote>();
+        result.add::<MathBlock>();
+        result.add::<InlineMath>();
+        result.add::<EquationRef>();
+        result.add::<Diagram>();
+
+        result
+    }
+
+    /// Register node type `T`, its name is [type_name] of the node.
+    pub fn add<T: NodeValue + DeserializeOwned>(&mut self) {
+        self.insert(short_name(std::any::type_name::<T>()), |data| {
+            Ok(Node::new(serde_json::from_value::<T>(data)?))
+        });
+    }
+
+    /// Register custom constructor for node type `name`, e.g. for nodes
+    /// which can't derive `serde::Deserialize`.
+    pub fn insert(&mut self, name: impl Into<String>, f: NodeConstructor) {
+        self.constructors.insert(name.into(), f);
+    }
+
+    /// Allow attribute `name` on nodes, e.g. names added to
+    /// [AttributesOptions](crate::plugins::pandoc::attrs::AttributesOptions).
+    pub fn add_attribute(&mut self, name: &'static str) {
+        if !self.attributes.contains(&name) {
+            self.attributes.push(name);
+        }
+    }
+
+    /// Remove node type `name`, so it can no longer be read.
+    pub fn remove(&mut self, name: &str) {
+        self.constructors.remove(name);
+    }
+
+    /// Check whether node type `name` is registered.
+    pub fn contains(&self, name: &str) -> bool {
+        self.constructors.contains_key(name)
+    }
+
+    /// Read node tree from JSON string.
+    pub fn deserialize(&self, json: &str) -> Result<Node, JsonError> {
+        let value = serde_json::from_str(json).map_err(JsonError::Syntax)?;
+        self.deserialize_value(&value)
+    }
+
+    /// Read node tree from JSON value.
+    pub fn deserialize_value(&self, value: &Value) -> Result<Node, JsonError> {
+        self.read_node(value, &mut String::new())
+    }
+
+    /// Read node tree from JSON string and [validate] it with parser `md`.
+    pub fn deserialize_with(&self, json: &str, md: &MarkdownIt) -> Result<Node, JsonError> {
+        let mut node = self.deserialize(json)?;
+        validate(&mut node, md);
+        Ok(node)
+    }
+
+    fn read_node(&self, value: &Value, path: &mut String) -> Result<Node, JsonError> {
+        let invalid = |path: &str, message: String| JsonError::InvalidNode {
+            path: if path.is_empty() { "/".into() } else { path.into() },
+            message,
+        };
+
+        let Some(object) = value.as_object() else {
+            return Err(invalid(path, "node must be an object".into()));
+        };
+        let Some(name) = object.get("type").and_then(Value::as_str) else {
+            return Err(invalid(path, "node must have a string `type`".into()));
+        };
+        let Some(f) = self.constructors.get(name) else {
+            return Err(JsonError::UnknownType {
+                path: if path.is_empty() { "/".into() } else { path.clone() },
+                name: name.into(),
+            });
+        };
+
+        let data = object.get("data").cloned().unwrap_or_default();
+        let mut node = f(data).map_err(|err| invalid(path, format!("invalid `{name}` data: {err}")))?;
+
+        if let Some(srcmap) = object.get("srcmap") {
+            let offset = |key| srcmap.get(key).and_then(Value::as_u64).map(|x| x as usize);
+            let (Some(start), Some(end)) = (offset("start"), offset("end")) else {
+                return Err(invalid(path, "srcmap must have numeric `start` and `end`".into()));
+            };
+            node.srcmap = Some(SourcePos::new(start, end));
+        }
+
+        if let Some(attrs) = object.get("attrs") {
+            let attrs = Vec::<(String, String)>::deserialize(attrs)
+                .map_err(|err| invalid(path, format!("invalid attrs: {err}")))?;
+            for (name, value) in attrs {
+                let Some(name) = self.attributes.iter().find(|known| **known == name) else {
+                    return Err(invalid(path, format!("unknown attribute `{name}`")));
+                };
+                node.attrs.push((name, value));
+            }
+        }
+
+        if let Some(children) = object.get("children") {
+            let Some(children) = children.as_array() else {
+                return Err(invalid(path, "children must be an array".into()));
+            };
+            for (idx, child) in children.iter().enumerate() {
+                let len = path.len();
+                path.push_str(&format!("/children/{idx}"));
+                let child = stacker::maybe_grow(64 * 1024, 1024 * 1024, || self.read_node(child, path))?;
+                path.truncate(len);
+                node.children.push(child);
+            }
+        }
+
+        Ok(node)
+    }
+}
+
+// heading tags are looked up by level when rendering
+fn check_heading_level(level: u8) -> Result<(), serde_json::Error> {
+    if !(1..=6).contains(&level) {
+        return Err(serde_json::Error::custom(format!("heading level {level} is not in 1..=6")));
+    }
+    Ok(())
+}
+
+/// Check a tree read from JSON the way parser `md` checks its own output:
+///  - links, images, autolinks and linkified urls rejected by link formatter
+///    are replaced with their content,
+///  - equation references with rejected targets are rendered as unknown,
+///  - url attributes (e.g. `cite`) with rejected values are removed,
+///  - attributes link formatter adds to links (e.g. `rel`) are updated,
+///  - raw html is cleaned by the sanitizer if it's added to `md`.
+pub fn validate(root: &mut Node, md: &MarkdownIt) {
+    root.walk_mut(|node, _| {
+        node.attrs.retain(|(name, value)| {
+            !URL_ATTRIBUTES.contains(name) || md.link_formatter.validate_link(value).is_some()
+        });
+
+        let children = std::mem::take(&mut node.children);
+        keep_valid_links(children, md, &mut node.children);
+
+        if let Some(eqref) = node.cast_mut::<EquationRef>() {
+            if md.link_formatter.validate_link(&format!("#{}", eqref.label)).is_none() {
+                eqref.number = None;
+            }
+        }
+
+        if let Some(url) = link_url(node) {
+            for (name, value) in md.link_formatter.link_attrs(url) {
+                node.attrs.retain(|(existing, _)| *existing != name);
+                node.attrs.push((name, value));
+            }
+        }
+    });
+
+    SanitizeRule::run(root, md);
+}
+
+// url of a node rendered as `<a>`
+fn link_url(node: &Node) -> Option<&str> {
+    #[cfg(feature = "linkify")]
+    if let Some(link) = node.cast::<crate::plugins::extra::linkify::Linkified>() {
+        return Some(&link.url);
+    }
+
+    node.cast::<Link>().map(|link| link.url.as_str())
+        .or_else(|| node.cast::<Autolink>().map(|link| link.url.as_str()))
+}
+
+// move `children` to `result`, replacing invalid links and images with their content
+fn keep_valid_links(children: Vec<Node>, md: &MarkdownIt, result: &mut Vec<Node>) {
+    for mut child in children {
+        let url = link_url(&child).or_else(|| child.cast::<Image>().map(|image| image.url.as_str()));
+
+        if url.is_some_and(|url| md.link_formatter.validate_link(url).is_none()) {
+            keep_valid_links(std::mem::take(&mut child.children), md, result);
+        } else {
+            result.push(child);
+        }
+    }
+}
+
+impl Default for NodeRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+/// Error reading node tree from JSON, `path` is JSON pointer to the node.
+pub enum JsonError {
+    /// Input is not valid JSON.
+    Syntax(serde_json::Error),
+    /// Node type is not registered in [NodeRegistry].
+    UnknownType { path: String, name: String },
+    /// Node is not an object of expected format, or its data doesn't match its type.
+    InvalidNode { path: String, message: String },
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax(err) => write!(f, "invalid json: {err}"),
+            Self::UnknownType { path, name } => write!(f, "unknown node type `{name}` at {path}"),
+            Self::InvalidNode { path, message } => write!(f, "{message} at {path}"),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Syntax(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::plugins::{cmark, extra, gfm};
+    use crate::MarkdownIt;
+
+    use super::*;
+
+    fn parse(src: &str) -> Value {
+        let mut md = MarkdownIt::new();
+        cmark::add(&mut md);
+        extra::tables::add(&mut md);
+        gfm::tasklist::add(&mut md);
+        to_value(&md.parse(src))
+    }
+
+    #[test]
+    fn document() {
+        assert_eq!(parse("*a*\n\n---"), json!({
+            "type": "Root",
+            "srcmap": { "start": 0, "end": 8, "start_line": 1, "start_column": 1, "end_line": 3, "end_column": 3 },
+            "children": [
+                {
+                    "type": "Paragraph",
+                    "srcmap": { "start": 0, "end": 3, "start_line": 1, "start_column": 1, "end_line": 1, "end_column": 3 },
+                    "children": [{
+                        "type": "Em",
+                        "srcmap": { "start": 0, "end": 3, "start_line": 1, "start_column": 1, "end_line": 1, "end_column": 3 },
+                        "data": { "marker": "*" },
+                        "children": [{
+                            "type": "Text",
+                            "srcmap": { "start": 1, "end": 2, "start_line": 1, "start_column": 2, "end_line": 1, "end_column": 2 },
+                            "data": { "content": "a" },
+                        }],
+                    }],
+                },
+                {
+                    "type": "ThematicBreak",
+                    "srcmap": { "start": 5, "end": 8, "start_line": 3, "start_column": 1, "end_line": 3, "end_column": 3 },
+                    "data": { "marker": "-", "marker_len": 3 },
+                },
+            ],
+        }));
+    }
+
+    #[test]
+    fn node_data() {
+        let json = parse("[a](/url \"t\")\n\n```rust\nx\n```\n\n| a |\n|:-:|\n\n- [x] b");
+        assert_eq!(json["children"][0]["children"][0]["data"], json!({ "url": "/url", "title": "t" }));
+        assert_eq!(json["children"][1]["data"]["info"], "rust");
+        assert_eq!(json["children"][2]["data"], json!({ "alignments": ["center"] }));
+        assert_eq!(json["children"][3]["children"][0]["children"][0]["data"], json!({ "checked": true, "disabled": false }));
+    }
+
+    #[test]
+    fn subtree_without_lines() {
+        let mut md = MarkdownIt::new();
+        cmark::add(&mut md);
+        let mut root = md.parse("a\n\n`b`");
+        let mut node = root.children.pop().unwrap();
+        node.attrs.push(("class", "x".into()));
+        assert_eq!(to_value(&node), json!({
+            "type": "Paragraph",
+            "srcmap": { "start": 3, "end": 6 },
+            "attrs": [["class", "x"]],
+            "children": [{
+                "type": "CodeInline",
+                "srcmap": { "start": 3, "end": 6 },
+                "data": { "marker": "`", "marker_len": 1 },
+                "children": [{ "type": "Text", "srcmap": { "start": 4, "end": 5 }, "data": { "content": "b" } }],
+            }],
+        }));
+        assert_eq!(to_value_with_source(&node, "a\n\n`b`")["srcmap"]["start_line"], 3);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut md = MarkdownIt::new();
+        cmark::add(&mut md);
+        extra::add(&mut md);
+        gfm::add(&mut md);
+        crate::plugins::html::add(&mut md);
+        crate::plugins::pandoc::add(&mut md);
+        crate::plugins::jotdown::add(&mut md);
+        // highlighted code is rendered with parser settings, so it can't be read back
+        #[cfg(feature = "syntect")]
+        md.remove_rule::<extra::syntect::SyntectRule>();
+
+        let src = "# A {.x}\n\n*b* **c** ~~d~~ `e` &amp; \\* <i>f</i>\n\n```rust\ng\n```\n\n\
+            | h |\n|--:|\n| i |\n\n- [x] j\n\n1. [k](/l \"m\") ![n](/o)\n\n\
+            p[^1] $q$\n\n[^1]: r\n\nTerm\n: s\n\n$$t$$\n\n---\n";
+        let root = md.parse(src);
+        let json = to_value(&root).to_string();
+        let mut registry = NodeRegistry::new();
+        assert!(registry.deserialize(&json).is_err());
+        registry.add::<crate::plugins::html::html_block::HtmlBlock>();
+        registry.add::<crate::plugins::html::html_inline::HtmlInline>();
+        let node = registry.deserialize(&json).unwrap();
+        assert_eq!(node.render(&Default::default()), root.render(&Default::default()));
+        assert_eq!(to_value(&node), to_value_with_source(&root, ""));
+    }
+
+    #[rstest::rstest]
+    #[case("[1", "invalid json: EOF while parsing a list at line 1 column 2")]
+    #[case(r#"{ "type": "Root", "children": [{ "type": "Foo" }] }"#, "unknown node type `Foo` at /children/0")]
+    #[case(r#"{ "type": "Root", "children": [{ "type": "Em", "data": { "marker": 1 } }] }"#,
+        "invalid `Em` data: invalid type: integer `1`, expected a character at /children/0")]
+    #[case(r#"{ "type": "Paragraph", "attrs": [["a b", "c"]] }"#, "unknown attribute `a b` at /")]
+    #[case(r#"{ "type": "Paragraph", "attrs": [["onclick", "c"]] }"#, "unknown attribute `onclick` at /")]
+    #[case(r#"{ "type": "Paragraph", "attrs": [["style", "c"]] }"#, "unknown attribute `style` at /")]
+    #[case(r#"{ "type": "HtmlInline", "data": { "content": "<script>" } }"#, "unknown node type `HtmlInline` at /")]
+    #[case(r#"{ "type": "TextSpecial", "data": { "content": "a", "markup": "a", "info": "b" } }"#,
+        "invalid `TextSpecial` data: unknown info `b` at /")]
+    #[case(r#"{ "type": "CodeFence", "data": { "info": "", "marker": "`", "marker_len": 3, "content": "", "lang_prefix": "x" } }"#,
+        "invalid `CodeFence` data: unknown lang_prefix `x` at /")]
+    #[case(r#"{ "type": "ATXHeading", "data": { "level": 0 } }"#, "invalid `ATXHeading` data: heading level 0 is not in 1..=6 at /")]
+    #[case(r#"{ "type": "SetextHeader", "data": { "level": 7, "marker": "=" } }"#,
+        "invalid `SetextHeader` data: heading level 7 is not in 1..=6 at /")]
+    #[case(r#"{ "type": "Paragraph", "srcmap": {} }"#, "srcmap must have numeric `start` and `end` at /")]
+    #[case(r#"{ "type": "Root", "children": [{ "children": [] }] }"#, "node must have a string `type` at /children/0")]
+    fn errors(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(NodeRegistry::new().deserialize(input).unwrap_err().to_string(), expected);
+    }
+
+    #[test]
+    fn plugin_nodes() {
+        #[derive(Debug, serde::Deserialize)]
+        struct Kbd { key: String }
+        impl crate::NodeValue for Kbd {
+            fn render(&self, _: &Node, fmt: &mut dyn crate::Renderer, _: &crate::parser::extset::RenderOptionsExtSet) {
+                fmt.text(&format!("[{}]", self.key));
+            }
+        }
+
+        let json = r#"{ "type": "Kbd", "data": { "key": "Ctrl" } }"#;
+        let mut registry = NodeRegistry::new();
+        assert!(registry.deserialize(json).is_err());
+        registry.add::<Kbd>();
+        assert_eq!(registry.deserialize(json).unwrap().render(&Default::default()), "[Ctrl]");
+    }
+
+    #[test]
+    #[cfg(feature = "linkify")]
+    fn validates() {
+        use crate::parser::linkfmt::LinkPolicy;
+
+        let md = &mut MarkdownIt::with_link_policy(LinkPolicy {
+            external_rel: Some("nofollow".into()),
+            ..Default::default()
+        });
+        cmark::add(md);
+        crate::plugins::html::add(md);
+        crate::plugins::html::sanitize::add(md);
+
+        let json = json!({ "type": "Root", "children": [{ "type": "Paragraph", "children": [
+            { "type": "Link", "data": { "url": "javascript:a" }, "children": [
+                { "type": "Image", "data": { "url": "vbscript:b" }, "children": [{ "type": "Text", "data": { "content": "b" } }] },
+                { "type": "Link", "data": { "url": "data:c" }, "children": [{ "type": "Text", "data": { "content": "c" } }] },
+            ] },
+            { "type": "Link", "data": { "url": "https://d.org" }, "attrs": [["rel", "x"], ["cite", "javascript:d"]] },
+            { "type": "Autolink", "data": { "url": "javascript:e" }, "children": [{ "type": "Text", "data": { "content": "e" } }] },
+            { "type": "Linkified", "data": { "url": "javascript:f" }, "children": [{ "type": "Text", "data": { "content": "f" } }] },
+            { "type": "Autolink", "data": { "url": "https://g.org" }, "children": [{ "type": "Text", "data": { "content": "g" } }] },
+            { "type": "EquationRef", "data": { "label": "\"onclick", "number": 1 } },
+            { "type": "HtmlInline", "data": { "content": "<script>" } },
+        ] }] }).to_string();
+
+        let mut registry = NodeRegistry::new();
+        registry.add::<crate::plugins::html::html_inline::HtmlInline>();
+        let node = registry.deserialize_with(&json, md).unwrap();
+        assert_eq!(node.render(&Default::default()), "<p>bc<a rel=\"nofollow\" href=\"https://d.org\"></a>ef\
+            <a rel=\"nofollow\" href=\"https://g.org\">g</a><a href=\"#&quot;onclick\" class=\"eqref\">(1)</a></p>\n");
+    }
+
+    #[test]
+    fn custom_attributes() {
+        let json = r#"{ "type": "Paragraph", "attrs": [["id", "a"], ["data-x", "b"]] }"#;
+        let mut registry = NodeRegistry::new();
+        assert!(registry.deserialize(json).is_err());
+        registry.add_attribute("data-x");
+        assert_eq!(registry.deserialize(json).unwrap().render(&Default::default()), "<p id=\"a\" data-x=\"b\"></p>\n");
+    }
+
+    #[test]
+    fn generic_type_name() {
+        #[derive(Debug)]
+        struct Wrapper<T>(T);
+        impl<T: std::fmt::Debug + 'static> crate::NodeValue for Wrapper<T> {}
+
+        assert_eq!(type_name(&Node::new(Wrapper(Root::new(String::new())))), "Wrapper<Root>");
+    }
+}
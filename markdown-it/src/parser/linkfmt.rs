@@ -15,6 +15,117 @@ pub trait LinkFormatter : Debug + Send + Sync {
 
     /// Decode link url to a human-readable format.
     fn normalize_link_text(&self, url: &str) -> String;
+
+    /// Extra attributes for a link to (normalized) `url`,
+    /// e.g. `rel` and `target` for external links.
+    fn link_attrs(&self, url: &str) -> Vec<(&'static str, String)> {
+        let _ = url;
+        Vec::new()
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Which links [MDLinkFormatter] allows, and attributes added to external links.
+///
+/// Default policy only blocks urls that are a security risk, see
+/// [strict](LinkPolicy::strict) for untrusted input:
+///
+/// ```rust
+/// use markdown_it::parser::linkfmt::LinkPolicy;
+///
+/// let md = &mut markdown_it::MarkdownIt::with_link_policy(LinkPolicy::strict());
+/// markdown_it::plugins::cmark::add(md);
+///
+/// let html = md.parse("[a](https://example.org) [b](ftp://example.org)").render(&Default::default());
+/// assert_eq!(html.trim(), "<p><a rel=\"noopener noreferrer nofollow\" target=\"_blank\" \
+///     href=\"https://example.org\">a</a> [b](ftp://example.org)</p>");
+/// ```
+pub struct LinkPolicy {
+    /// Allowed url schemes, e.g. `https` or `mailto` (case-insensitive).
+    ///
+    /// `None` allows all schemes except `javascript`, `vbscript`, `file` and `data`.
+    /// `data` urls are only allowed for images (gif, png, jpeg, webp) either way.
+    pub schemes: Option<Vec<String>>,
+    /// Allow links without scheme, like `/path`, `page.html` or `#anchor`.
+    pub allow_relative: bool,
+    /// If not empty, only links to these hosts (and their subdomains) are allowed.
+    pub allowed_hosts: Vec<String>,
+    /// Links to these hosts (and their subdomains) are not allowed.
+    pub denied_hosts: Vec<String>,
+    /// Hosts of your own site, links with any other host are external.
+    pub internal_hosts: Vec<String>,
+    /// `rel` attribute added to external links.
+    pub external_rel: Option<String>,
+    /// `target` attribute added to external links.
+    pub external_target: Option<String>,
+}
+
+impl Default for LinkPolicy {
+    fn default() -> Self {
+        Self {
+            schemes: None,
+            allow_relative: true,
+            allowed_hosts: Vec::new(),
+            denied_hosts: Vec::new(),
+            internal_hosts: Vec::new(),
+            external_rel: None,
+            external_target: None,
+        }
+    }
+}
+
+impl LinkPolicy {
+    /// Policy for untrusted input: only `http`, `https` and `mailto` links,
+    /// external links open in a new tab with `rel="noopener noreferrer nofollow"`.
+    pub fn strict() -> Self {
+        Self {
+            schemes: Some(vec!["http".into(), "https".into(), "mailto".into()]),
+            external_rel: Some("noopener noreferrer nofollow".into()),
+            external_target: Some("_blank".into()),
+            ..Default::default()
+        }
+    }
+
+    /// Returns true if `url` points to a host not listed in `internal_hosts`.
+    pub fn is_external(&self, url: &str) -> bool {
+        if let Some(host) = authority_host(url) {
+            return !has_host(&self.internal_hosts, &host);
+        }
+
+        let url = mdurl::parse_url(url);
+        let Some(host) = url.hostname.filter(|host| !host.is_empty()) else { return false; };
+        // `mailto:user@host` has a host too, but it isn't a page to navigate to
+        url.slashes && !has_host(&self.internal_hosts, &host)
+    }
+}
+
+// Host of a url with special scheme or a protocol-relative url, as browsers
+// resolve it: `https:host`, `https:/host`, `https:\\host` and `\\host` all
+// point to `host`. Returns `None` for other urls, empty string if there's no host.
+fn authority_host(url: &str) -> Option<String> {
+    const SPECIAL_SCHEMES: [&str; 5] = ["http:", "https:", "ws:", "wss:", "ftp:"];
+    let slash = |ch: char| ch == '/' || ch == '\\';
+
+    let scheme = SPECIAL_SCHEMES.iter().find(|scheme| {
+        url.get(..scheme.len()).is_some_and(|prefix| prefix.eq_ignore_ascii_case(scheme))
+    });
+    let authority = match scheme {
+        Some(scheme) => url[scheme.len()..].trim_start_matches(slash),
+        None if url.starts_with(slash) && url[1..].starts_with(slash) => url.trim_start_matches(slash),
+        None => return None,
+    };
+
+    let authority = &authority[..authority.find(slash).unwrap_or(authority.len())];
+    Some(mdurl::parse_url(&format!("//{authority}")).hostname.unwrap_or_default())
+}
+
+fn has_host(list: &[String], host: &str) -> bool {
+    list.iter().any(|item| {
+        host.eq_ignore_ascii_case(item) ||
+            host.len() > item.len() &&
+            host.as_bytes()[host.len() - item.len() - 1] == b'.' &&
+            host[host.len() - item.len()..].eq_ignore_ascii_case(item)
+    })
 }
 
 /// Default link validator and formatter for markdown-it.
@@ -22,15 +133,25 @@ pub trait LinkFormatter : Debug + Send + Sync {
 /// This validator can prohibit more than really needed to prevent XSS. It's a
 /// tradeoff to keep code simple and to be secure by default.
 ///
-/// If you need different setup - override validator method as you wish. Or
-/// replace it with dummy function and use external sanitizer.
+/// If you need different setup - use [LinkPolicy], override validator method
+/// as you wish, or replace it with dummy function and use external sanitizer.
 ///
 #[derive(Default, Debug)]
-pub struct MDLinkFormatter;
+pub struct MDLinkFormatter {
+    policy: LinkPolicy,
+}
 
 impl MDLinkFormatter {
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    pub fn with_policy(policy: LinkPolicy) -> Self {
+        Self { policy }
+    }
+
+    pub fn policy(&self) -> &LinkPolicy {
+        &self.policy
     }
 }
 
@@ -45,11 +166,30 @@ impl LinkFormatter for MDLinkFormatter {
             Regex::new(r#"(?i)^data:image/(gif|png|jpeg|webp);"#).unwrap()
         );
 
-        if !BAD_PROTO_RE.is_match(url) || GOOD_DATA_RE.is_match(url) {
-            Some(())
-        } else {
-            None
+        let policy = &self.policy;
+        let parsed = mdurl::parse_url(url);
+
+        match (&parsed.protocol, &policy.schemes) {
+            (None, _) => {
+                if !policy.allow_relative { return None; }
+            }
+            (Some(protocol), Some(schemes)) => {
+                let scheme = protocol.trim_end_matches(':');
+                if !schemes.iter().any(|s| s.eq_ignore_ascii_case(scheme)) { return None; }
+                if scheme.eq_ignore_ascii_case("data") && !GOOD_DATA_RE.is_match(url) { return None; }
+            }
+            (Some(_), None) => {
+                if BAD_PROTO_RE.is_match(url) && !GOOD_DATA_RE.is_match(url) { return None; }
+            }
+        }
+
+        let host = authority_host(url).or(parsed.hostname.filter(|host| !host.is_empty()));
+        if let Some(host) = host {
+            if has_host(&policy.denied_hosts, &host) { return None; }
+            if !policy.allowed_hosts.is_empty() && !has_host(&policy.allowed_hosts, &host) { return None; }
         }
+
+        Some(())
     }
 
     fn normalize_link(&self, url: &str) -> String {
@@ -59,12 +199,26 @@ impl LinkFormatter for MDLinkFormatter {
     fn normalize_link_text(&self, url: &str) -> String {
         url.to_owned()
     }
+
+    fn link_attrs(&self, url: &str) -> Vec<(&'static str, String)> {
+        let mut attrs = Vec::new();
+        if self.policy.is_external(url) {
+            if let Some(rel) = &self.policy.external_rel {
+                attrs.push(("rel", rel.clone()));
+            }
+            if let Some(target) = &self.policy.external_target {
+                attrs.push(("target", target.clone()));
+            }
+        }
+        attrs
+    }
 }
 
 
 #[cfg(test)]
 mod tests {
     use super::LinkFormatter;
+    use super::LinkPolicy;
     use super::MDLinkFormatter;
 
     #[test]
@@ -97,4 +251,61 @@ mod tests {
         assert!(fmt.validate_link("data:image/gif;base64,R0lGODlhAQABAIAAAAAAAP///yH5BAEAAAAALAAAAAABAAEAAAIBRAA7").is_some());
         assert!(fmt.validate_link("data:text/html;base64,PHNjcmlwdD5hbGVydCgnWFNTJyk8L3NjcmlwdD4K").is_none());
     }
+
+    #[test]
+    fn should_only_allow_listed_schemes() {
+        let fmt = MDLinkFormatter::with_policy(LinkPolicy {
+            schemes: Some(vec!["https".into(), "data".into()]),
+            allow_relative: false,
+            ..Default::default()
+        });
+        assert!(fmt.validate_link("HTTPS://example.org").is_some());
+        assert!(fmt.validate_link("http://example.org").is_none());
+        assert!(fmt.validate_link("/path").is_none());
+        assert!(fmt.validate_link("data:image/png;base64,AAAA").is_some());
+        assert!(fmt.validate_link("data:text/html;base64,AAAA").is_none());
+    }
+
+    #[test]
+    fn should_check_hosts() {
+        let fmt = MDLinkFormatter::with_policy(LinkPolicy {
+            allowed_hosts: vec!["example.org".into()],
+            denied_hosts: vec!["bad.example.org".into()],
+            ..Default::default()
+        });
+        assert!(fmt.validate_link("https://example.org/a").is_some());
+        assert!(fmt.validate_link("https://user@docs.Example.org:8080/a").is_some());
+        assert!(fmt.validate_link("https://www.bad.example.org").is_none());
+        assert!(fmt.validate_link("https://notexample.org").is_none());
+        assert!(fmt.validate_link("//evil.com/a").is_none());
+        assert!(fmt.validate_link("http:evil.com").is_none());
+        assert!(fmt.validate_link("HTTPS:/evil.com").is_none());
+        assert!(fmt.validate_link("https:\\\\evil.com").is_none());
+        assert!(fmt.validate_link("wss:evil.com@example.org").is_some());
+        assert!(fmt.validate_link("\\\\evil.com").is_none());
+        assert!(fmt.validate_link("/\\evil.com").is_none());
+        assert!(fmt.validate_link("https:").is_none());
+        assert!(fmt.validate_link("https:example.org/a").is_some());
+        assert!(fmt.validate_link("/relative").is_some());
+    }
+
+    #[test]
+    fn should_mark_external_links() {
+        let fmt = MDLinkFormatter::with_policy(LinkPolicy {
+            internal_hosts: vec!["example.org".into()],
+            ..LinkPolicy::strict()
+        });
+        assert_eq!(fmt.link_attrs("https://other.org"), vec![
+            ("rel", "noopener noreferrer nofollow".to_owned()),
+            ("target", "_blank".to_owned()),
+        ]);
+        assert!(fmt.link_attrs("//other.org").len() == 2);
+        assert!(fmt.link_attrs("http:other.org").len() == 2);
+        assert!(fmt.link_attrs("https:/other.org/a").len() == 2);
+        assert!(fmt.link_attrs("https:example.org").is_empty());
+        assert!(fmt.link_attrs("https://www.example.org").is_empty());
+        assert!(fmt.link_attrs("mailto:a@other.org").is_empty());
+        assert!(fmt.link_attrs("/path").is_empty());
+        assert!(MDLinkFormatter::new().link_attrs("https://other.org").is_empty());
+    }
 }
@@ -7,7 +7,7 @@ use crate::parser::block::{self, BlockParser};
 use crate::parser::core::{Root, *};
 use crate::parser::extset::MarkdownItExtSet;
 use crate::parser::inline::{self, InlineParser};
-use crate::parser::linkfmt::{LinkFormatter, MDLinkFormatter};
+use crate::parser::linkfmt::{LinkFormatter, LinkPolicy, MDLinkFormatter};
 use crate::Node;
 
 type RuleFn = fn (&mut Node, &MarkdownIt);
@@ -47,15 +47,26 @@ impl MarkdownIt {
         Self::default()
     }
 
+    /// Create parser that validates links according to `policy`.
+    pub fn with_link_policy(policy: LinkPolicy) -> Self {
+        Self {
+            link_formatter: Box::new(MDLinkFormatter::with_policy(policy)),
+            ..Default::default()
+        }
+    }
+
     pub fn parse(&self, src: &str) -> Node {
         let mut node = Node::new(Root::new(src.to_owned()));
         node.srcmap = Some(SourcePos::new(0, src.len()));
+        self.run_rules(&mut node);
+        node
+    }
 
+    pub(super) fn run_rules(&self, node: &mut Node) {
         for rule in self.ruler.iter() {
-            rule(&mut node, self);
+            rule(node, self);
             debug_assert!(node.is::<Root>(), "root node of the AST must always be Root");
         }
-        node
     }
 
     pub fn add_rule<T: CoreRule>(&mut self) -> RuleBuilder<RuleFn> {
@@ -38,8 +38,11 @@ pub mod block;
 pub mod core;
 pub mod extset;
 pub mod inline;
+#[cfg(feature = "serde")]
+pub mod json;
 pub mod linkfmt;
+pub mod renderer;
 
+pub(super) mod incremental;
 pub(super) mod main;
 pub(super) mod node;
-pub(super) mod renderer;
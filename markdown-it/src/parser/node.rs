@@ -1,13 +1,14 @@
 use downcast_rs::{impl_downcast, Downcast};
 use std::any::TypeId;
-use std::collections::HashMap;
 use std::fmt::Debug;
+use std::io;
 
 use crate::common::sourcemap::SourcePos;
 use crate::common::TypeKey;
-use crate::parser::extset::NodeExtSet;
-use crate::parser::inline::Text;
-use crate::parser::renderer::HTMLRenderer;
+use crate::parser::extset::{NodeExtSet, RenderOptionsExtSet};
+use crate::parser::inline::{Text, TextSpecial};
+use crate::parser::renderer::cache::RenderCache;
+use crate::parser::renderer::{HTMLRenderer, HTMLRendererOptions, IoWriter};
 use crate::plugins::cmark::inline::newline::Softbreak;
 use crate::Renderer;
 
@@ -84,8 +85,17 @@ impl Node {
     }
 
     /// Render this node to HTML.
-    pub fn render(&self, options: &HashMap<String, String>) -> String {
-        let mut fmt = HTMLRenderer::<false>::new();
+    pub fn render(&self, options: &RenderOptionsExtSet) -> String {
+        let mut fmt = HTMLRenderer::new();
+        fmt.render(self, options);
+        fmt.into()
+    }
+
+    /// Render this node to HTML, reusing expensive output (highlighted code,
+    /// math, etc.) stored in `cache` by previous renders.
+    pub fn render_cached(&self, options: &RenderOptionsExtSet, cache: &RenderCache) -> String {
+        let mut fmt = HTMLRenderer::new();
+        fmt.ext().insert(cache.clone());
         fmt.render(self, options);
         fmt.into()
     }
@@ -93,12 +103,30 @@ impl Node {
     /// Render this node to XHTML, it adds slash to self-closing tags like this: `<img />`.
     ///
     /// This mode exists for compatibility with CommonMark tests.
-    pub fn xrender(&self, options: &HashMap<String, String>) -> String {
-        let mut fmt = HTMLRenderer::<true>::new();
+    pub fn xrender(&self, options: &RenderOptionsExtSet) -> String {
+        let mut fmt = HTMLRenderer::with_options(HTMLRendererOptions { xhtml: true, ..Default::default() });
         fmt.render(self, options);
         fmt.into()
     }
 
+    /// Render this node to HTML, streaming output into `out`.
+    ///
+    /// Unlike [render](Node::render), it doesn't keep the entire result in memory,
+    /// so it's better suited for large documents. Wrap `out` in [std::io::BufWriter]
+    /// if it isn't buffered already.
+    pub fn render_to(&self, out: &mut impl io::Write, options: &RenderOptionsExtSet) -> io::Result<()> {
+        let mut fmt = HTMLRenderer::with_writer(IoWriter::new(out), HTMLRendererOptions::default());
+        fmt.render(self, options);
+        fmt.into_inner().finish().map(|_| ())
+    }
+
+    /// Serialize this node (and its children) to JSON, see [json](crate::parser::json)
+    /// for the format.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        crate::parser::json::to_value(self).to_string()
+    }
+
     /// Replace custom value with another value (this is roughly equivalent
     /// to replacing the entire node and copying children and sourcemaps).
     pub fn replace<T: NodeValue>(&mut self, value: T) {
@@ -176,6 +204,8 @@ impl Node {
         self.walk(|node, _| {
             if let Some(text) = node.cast::<Text>() {
                 result.push_str(text.content.as_str());
+            } else if let Some(text) = node.cast::<TextSpecial>() {
+                result.push_str(text.content.as_str());
             } else if node.is::<Softbreak>() {
                 result.push('\n');
             }
@@ -210,29 +240,34 @@ impl Default for Node {
 pub trait NodeValue: Debug + Downcast {
     /// Output HTML corresponding to this node using Renderer API.
     ///
+    /// `options` are set by the caller, plugins read their own option
+    /// types from it (see [RenderOptionsExt](crate::parser::extset::RenderOptionsExt)).
+    ///
     /// Example implementation looks like this:
     /// ```rust
     /// # const IGNORE : &str = stringify! {
-    /// fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    /// fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
     ///    fmt.open("div", &[]);
-    ///    fmt.contents(&node.children);
+    ///    fmt.contents(&node.children, options);
     ///    fmt.close("div");
     ///    fmt.cr();
     /// }
     /// # };
     /// ```
-    fn render(
-        &self,
-        node: &Node,
-        fmt: &mut dyn Renderer,
-        parse_options: &HashMap<String, String>,
-        cache: &mut HashMap<String, String>,
-    ) {
-        let _ = fmt;
-        let _ = parse_options;
-        let _ = cache;
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
+        let _ = (fmt, options);
         unimplemented!("{} doesn't implement render", node.name());
     }
+
+    /// Serialize data specific to this node (e.g. heading level or link url)
+    /// for [JSON output](crate::parser::json).
+    ///
+    /// Nodes output no data unless they implement this, ones deriving
+    /// `serde::Serialize` can simply return `serde_json::to_value(self).ok()`.
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        None
+    }
 }
 
 impl_downcast!(NodeValue);
@@ -0,0 +1,842 @@
+//! Terminal renderer, outputs text styled with ANSI escape sequences.
+//!
+//! Emphasis is written in italic and bold, headings are colored, tables
+//! are drawn with box characters, code is highlighted with 24-bit colors
+//! (with `syntect` feature), links are clickable in terminals supporting
+//! OSC 8, and paragraphs are wrapped to the terminal width.
+//!
+//! ```rust
+//! use markdown_it::parser::renderer::ansi::{AnsiRenderer, AnsiRendererOptions};
+//!
+//! let md = &mut markdown_it::MarkdownIt::new();
+//! markdown_it::plugins::cmark::add(md);
+//!
+//! let mut fmt = AnsiRenderer::with_options(AnsiRendererOptions { width: Some(14), ..Default::default() });
+//! fmt.render(&md.parse("some **bold** text here"));
+//! assert_eq!(String::from(fmt), "some \x1b[1mbold\x1b[0m text\nhere\n");
+//! ```
+//!
+//! Nodes without a handler output their children, plugins can register
+//! their own handlers with [AnsiRenderer::set_handler].
+//!
+//! Control characters in the document are replaced with `\u{FFFD}`, so notes
+//! can't send their own escape sequences to the terminal.
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use unicode_width::UnicodeWidthChar;
+
+use crate::common::TypeKey;
+use crate::generics::block::fence_handler::get_language;
+use crate::parser::core::Root;
+use crate::parser::extset::{RenderExt, RenderExtSet};
+use crate::parser::inline::{Text, TextSpecial};
+use crate::plugins::cmark::block::blockquote::Blockquote;
+use crate::plugins::cmark::block::code::CodeBlock;
+use crate::plugins::cmark::block::fence::CodeFence;
+use crate::plugins::cmark::block::heading::ATXHeading;
+use crate::plugins::cmark::block::hr::ThematicBreak;
+use crate::plugins::cmark::block::lheading::SetextHeader;
+use crate::plugins::cmark::block::list::{BulletList, ListItem, OrderedList};
+use crate::plugins::cmark::block::paragraph::Paragraph;
+use crate::plugins::cmark::block::reference::Definition;
+use crate::plugins::cmark::inline::autolink::Autolink;
+use crate::plugins::cmark::inline::backticks::CodeInline;
+use crate::plugins::cmark::inline::emphasis::{Em, Strong};
+use crate::plugins::cmark::inline::image::Image;
+use crate::plugins::cmark::inline::link::Link;
+use crate::plugins::cmark::inline::newline::{Hardbreak, Softbreak};
+use crate::plugins::extra::strikethrough::Strikethrough;
+use crate::plugins::extra::tables::{ColumnAlignment, Table, TableBody, TableCell, TableHead, TableRow};
+use crate::plugins::gfm::heading_anchors::HeadingAnchor;
+use crate::plugins::gfm::tasklist::TodoCheckbox;
+use crate::plugins::html::html_block::HtmlBlock;
+use crate::plugins::html::html_inline::HtmlInline;
+use crate::plugins::jotdown::diagrams::Diagram;
+use crate::plugins::jotdown::katex::block::MathBlock;
+use crate::plugins::jotdown::katex::equations::EquationRef;
+use crate::plugins::jotdown::katex::inline::InlineMath;
+use crate::plugins::pandoc::deflist::{DefinitionDescription, DefinitionList, DefinitionTerm};
+use crate::plugins::pandoc::footnote::back_refs::FootnoteRefAnchor;
+use crate::plugins::pandoc::footnote::definitions::FootnoteDefinition;
+use crate::plugins::pandoc::footnote::references::FootnoteReference;
+use crate::{Node, NodeValue};
+
+/// Function that writes styled text for a node.
+pub type AnsiHandler = fn (node: &Node, fmt: &mut AnsiRenderer);
+
+const BOLD: &str = "1";
+const DIM: &str = "2";
+const ITALIC: &str = "3";
+const STRIKE: &str = "9";
+const LINK: &str = "4;34";
+const CODE: &str = "33";
+const MATH: &str = "3;33";
+const FOOTNOTE: &str = "36";
+const HEADINGS: [&str; 6] = ["1;4;35", "1;35", "1;36", "1;34", "1;32", "1;33"];
+const BULLETS: [&str; 3] = ["•", "◦", "▪"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Options for [AnsiRenderer].
+pub struct AnsiRendererOptions {
+    /// Wrap text to this many columns, `None` keeps paragraphs on one line.
+    pub width: Option<usize>,
+    /// Make links clickable with OSC 8 escape sequences.
+    pub hyperlinks: bool,
+    /// Syntect theme for code blocks, used with `syntect` feature.
+    pub theme: &'static str,
+}
+
+impl Default for AnsiRendererOptions {
+    fn default() -> Self {
+        Self { width: Some(80), hyperlinks: true, theme: "base16-ocean.dark" }
+    }
+}
+
+#[derive(Debug)]
+// line prefix of a block container, e.g. `│ ` for blockquotes
+struct Prefix {
+    text: String,
+    // replaces `text` on the first line, e.g. list marker
+    first_line: Option<String>,
+    width: usize,
+    // length of the output when container was opened
+    start: usize,
+}
+
+#[derive(Debug)]
+/// Renderer that outputs text with ANSI escape sequences for terminals.
+pub struct AnsiRenderer {
+    result: String,
+    ext: RenderExtSet,
+    options: AnsiRendererOptions,
+    handlers: HashMap<TypeKey, AnsiHandler>,
+
+    prefixes: Vec<Prefix>,
+    // newlines required before the next block
+    pending_newlines: usize,
+    pending_space: bool,
+    line_start: bool,
+    // visible width of the current line
+    column: usize,
+    tight: bool,
+    wrapping: bool,
+    // requested SGR parameters and link, escapes are written lazily
+    // before visible text, so they can be reapplied after line breaks
+    styles: Vec<String>,
+    link: Option<String>,
+    written_styles: Vec<String>,
+    written_link: Option<String>,
+}
+
+impl AnsiRenderer {
+    pub fn new() -> Self {
+        Self::with_options(AnsiRendererOptions::default())
+    }
+
+    pub fn with_options(options: AnsiRendererOptions) -> Self {
+        let mut result = Self {
+            result: String::new(),
+            ext: RenderExtSet::new(),
+            wrapping: options.width.is_some(),
+            options,
+            handlers: HashMap::new(),
+            prefixes: Vec::new(),
+            pending_newlines: 0,
+            pending_space: false,
+            line_start: true,
+            column: 0,
+            tight: false,
+            styles: Vec::new(),
+            link: None,
+            written_styles: Vec::new(),
+            written_link: None,
+        };
+
+        result.set_handler::<Root>(|node, fmt| fmt.contents(&node.children));
+        result.set_handler::<Text>(|node, fmt| fmt.text(&node.cast::<Text>().unwrap().content));
+        result.set_handler::<TextSpecial>(|node, fmt| fmt.text(&node.cast::<TextSpecial>().unwrap().content));
+
+        // commonmark blocks
+        result.set_handler::<Paragraph>(|node, fmt| {
+            fmt.block_start();
+            fmt.contents(&node.children);
+            fmt.block_end();
+        });
+        result.set_handler::<ATXHeading>(|node, fmt| heading(node, fmt, node.cast::<ATXHeading>().unwrap().level));
+        result.set_handler::<SetextHeader>(|node, fmt| heading(node, fmt, node.cast::<SetextHeader>().unwrap().level));
+        result.set_handler::<ThematicBreak>(|_, fmt| {
+            let width = fmt.options.width.unwrap_or(80).saturating_sub(fmt.prefix_width()).max(3);
+            fmt.block_start();
+            fmt.styled(DIM, |fmt| fmt.write(&"─".repeat(width)));
+            fmt.block_end();
+        });
+        result.set_handler::<CodeBlock>(|node, fmt| code(fmt, "", &node.cast::<CodeBlock>().unwrap().content));
+        result.set_handler::<CodeFence>(|node, fmt| {
+            let data = node.cast::<CodeFence>().unwrap();
+            code(fmt, &get_language(&data.info), &data.content);
+        });
+        result.set_handler::<Blockquote>(|node, fmt| {
+            fmt.block_start();
+            let tight = fmt.set_tight(false);
+            fmt.wrap("\x1b[2m│\x1b[0m ", None, |fmt| fmt.contents(&node.children));
+            fmt.set_tight(tight);
+            fmt.block_end();
+        });
+        result.set_handler::<BulletList>(|node, fmt| {
+            let depth = fmt.ext.get::<ListContext>().map(|ctx| ctx.depth).unwrap_or_default();
+            let bullet = BULLETS[depth % BULLETS.len()];
+            list(node, fmt, |_| bullet.to_owned());
+        });
+        result.set_handler::<OrderedList>(|node, fmt| {
+            let start = node.cast::<OrderedList>().unwrap().start as usize;
+            list(node, fmt, |idx| format!("{}.", start + idx));
+        });
+        result.set_handler::<ListItem>(list_item);
+        result.set_handler::<Definition>(|_, _| {});
+        result.set_handler::<HtmlBlock>(|node, fmt| {
+            fmt.block_start();
+            fmt.styled(DIM, |fmt| fmt.write(&escape_controls(node.cast::<HtmlBlock>().unwrap().content.trim_end())));
+            fmt.block_end();
+        });
+
+        // commonmark inlines
+        result.set_handler::<Em>(|node, fmt| fmt.styled(ITALIC, |fmt| fmt.contents(&node.children)));
+        result.set_handler::<Strong>(|node, fmt| fmt.styled(BOLD, |fmt| fmt.contents(&node.children)));
+        result.set_handler::<CodeInline>(|node, fmt| fmt.styled(CODE, |fmt| fmt.text(&node.collect_text())));
+        result.set_handler::<Link>(|node, fmt| link(node, fmt, &node.cast::<Link>().unwrap().url));
+        result.set_handler::<Autolink>(|node, fmt| link(node, fmt, &node.cast::<Autolink>().unwrap().url));
+        result.set_handler::<Image>(|node, fmt| {
+            let url = &node.cast::<Image>().unwrap().url;
+            fmt.hyperlink(url, |fmt| fmt.styled(DIM, |fmt| {
+                fmt.text("[image: ");
+                fmt.contents(&node.children);
+                fmt.text("]");
+            }));
+        });
+        result.set_handler::<Hardbreak>(|_, fmt| fmt.line_break());
+        result.set_handler::<Softbreak>(|_, fmt| fmt.space());
+        result.set_handler::<HtmlInline>(|node, fmt| fmt.styled(DIM, |fmt| fmt.text(&node.cast::<HtmlInline>().unwrap().content)));
+
+        // extensions
+        result.set_handler::<Strikethrough>(|node, fmt| fmt.styled(STRIKE, |fmt| fmt.contents(&node.children)));
+        result.set_handler::<Table>(table);
+        result.set_handler::<TodoCheckbox>(|node, fmt| {
+            fmt.text(if node.cast::<TodoCheckbox>().unwrap().checked { "☑" } else { "☐" });
+        });
+        result.set_handler::<DefinitionList>(|node, fmt| {
+            fmt.block_start();
+            let tight = fmt.set_tight(true);
+            fmt.contents(&node.children);
+            fmt.set_tight(tight);
+            fmt.block_end();
+        });
+        result.set_handler::<DefinitionTerm>(|node, fmt| {
+            fmt.block_start();
+            fmt.styled(BOLD, |fmt| fmt.contents(&node.children));
+            fmt.block_end();
+        });
+        result.set_handler::<DefinitionDescription>(|node, fmt| {
+            fmt.block_start();
+            fmt.wrap("    ", None, |fmt| fmt.contents(&node.children));
+            fmt.block_end();
+        });
+        result.set_handler::<FootnoteReference>(|node, fmt| {
+            let def_id = node.cast::<FootnoteReference>().unwrap().def_id;
+            fmt.styled(FOOTNOTE, |fmt| fmt.text(&format!("[{def_id}]")));
+        });
+        result.set_handler::<FootnoteDefinition>(footnote_definition);
+        result.set_handler::<MathBlock>(|node, fmt| {
+            fmt.block_start();
+            fmt.wrap("    ", None, |fmt| fmt.styled(MATH, |fmt| fmt.write(&escape_controls(node.cast::<MathBlock>().unwrap().content.trim()))));
+            fmt.block_end();
+        });
+        result.set_handler::<InlineMath>(|node, fmt| fmt.styled(MATH, |fmt| fmt.text(&node.cast::<InlineMath>().unwrap().content)));
+        result.set_handler::<EquationRef>(|node, fmt| {
+            let data = node.cast::<EquationRef>().unwrap();
+            match data.number {
+                Some(number) => fmt.text(&format!("({number})")),
+                None => fmt.text(&format!("({})", data.label)),
+            }
+        });
+        result.set_handler::<Diagram>(|node, fmt| code(fmt, "", &node.cast::<Diagram>().unwrap().source));
+        #[cfg(feature = "linkify")]
+        result.set_handler::<crate::plugins::extra::linkify::Linkified>(|node, fmt| {
+            link(node, fmt, &node.cast::<crate::plugins::extra::linkify::Linkified>().unwrap().url);
+        });
+
+        // only useful in html
+        result.set_handler::<FootnoteRefAnchor>(|_, _| {});
+        result.set_handler::<HeadingAnchor>(|_, _| {});
+
+        result
+    }
+
+    /// Write all nodes of type `T` with function `f`.
+    pub fn set_handler<T: NodeValue>(&mut self, f: AnsiHandler) {
+        self.handlers.insert(TypeKey::of::<T>(), f);
+    }
+
+    /// Remove handler for type `T`, so only children of its nodes are written.
+    pub fn remove_handler<T: NodeValue>(&mut self) {
+        self.handlers.remove(&TypeKey::of::<T>());
+    }
+
+    /// Render node (and its children) appending result to the buffer.
+    pub fn render(&mut self, node: &Node) {
+        if let Some(f) = self.handlers.get(&node.node_type).copied() {
+            f(node, self);
+        } else {
+            self.contents(&node.children);
+        }
+    }
+
+    /// Render each node in the list.
+    pub fn contents(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            self.render(node);
+        }
+    }
+
+    /// Start a new block, separating it from previous one.
+    pub fn block_start(&mut self) {
+        let mut newlines = std::mem::take(&mut self.pending_newlines);
+        self.pending_space = false;
+        if self.result.is_empty() { return; }
+
+        if !self.line_start {
+            self.line_break();
+            newlines = newlines.saturating_sub(1);
+        }
+
+        for _ in 0..newlines {
+            self.write_prefix(true);
+            self.result.push('\n');
+        }
+    }
+
+    /// Finish a block, next one will be separated by an empty line
+    /// (or line break inside tight lists).
+    pub fn block_end(&mut self) {
+        self.pending_newlines = if self.tight { 1 } else { 2 };
+    }
+
+    /// Set whether blocks are separated by line breaks instead of empty lines,
+    /// returns previous value.
+    pub fn set_tight(&mut self, tight: bool) -> bool {
+        std::mem::replace(&mut self.tight, tight)
+    }
+
+    /// Render block container: `prefix` is written at the start of each line
+    /// inside it, `first_line` replaces it on the first line (e.g. list marker).
+    /// Both may contain escape sequences.
+    pub fn wrap(&mut self, prefix: &str, first_line: Option<&str>, f: impl FnOnce(&mut Self)) {
+        self.prefixes.push(Prefix {
+            text: prefix.to_owned(),
+            first_line: first_line.map(str::to_owned),
+            width: visible_width(prefix),
+            start: self.result.len(),
+        });
+
+        f(self);
+
+        if self.result.len() == self.prefixes.last().unwrap().start {
+            // empty container, but its marker is still needed
+            self.write_prefix(true);
+            self.line_start = false;
+        }
+        self.prefixes.pop();
+    }
+
+    /// Render something into a separate buffer and return it, e.g. to measure
+    /// its width. Container prefixes are not applied, and lines are not wrapped.
+    pub fn capture(&mut self, f: impl FnOnce(&mut Self)) -> String {
+        let result = std::mem::take(&mut self.result);
+        let prefixes = std::mem::take(&mut self.prefixes);
+        let pending_newlines = std::mem::take(&mut self.pending_newlines);
+        let pending_space = std::mem::take(&mut self.pending_space);
+        let line_start = std::mem::replace(&mut self.line_start, true);
+        let column = std::mem::take(&mut self.column);
+        let wrapping = std::mem::replace(&mut self.wrapping, false);
+        let written_styles = std::mem::take(&mut self.written_styles);
+        let written_link = std::mem::take(&mut self.written_link);
+
+        f(self);
+        self.reset_styles();
+
+        self.written_styles = written_styles;
+        self.written_link = written_link;
+        self.prefixes = prefixes;
+        self.pending_newlines = pending_newlines;
+        self.pending_space = pending_space;
+        self.line_start = line_start;
+        self.column = column;
+        self.wrapping = wrapping;
+        std::mem::replace(&mut self.result, result)
+    }
+
+    /// Render contents with SGR style, e.g. `1` for bold or `38;2;255;0;0` for red.
+    pub fn styled(&mut self, style: &str, f: impl FnOnce(&mut Self)) {
+        self.styles.push(style.to_owned());
+        f(self);
+        self.styles.pop();
+    }
+
+    /// Render contents as a link to `url`, if hyperlinks are enabled.
+    pub fn hyperlink(&mut self, url: &str, f: impl FnOnce(&mut Self)) {
+        if !self.options.hyperlinks || self.link.is_some() {
+            f(self);
+            return;
+        }
+
+        // url is written inside of an escape sequence
+        self.link = Some(url.chars().filter(|ch| !ch.is_control()).collect());
+        f(self);
+        self.link = None;
+    }
+
+    /// Write text, wrapping it at spaces if it doesn't fit into the width.
+    /// Control characters are replaced, see [escape_controls].
+    pub fn text(&mut self, text: &str) {
+        let text = escape_controls(text);
+        for (idx, word) in text.split(' ').enumerate() {
+            if idx > 0 {
+                self.space();
+            }
+            self.word(word);
+        }
+    }
+
+    /// Write a space between words, it becomes a line break if next word
+    /// doesn't fit into the width.
+    pub fn space(&mut self) {
+        if !self.line_start {
+            self.pending_space = true;
+        }
+    }
+
+    /// Write text as is, without wrapping. It may contain escape sequences,
+    /// so text from the document should go through [escape_controls] first.
+    pub fn write(&mut self, text: &str) {
+        for (idx, line) in text.split('\n').enumerate() {
+            if idx > 0 {
+                self.line_break();
+            }
+            self.push(line);
+        }
+    }
+
+    /// Start a new line.
+    pub fn line_break(&mut self) {
+        self.reset_styles();
+        if self.line_start {
+            self.write_prefix(true);
+        }
+        self.result.push('\n');
+        self.line_start = true;
+        self.pending_space = false;
+        self.column = 0;
+    }
+
+    /// Extension set to store custom stuff.
+    pub fn ext(&mut self) -> &mut RenderExtSet {
+        &mut self.ext
+    }
+
+    fn word(&mut self, word: &str) {
+        if word.is_empty() { return; }
+
+        let width = visible_width(word);
+        if let (true, Some(max_width), false) = (self.wrapping, self.options.width, self.line_start) {
+            if self.column + self.pending_space as usize + width > max_width {
+                self.line_break();
+            }
+        }
+        self.push(word);
+    }
+
+    fn push(&mut self, text: &str) {
+        if text.is_empty() { return; }
+
+        if self.line_start {
+            self.write_prefix(false);
+            self.line_start = false;
+        }
+        if std::mem::take(&mut self.pending_space) {
+            // space belongs to the outer style, unless both words share it
+            if self.written_styles != self.styles || self.written_link != self.link {
+                self.reset_styles();
+            }
+            self.result.push(' ');
+            self.column += 1;
+        }
+        self.apply_styles();
+        self.result.push_str(text);
+        self.column += visible_width(text);
+    }
+
+    fn prefix_width(&self) -> usize {
+        self.prefixes.iter().map(|prefix| prefix.width).sum()
+    }
+
+    fn write_prefix(&mut self, trim: bool) {
+        if self.prefixes.is_empty() { return; }
+
+        // prefixes have their own style
+        self.reset_styles();
+
+        let start = self.result.len();
+        for prefix in self.prefixes.iter_mut() {
+            match prefix.first_line.take() {
+                Some(text) => self.result.push_str(&text),
+                None => self.result.push_str(&prefix.text),
+            }
+        }
+        if trim {
+            let len = self.result[start..].trim_end().len();
+            self.result.truncate(start + len);
+        }
+        self.column = visible_width(&self.result[start..]);
+    }
+
+    // write escapes to switch from the current style and link to requested ones
+    fn apply_styles(&mut self) {
+        if self.written_link != self.link {
+            if self.written_link.is_some() {
+                self.result.push_str("\x1b]8;;\x1b\\");
+            }
+            if let Some(url) = &self.link {
+                self.result.push_str(&format!("\x1b]8;;{url}\x1b\\"));
+            }
+            self.written_link = self.link.clone();
+        }
+
+        if self.written_styles != self.styles {
+            if !self.written_styles.is_empty() {
+                self.result.push_str("\x1b[0m");
+            }
+            for style in self.styles.iter() {
+                self.result.push_str(&format!("\x1b[{style}m"));
+            }
+            self.written_styles = self.styles.clone();
+        }
+    }
+
+    // close link and reset style
+    fn reset_styles(&mut self) {
+        if self.written_link.take().is_some() {
+            self.result.push_str("\x1b]8;;\x1b\\");
+        }
+        if !std::mem::take(&mut self.written_styles).is_empty() {
+            self.result.push_str("\x1b[0m");
+        }
+    }
+}
+
+impl Default for AnsiRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<AnsiRenderer> for String {
+    fn from(mut fmt: AnsiRenderer) -> Self {
+        fmt.reset_styles();
+        let mut result = fmt.result;
+        if !result.is_empty() {
+            result.push('\n');
+        }
+        result
+    }
+}
+
+#[derive(Debug, Default)]
+/// Marker of the next list item and nesting level of lists.
+struct ListContext {
+    marker: String,
+    depth: usize,
+}
+
+impl RenderExt for ListContext {}
+
+/// Replace control characters other than `\n` and `\t` with `\u{FFFD}`.
+pub fn escape_controls(text: &str) -> Cow<'_, str> {
+    let unsafe_control = |ch: char| ch.is_control() && ch != '\n' && ch != '\t';
+    if !text.contains(unsafe_control) {
+        return Cow::Borrowed(text);
+    }
+    text.chars().map(|ch| if unsafe_control(ch) { '\u{FFFD}' } else { ch }).collect()
+}
+
+/// Width of text on the screen in columns, without escape sequences.
+pub fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars();
+
+    while let Some(ch) = chars.next() {
+        if ch != '\x1b' {
+            width += ch.width().unwrap_or(0);
+            continue;
+        }
+
+        match chars.next() {
+            // CSI, `ESC [ params final`
+            Some('[') => for ch in chars.by_ref() {
+                if ('\x40'..='\x7e').contains(&ch) { break; }
+            },
+            // OSC, `ESC ] text ST`
+            Some(']') => while let Some(ch) = chars.next() {
+                if ch == '\x07' { break; }
+                if ch == '\x1b' {
+                    chars.next();
+                    break;
+                }
+            },
+            _ => {}
+        }
+    }
+
+    width
+}
+
+fn heading(node: &Node, fmt: &mut AnsiRenderer, level: u8) {
+    let style = HEADINGS[(level as usize).clamp(1, 6) - 1];
+    fmt.block_start();
+    fmt.styled(style, |fmt| fmt.contents(&node.children));
+    fmt.block_end();
+}
+
+fn code(fmt: &mut AnsiRenderer, language: &str, content: &str) {
+    let content = escape_controls(content.trim_end_matches('\n'));
+
+    #[cfg(feature = "syntect")]
+    let highlighted = if language.is_empty() {
+        None
+    } else {
+        crate::plugins::extra::syntect::highlight_terminal(&content, language, fmt.options.theme)
+    };
+    #[cfg(not(feature = "syntect"))]
+    let highlighted: Option<String> = { let _ = language; None };
+
+    fmt.block_start();
+    fmt.wrap("    ", None, |fmt| match highlighted {
+        Some(highlighted) => fmt.write(&highlighted),
+        None => fmt.styled(CODE, |fmt| fmt.write(&content)),
+    });
+    fmt.block_end();
+}
+
+fn list(node: &Node, fmt: &mut AnsiRenderer, marker: impl Fn(usize) -> String) {
+    let old_context = fmt.ext.remove::<ListContext>();
+    let depth = old_context.as_ref().map(|ctx| ctx.depth + 1).unwrap_or_default();
+    let loose = node.children.iter().any(|item| item.children.iter().any(|child| child.is::<Paragraph>()));
+    let tight = fmt.set_tight(!loose);
+
+    fmt.block_start();
+    for (idx, item) in node.children.iter().enumerate() {
+        fmt.ext.insert(ListContext { marker: marker(idx), depth });
+        fmt.render(item);
+    }
+
+    fmt.set_tight(tight);
+    fmt.block_end();
+    fmt.ext.remove::<ListContext>();
+    if let Some(ctx) = old_context {
+        fmt.ext.insert(ctx);
+    }
+}
+
+fn list_item(node: &Node, fmt: &mut AnsiRenderer) {
+    let marker = match fmt.ext.get::<ListContext>() {
+        Some(ctx) => ctx.marker.clone(),
+        None => BULLETS[0].to_owned(),
+    };
+    let indent = " ".repeat(visible_width(&marker) + 1);
+
+    fmt.block_start();
+    fmt.wrap(&indent, Some(&format!("\x1b[{BOLD}m{marker}\x1b[0m ")), |fmt| {
+        let mut children = &node.children[..];
+
+        // checkbox goes on the same line as the text, even in loose lists
+        if let Some(checkbox) = children.first().filter(|child| child.is::<TodoCheckbox>()) {
+            fmt.render(checkbox);
+            children = &children[1..];
+            if let Some(paragraph) = children.first().filter(|child| child.is::<Paragraph>()) {
+                fmt.space();
+                fmt.contents(&paragraph.children);
+                fmt.block_end();
+                children = &children[1..];
+            }
+        }
+
+        fmt.contents(children);
+    });
+    fmt.block_end();
+}
+
+fn link(node: &Node, fmt: &mut AnsiRenderer, url: &str) {
+    fmt.hyperlink(url, |fmt| fmt.styled(LINK, |fmt| fmt.contents(&node.children)));
+}
+
+fn table(node: &Node, fmt: &mut AnsiRenderer) {
+    let data = node.cast::<Table>().unwrap();
+    let columns = data.alignments.len();
+
+    let mut rows = Vec::new();
+    let mut head_rows = 0;
+    for section in node.children.iter() {
+        let head = section.is::<TableHead>();
+        if !head && !section.is::<TableBody>() { continue; }
+        for row in section.children.iter().filter(|row| row.is::<TableRow>()) {
+            let mut cells = row.children.iter()
+                .filter(|cell| cell.is::<TableCell>())
+                .map(|cell| fmt.capture(|fmt| {
+                    if head {
+                        fmt.styled(BOLD, |fmt| fmt.contents(&cell.children));
+                    } else {
+                        fmt.contents(&cell.children);
+                    }
+                }))
+                .collect::<Vec<_>>();
+            cells.resize(columns, String::new());
+            rows.push(cells);
+            if head { head_rows += 1; }
+        }
+    }
+
+    let mut widths = vec![0; columns];
+    for row in rows.iter() {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(visible_width(cell));
+        }
+    }
+
+    let border = |left: &str, middle: &str, right: &str| {
+        let lines = widths.iter().map(|width| "─".repeat(width + 2)).collect::<Vec<_>>();
+        format!("{left}{}{right}", lines.join(middle))
+    };
+
+    let mut lines = vec![border("┌", "┬", "┐")];
+    for (idx, row) in rows.iter().enumerate() {
+        if idx > 0 && idx == head_rows {
+            lines.push(border("├", "┼", "┤"));
+        }
+        let cells = row.iter().zip(widths.iter()).zip(data.alignments.iter()).map(|((cell, width), alignment)| {
+            let padding = width - visible_width(cell);
+            let left = match alignment {
+                ColumnAlignment::None | ColumnAlignment::Left => 0,
+                ColumnAlignment::Right => padding,
+                ColumnAlignment::Center => padding / 2,
+            };
+            format!(" {}{cell}{} ", " ".repeat(left), " ".repeat(padding - left))
+        }).collect::<Vec<_>>();
+        lines.push(format!("│{}│", cells.join("│")));
+    }
+    lines.push(border("└", "┴", "┘"));
+
+    fmt.block_start();
+    fmt.write(&lines.join("\n"));
+    fmt.block_end();
+}
+
+fn footnote_definition(node: &Node, fmt: &mut AnsiRenderer) {
+    let Some(def_id) = node.cast::<FootnoteDefinition>().unwrap().def_id else { return };
+    let marker = format!("[{def_id}]");
+    let indent = " ".repeat(marker.len() + 1);
+
+    fmt.block_start();
+    let tight = fmt.set_tight(false);
+    fmt.wrap(&indent, Some(&format!("\x1b[{FOOTNOTE}m{marker}\x1b[0m ")), |fmt| fmt.contents(&node.children));
+    fmt.set_tight(tight);
+    fmt.block_end();
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::plugins::{cmark, extra, gfm, pandoc};
+    use crate::MarkdownIt;
+
+    use super::*;
+
+    fn render(src: &str, width: Option<usize>) -> String {
+        let mut md = MarkdownIt::new();
+        cmark::add(&mut md);
+        extra::tables::add(&mut md);
+        extra::strikethrough::add(&mut md);
+        gfm::tasklist::add(&mut md);
+        pandoc::add(&mut md);
+
+        let mut fmt = AnsiRenderer::with_options(AnsiRendererOptions { width, hyperlinks: false, ..Default::default() });
+        fmt.render(&md.parse(src));
+        fmt.into()
+    }
+
+    #[rstest]
+    #[case("# A\n\n### B", "\x1b[1;4;35mA\x1b[0m\n\n\x1b[1;36mB\x1b[0m\n")]
+    #[case("*a* **b** ~~c~~ `d`", "\x1b[3ma\x1b[0m \x1b[1mb\x1b[0m \x1b[9mc\x1b[0m \x1b[33md\x1b[0m\n")]
+    #[case("- a\n- b\n  1. c", "\x1b[1m•\x1b[0m a\n\x1b[1m•\x1b[0m b\n  \x1b[1m1.\x1b[0m c\n")]
+    #[case("> a\n>\n> b", "\x1b[2m│\x1b[0m a\n\x1b[2m│\x1b[0m\n\x1b[2m│\x1b[0m b\n")]
+    #[case("- [x] done", "\x1b[1m•\x1b[0m ☑ done\n")]
+    #[case("    code", "    \x1b[33mcode\x1b[0m\n")]
+    #[case("| a | b |\n|---|--:|\n| long | 1 |", "┌──────┬───┐\n│ \x1b[1ma\x1b[0m    │ \x1b[1mb\x1b[0m │\n├──────┼───┤\n│ long │ 1 │\n└──────┴───┘\n")]
+    #[case("a[^1]\n\n[^1]: note", "a\x1b[36m[1]\x1b[0m\n\n\x1b[36m[1]\x1b[0m note\n")]
+    fn ansi(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(render(input, None), expected);
+    }
+
+    #[rstest]
+    #[case("aaa bbb ccc\nddd", "aaa bbb\nccc ddd\n")]
+    #[case("aaa *bbb ccc*", "aaa \x1b[3mbbb\x1b[0m\n\x1b[3mccc\x1b[0m\n")]
+    #[case("> aaa bbb ccc", "\x1b[2m│\x1b[0m aaa bbb\n\x1b[2m│\x1b[0m ccc\n")]
+    #[case("- aaa bbb ccc", "\x1b[1m•\x1b[0m aaa bbb\n  ccc\n")]
+    #[case("verylongword", "verylongword\n")]
+    fn wrap(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(render(input, Some(9)), expected);
+    }
+
+    #[test]
+    fn hyperlinks() {
+        let md = &mut MarkdownIt::new();
+        cmark::add(md);
+        let mut fmt = AnsiRenderer::with_options(AnsiRendererOptions { width: Some(2), ..Default::default() });
+        fmt.render(&md.parse("[a b](/url)"));
+        assert_eq!(
+            String::from(fmt),
+            "\x1b]8;;/url\x1b\\\x1b[4;34ma\x1b]8;;\x1b\\\x1b[0m\n\x1b]8;;/url\x1b\\\x1b[4;34mb\x1b]8;;\x1b\\\x1b[0m\n",
+        );
+    }
+
+    #[test]
+    fn width_without_escapes() {
+        assert_eq!(visible_width("\x1b[1;31mab\x1b[0m\x1b]8;;http://x\x1b\\c\x1b]8;;\x07"), 3);
+        assert_eq!(visible_width("日本語 🦀"), 9);
+    }
+
+    #[rstest]
+    #[case("a\x1b]52;c;YQ==\x07b", "a\u{FFFD}]52;c;YQ==\u{FFFD}b\n")]
+    #[case("`\x1b]0;title\x07`", "\x1b[33m\u{FFFD}]0;title\u{FFFD}\x1b[0m\n")]
+    #[case("<div>\x1b[2J</div>", "\x1b[2m<div>\u{FFFD}[2J</div>\x1b[0m\n")]
+    #[case("    a\u{9b}2J\tb", "    \x1b[33ma\u{FFFD}2J\tb\x1b[0m\n")]
+    fn escapes_controls(#[case] input: &str, #[case] expected: &str) {
+        let md = &mut MarkdownIt::new();
+        cmark::add(md);
+        crate::plugins::html::add(md);
+        let mut fmt = AnsiRenderer::with_options(AnsiRendererOptions { width: None, ..Default::default() });
+        fmt.render(&md.parse(input));
+        assert_eq!(String::from(fmt), expected);
+    }
+
+    #[test]
+    fn hyperlink_without_controls() {
+        let mut fmt = AnsiRenderer::new();
+        fmt.hyperlink("/a\x07\x1b]0;b", |fmt| fmt.text("c"));
+        assert_eq!(String::from(fmt), "\x1b]8;;/a]0;b\x1b\\c\x1b]8;;\x1b\\\n");
+    }
+
+    #[test]
+    fn wraps_wide_chars() {
+        assert_eq!(render("日本語 日本語 日本語", Some(14)), "日本語 日本語\n日本語\n");
+    }
+}
@@ -0,0 +1,273 @@
+//! Memoization of expensive output (highlighted code, math, diagrams).
+//!
+//! [RenderCache] maps a hash of the input to the output produced from it,
+//! so the work is only done once for unchanged content. Cache is a cheap
+//! handle, clones share the same entries. Attach it to a renderer, and nodes
+//! will look it up while rendering:
+//!
+//! ```rust
+//! use markdown_it::parser::renderer::cache::RenderCache;
+//!
+//! let md = &mut markdown_it::MarkdownIt::new();
+//! markdown_it::plugins::cmark::add(md);
+//! markdown_it::plugins::jotdown::katex::add(md);
+//!
+//! let cache = RenderCache::new();
+//! let ast = md.parse("$x^2$");
+//! let first = ast.render_cached(&Default::default(), &cache);
+//! let second = ast.render_cached(&Default::default(), &cache);
+//! assert_eq!(first, second);
+//! ```
+//!
+//! Plugins and render overrides use [RenderCache::from_renderer] to do the same:
+//!
+//! ```rust
+//! use markdown_it::parser::extset::RenderOptionsExtSet;
+//! use markdown_it::parser::renderer::cache::{CacheKey, RenderCache};
+//! use markdown_it::parser::renderer::HTMLRenderer;
+//! use markdown_it::plugins::jotdown::diagrams::Diagram;
+//! use markdown_it::{Node, Renderer};
+//!
+//! fn render_svg(source: &str) -> Option<String> {
+//!     // call diagram tool here
+//!     Some(format!("<svg><!-- {} --></svg>", source.len()))
+//! }
+//!
+//! fn diagram(node: &Node, fmt: &mut dyn Renderer, _: &RenderOptionsExtSet) {
+//!     let diagram = node.cast::<Diagram>().unwrap();
+//!     let key = CacheKey::new(&["svg", &diagram.kind, &diagram.source]);
+//!     let svg = match RenderCache::from_renderer(fmt) {
+//!         Some(cache) => cache.get_or_insert_with(key, || render_svg(&diagram.source)),
+//!         None => render_svg(&diagram.source),
+//!     };
+//!     fmt.text_raw(&svg.unwrap_or_default());
+//! }
+//!
+//! let md = &mut markdown_it::MarkdownIt::new();
+//! markdown_it::plugins::cmark::add(md);
+//! markdown_it::plugins::jotdown::diagrams::add(md);
+//!
+//! let mut fmt = HTMLRenderer::new();
+//! fmt.set_override::<Diagram>(diagram);
+//! fmt.ext().insert(RenderCache::new());
+//! fmt.render(&md.parse("```mermaid\na-->b\n```"), &Default::default());
+//! assert_eq!(String::from(fmt), "<svg><!-- 6 --></svg>");
+//! ```
+//!
+//! With [RenderCache::with_dir], entries are also stored in a directory,
+//! so they are reused between program runs.
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use sha2::{Digest, Sha256};
+
+use crate::parser::extset::RenderExt;
+use crate::Renderer;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Content hash identifying cache entry.
+pub struct CacheKey([u8; 32]);
+
+impl CacheKey {
+    /// Hash all the inputs that affect the output. Put a name of the
+    /// output kind first (e.g. `"mathml"`), so different plugins don't
+    /// collide when they get the same content.
+    pub fn new(parts: &[&str]) -> Self {
+        // SHA-256, so crafted input can't collide with cached output of
+        // another document, and hash stays the same between program runs
+        let mut hasher = Sha256::new();
+        for part in parts {
+            // length prefix, so `["ab", "c"]` and `["a", "bc"]` differ
+            hasher.update((part.len() as u64).to_le_bytes());
+            hasher.update(part.as_bytes());
+        }
+        Self(hasher.finalize().into())
+    }
+}
+
+impl fmt::Display for CacheKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct Entry {
+    value: String,
+    // accessed since last `prune()`
+    used: bool,
+}
+
+#[derive(Debug, Default)]
+struct CacheInner {
+    entries: HashMap<CacheKey, Entry>,
+    dir: Option<PathBuf>,
+}
+
+impl CacheInner {
+    fn path(&self, key: CacheKey) -> Option<PathBuf> {
+        Some(self.dir.as_ref()?.join(format!("{key}.html")))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// Cache of rendered output, keyed by [CacheKey].
+pub struct RenderCache(Arc<Mutex<CacheInner>>);
+
+impl RenderExt for RenderCache {}
+
+impl RenderCache {
+    /// Create cache that only keeps entries in memory.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create cache that also stores entries in `dir` (created if missing).
+    ///
+    /// Entries are read from there on memory misses. Failed writes are
+    /// ignored, output is just computed again next time.
+    pub fn with_dir(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self(Arc::new(Mutex::new(CacheInner { entries: HashMap::new(), dir: Some(dir) }))))
+    }
+
+    /// Cache attached to the renderer (see [Renderer::ext]), if any.
+    pub fn from_renderer(fmt: &mut dyn Renderer) -> Option<Self> {
+        fmt.ext().get::<Self>().cloned()
+    }
+
+    /// Get cached output for `key`.
+    pub fn get(&self, key: CacheKey) -> Option<String> {
+        let mut inner = self.0.lock().unwrap();
+
+        if let Some(entry) = inner.entries.get_mut(&key) {
+            entry.used = true;
+            return Some(entry.value.clone());
+        }
+
+        let value = fs::read_to_string(inner.path(key)?).ok()?;
+        inner.entries.insert(key, Entry { value: value.clone(), used: true });
+        Some(value)
+    }
+
+    /// Store output for `key`.
+    pub fn insert(&self, key: CacheKey, value: String) {
+        let mut inner = self.0.lock().unwrap();
+
+        if let Some(path) = inner.path(key) {
+            // write to a temporary file first, so other processes
+            // sharing the directory never read a partial entry
+            let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+            if fs::write(&tmp, &value).and_then(|_| fs::rename(&tmp, &path)).is_err() {
+                let _ = fs::remove_file(&tmp);
+            }
+        }
+
+        inner.entries.insert(key, Entry { value, used: true });
+    }
+
+    /// Get cached output for `key`, or compute it with `f` and store it.
+    ///
+    /// `None` returned by `f` (e.g. input has errors) is not cached.
+    pub fn get_or_insert_with(&self, key: CacheKey, f: impl FnOnce() -> Option<String>) -> Option<String> {
+        if let Some(value) = self.get(key) {
+            return Some(value);
+        }
+
+        // lock isn't held here, so `f` can use the cache too
+        let value = f()?;
+        self.insert(key, value.clone());
+        Some(value)
+    }
+
+    /// Number of entries in memory.
+    pub fn len(&self) -> usize {
+        self.0.lock().unwrap().entries.len()
+    }
+
+    /// Returns true if there are no entries in memory.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove entries from memory that weren't accessed since previous call,
+    /// call it after each render to drop output of edited content.
+    ///
+    /// Files on disk are kept.
+    pub fn prune(&self) {
+        let mut inner = self.0.lock().unwrap();
+        inner.entries.retain(|_, entry| std::mem::take(&mut entry.used));
+    }
+
+    /// Remove all entries from memory, files on disk are kept.
+    pub fn clear(&self) {
+        self.0.lock().unwrap().entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    #[test]
+    fn key() {
+        assert_eq!(CacheKey::new(&["a", "b"]), CacheKey::new(&["a", "b"]));
+        assert_ne!(CacheKey::new(&["ab", "c"]), CacheKey::new(&["a", "bc"]));
+        assert_eq!(CacheKey::new(&[]).to_string(), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        assert_eq!(CacheKey::new(&[""]).to_string(), "af5570f5a1810b7af78caf4bc70a660f0df51e42baf91d4de5b2328de0e83dfc");
+    }
+
+    #[test]
+    fn memoizes() {
+        let calls = Cell::new(0);
+        let compute = || { calls.set(calls.get() + 1); Some("out".to_owned()) };
+
+        let cache = RenderCache::new();
+        let key = CacheKey::new(&["test", "in"]);
+        assert_eq!(cache.get_or_insert_with(key, compute), Some("out".into()));
+        assert_eq!(cache.clone().get_or_insert_with(key, compute), Some("out".into()));
+        assert_eq!(calls.get(), 1);
+
+        assert_eq!(cache.get_or_insert_with(CacheKey::new(&["test", "err"]), || None), None);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn prune() {
+        let cache = RenderCache::new();
+        let a = CacheKey::new(&["a"]);
+        let b = CacheKey::new(&["b"]);
+        cache.insert(a, "a".into());
+        cache.insert(b, "b".into());
+        cache.prune();
+        assert_eq!(cache.len(), 2);
+
+        cache.get(a);
+        cache.prune();
+        assert_eq!(cache.get(a), Some("a".into()));
+        assert_eq!(cache.get(b), None);
+    }
+
+    #[test]
+    fn persists() {
+        let dir = std::env::temp_dir().join(format!("markdown-it-cache-{}", std::process::id()));
+        let key = CacheKey::new(&["test", "disk"]);
+
+        RenderCache::with_dir(&dir).unwrap().insert(key, "saved".into());
+        let cache = RenderCache::with_dir(&dir).unwrap();
+        assert!(cache.is_empty());
+        assert_eq!(cache.get_or_insert_with(key, || None), Some("saved".into()));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
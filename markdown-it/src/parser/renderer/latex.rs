@@ -0,0 +1,630 @@
+//! LaTeX renderer, for exporting documents to PDF with a TeX toolchain.
+//!
+//! Headings become sections, lists become `itemize` and `enumerate`,
+//! code is written in `verbatim` (or `lstlisting`) environments, tables
+//! become `tabular`, footnotes become `\footnote`, and math is passed
+//! through as is. Special characters in text are escaped.
+//!
+//! ```rust
+//! use markdown_it::parser::renderer::latex::LatexRenderer;
+//!
+//! let md = &mut markdown_it::MarkdownIt::new();
+//! markdown_it::plugins::cmark::add(md);
+//!
+//! let mut fmt = LatexRenderer::new();
+//! fmt.render(&md.parse("# Costs\n\n- *50%* of [budget](https://example.org)"));
+//! assert_eq!(String::from(fmt), "\\section{Costs}\n\n\\begin{itemize}\n\
+//!     \\item \\emph{50\\%} of \\href{https://example.org}{budget}\n\\end{itemize}\n");
+//! ```
+//!
+//! Output uses `hyperref`, `graphicx`, `amsmath`, `amssymb` and `ulem` packages
+//! (and `listings` if enabled), [LatexRendererOptions::standalone] adds
+//! a preamble with them. Raw html is skipped. Nodes without a handler
+//! output their children, plugins can register their own handlers with
+//! [LatexRenderer::set_handler].
+//!
+//! Syntax highlighting plugin replaces code fences with html, so it shouldn't
+//! be enabled when exporting to LaTeX.
+use std::collections::{HashMap, HashSet};
+
+use crate::common::TypeKey;
+use crate::generics::block::fence_handler::get_language;
+use crate::parser::core::Root;
+use crate::parser::extset::RenderExtSet;
+use crate::parser::inline::{Text, TextSpecial};
+use crate::plugins::cmark::block::blockquote::Blockquote;
+use crate::plugins::cmark::block::code::CodeBlock;
+use crate::plugins::cmark::block::fence::CodeFence;
+use crate::plugins::cmark::block::heading::ATXHeading;
+use crate::plugins::cmark::block::hr::ThematicBreak;
+use crate::plugins::cmark::block::lheading::SetextHeader;
+use crate::plugins::cmark::block::list::{BulletList, ListItem, OrderedList};
+use crate::plugins::cmark::block::paragraph::Paragraph;
+use crate::plugins::cmark::block::reference::Definition;
+use crate::plugins::cmark::inline::autolink::Autolink;
+use crate::plugins::cmark::inline::backticks::CodeInline;
+use crate::plugins::cmark::inline::emphasis::{Em, Strong};
+use crate::plugins::cmark::inline::image::Image;
+use crate::plugins::cmark::inline::link::Link;
+use crate::plugins::cmark::inline::newline::{Hardbreak, Softbreak};
+use crate::plugins::extra::strikethrough::Strikethrough;
+use crate::plugins::extra::tables::{ColumnAlignment, Table, TableBody, TableCell, TableHead, TableRow};
+use crate::plugins::gfm::heading_anchors::HeadingAnchor;
+use crate::plugins::gfm::tasklist::TodoCheckbox;
+use crate::plugins::html::html_block::HtmlBlock;
+use crate::plugins::html::html_inline::HtmlInline;
+use crate::plugins::jotdown::diagrams::Diagram;
+use crate::plugins::jotdown::katex::block::MathBlock;
+use crate::plugins::jotdown::katex::equations::{EquationNumber, EquationRef};
+use crate::plugins::jotdown::katex::inline::InlineMath;
+use crate::plugins::jotdown::katex::prepared_tex;
+use crate::plugins::pandoc::deflist::{DefinitionDescription, DefinitionList, DefinitionTerm};
+use crate::plugins::pandoc::footnote::back_refs::FootnoteRefAnchor;
+use crate::plugins::pandoc::footnote::collect::FootnotesContainerNode;
+use crate::plugins::pandoc::footnote::definitions::FootnoteDefinition;
+use crate::plugins::pandoc::footnote::references::FootnoteReference;
+use crate::{Node, NodeValue};
+
+/// Function that writes LaTeX for a node.
+pub type LatexHandler = fn (node: &Node, fmt: &mut LatexRenderer);
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+/// Options for [LatexRenderer].
+pub struct LatexRendererOptions {
+    /// Write code in `lstlisting` environment with language from the fence
+    /// info string, instead of `verbatim`.
+    pub listings: bool,
+    /// Wrap the document in `\documentclass{article}` with required packages.
+    pub standalone: bool,
+}
+
+#[derive(Debug)]
+/// Renderer that outputs LaTeX.
+pub struct LatexRenderer {
+    result: String,
+    ext: RenderExtSet,
+    options: LatexRendererOptions,
+    handlers: HashMap<TypeKey, LatexHandler>,
+
+    // newlines required before the next block
+    pending_newlines: usize,
+    // right after `\item`, first block continues the line
+    item_start: bool,
+    enumerate_depth: usize,
+    // footnote text and number of references, by definition id
+    footnotes: HashMap<usize, (String, usize)>,
+    written_footnotes: HashSet<usize>,
+}
+
+impl LatexRenderer {
+    pub fn new() -> Self {
+        Self::with_options(LatexRendererOptions::default())
+    }
+
+    pub fn with_options(options: LatexRendererOptions) -> Self {
+        let mut result = Self {
+            result: String::new(),
+            ext: RenderExtSet::new(),
+            options,
+            handlers: HashMap::new(),
+            pending_newlines: 0,
+            item_start: false,
+            enumerate_depth: 0,
+            footnotes: HashMap::new(),
+            written_footnotes: HashSet::new(),
+        };
+
+        result.set_handler::<Root>(root);
+        result.set_handler::<Text>(|node, fmt| fmt.text(&node.cast::<Text>().unwrap().content));
+        result.set_handler::<TextSpecial>(|node, fmt| fmt.text(&node.cast::<TextSpecial>().unwrap().content));
+
+        // commonmark blocks
+        result.set_handler::<Paragraph>(|node, fmt| {
+            fmt.block_start();
+            fmt.contents(&node.children);
+            fmt.block_end();
+        });
+        result.set_handler::<ATXHeading>(|node, fmt| heading(node, fmt, node.cast::<ATXHeading>().unwrap().level));
+        result.set_handler::<SetextHeader>(|node, fmt| heading(node, fmt, node.cast::<SetextHeader>().unwrap().level));
+        result.set_handler::<ThematicBreak>(|_, fmt| {
+            fmt.block_start();
+            fmt.write("\\noindent\\rule{\\linewidth}{0.4pt}");
+            fmt.block_end();
+        });
+        result.set_handler::<CodeBlock>(|node, fmt| code(fmt, "", &node.cast::<CodeBlock>().unwrap().content));
+        result.set_handler::<CodeFence>(|node, fmt| {
+            let data = node.cast::<CodeFence>().unwrap();
+            code(fmt, &get_language(&data.info), &data.content);
+        });
+        result.set_handler::<Blockquote>(|node, fmt| {
+            fmt.begin("quote", "");
+            fmt.contents(&node.children);
+            fmt.end("quote");
+        });
+        result.set_handler::<BulletList>(|node, fmt| {
+            fmt.begin("itemize", "");
+            fmt.contents(&node.children);
+            fmt.end("itemize");
+        });
+        result.set_handler::<OrderedList>(ordered_list);
+        result.set_handler::<ListItem>(list_item);
+        result.set_handler::<Definition>(|_, _| {});
+        result.set_handler::<HtmlBlock>(|_, _| {});
+
+        // commonmark inlines
+        result.set_handler::<Em>(|node, fmt| fmt.command("emph", node));
+        result.set_handler::<Strong>(|node, fmt| fmt.command("textbf", node));
+        result.set_handler::<CodeInline>(|node, fmt| {
+            fmt.write("\\texttt{");
+            fmt.text(&node.collect_text());
+            fmt.write("}");
+        });
+        result.set_handler::<Link>(|node, fmt| href(node, fmt, &node.cast::<Link>().unwrap().url));
+        result.set_handler::<Autolink>(|node, fmt| href(node, fmt, &node.cast::<Autolink>().unwrap().url));
+        result.set_handler::<Image>(|node, fmt| {
+            let data = node.cast::<Image>().unwrap();
+            fmt.write(&format!("\\includegraphics{{{}}}", escape_url(&data.url)));
+        });
+        result.set_handler::<Hardbreak>(|_, fmt| fmt.write("\\\\\n"));
+        result.set_handler::<Softbreak>(|_, fmt| fmt.write("\n"));
+        result.set_handler::<HtmlInline>(|_, _| {});
+
+        // extensions
+        result.set_handler::<Strikethrough>(|node, fmt| fmt.command("sout", node));
+        result.set_handler::<Table>(table);
+        result.set_handler::<DefinitionList>(|node, fmt| {
+            fmt.begin("description", "");
+            fmt.contents(&node.children);
+            fmt.end("description");
+        });
+        result.set_handler::<DefinitionTerm>(|node, fmt| {
+            let term = fmt.capture(|fmt| fmt.contents(&node.children));
+            fmt.block_start();
+            fmt.write(&format!("\\item[{{{term}}}]"));
+            fmt.pending_newlines = 1;
+        });
+        result.set_handler::<DefinitionDescription>(|node, fmt| {
+            fmt.block_start();
+            fmt.contents(&node.children);
+            fmt.block_end();
+        });
+        result.set_handler::<FootnoteReference>(footnote_reference);
+        result.set_handler::<MathBlock>(math_block);
+        result.set_handler::<InlineMath>(|node, fmt| {
+            let data = node.cast::<InlineMath>().unwrap();
+            fmt.write(&format!("\\({}\\)", prepared_tex(node, &data.content)));
+        });
+        result.set_handler::<EquationRef>(|node, fmt| {
+            fmt.write(&format!("\\eqref{{{}}}", node.cast::<EquationRef>().unwrap().label));
+        });
+        result.set_handler::<Diagram>(|node, fmt| code(fmt, "", &node.cast::<Diagram>().unwrap().source));
+        #[cfg(feature = "linkify")]
+        result.set_handler::<crate::plugins::extra::linkify::Linkified>(|node, fmt| {
+            href(node, fmt, &node.cast::<crate::plugins::extra::linkify::Linkified>().unwrap().url);
+        });
+
+        // written at the place of their references
+        result.set_handler::<FootnotesContainerNode>(|_, _| {});
+        result.set_handler::<FootnoteRefAnchor>(|_, _| {});
+        result.set_handler::<HeadingAnchor>(|_, _| {});
+
+        result
+    }
+
+    /// Write all nodes of type `T` with function `f`.
+    pub fn set_handler<T: NodeValue>(&mut self, f: LatexHandler) {
+        self.handlers.insert(TypeKey::of::<T>(), f);
+    }
+
+    /// Remove handler for type `T`, so only children of its nodes are written.
+    pub fn remove_handler<T: NodeValue>(&mut self) {
+        self.handlers.remove(&TypeKey::of::<T>());
+    }
+
+    /// Render node (and its children) appending result to the buffer.
+    pub fn render(&mut self, node: &Node) {
+        self.collect_footnotes(node);
+        self.render_node(node);
+    }
+
+    fn render_node(&mut self, node: &Node) {
+        if let Some(f) = self.handlers.get(&node.node_type).copied() {
+            f(node, self);
+        } else {
+            self.contents(&node.children);
+        }
+    }
+
+    /// Render each node in the list.
+    pub fn contents(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            self.render_node(node);
+        }
+    }
+
+    /// Start a new block on a new line, separating it from previous one.
+    pub fn block_start(&mut self) {
+        let newlines = std::mem::take(&mut self.pending_newlines);
+        if std::mem::take(&mut self.item_start) || self.result.is_empty() { return; }
+
+        let newlines = if self.result.ends_with('\n') { newlines.saturating_sub(1) } else { newlines.max(1) };
+        for _ in 0..newlines {
+            self.result.push('\n');
+        }
+    }
+
+    /// Finish a block, next one will be separated by an empty line.
+    pub fn block_end(&mut self) {
+        self.pending_newlines = 2;
+    }
+
+    /// Start environment, `\begin{name}args`.
+    pub fn begin(&mut self, name: &str, args: &str) {
+        self.block_start();
+        self.write(&format!("\\begin{{{name}}}{args}"));
+        self.pending_newlines = 1;
+    }
+
+    /// Finish environment, `\end{name}`.
+    pub fn end(&mut self, name: &str) {
+        self.pending_newlines = 1;
+        self.block_start();
+        self.write(&format!("\\end{{{name}}}"));
+        self.block_end();
+    }
+
+    /// Write children of the node as an argument of command, `\name{...}`.
+    pub fn command(&mut self, name: &str, node: &Node) {
+        self.write(&format!("\\{name}{{"));
+        self.contents(&node.children);
+        self.write("}");
+    }
+
+    /// Render something into a separate buffer and return it.
+    pub fn capture(&mut self, f: impl FnOnce(&mut Self)) -> String {
+        let result = std::mem::take(&mut self.result);
+        let pending_newlines = std::mem::take(&mut self.pending_newlines);
+        let item_start = std::mem::take(&mut self.item_start);
+
+        f(self);
+
+        self.pending_newlines = pending_newlines;
+        self.item_start = item_start;
+        std::mem::replace(&mut self.result, result)
+    }
+
+    /// Write LaTeX as is.
+    pub fn write(&mut self, text: &str) {
+        if text.is_empty() { return; }
+        self.item_start = false;
+        self.result.push_str(text);
+    }
+
+    /// Write plain text, escaping TeX special characters.
+    pub fn text(&mut self, text: &str) {
+        self.write(&escape(text));
+    }
+
+    /// Extension set to store custom stuff.
+    pub fn ext(&mut self) -> &mut RenderExtSet {
+        &mut self.ext
+    }
+
+    // footnotes are written where they are referenced, so their text
+    // is prepared in advance
+    fn collect_footnotes(&mut self, root: &Node) {
+        let mut definitions = Vec::new();
+        let mut references = HashMap::<usize, usize>::new();
+        root.walk(|node, _| {
+            if let Some(data) = node.cast::<FootnoteDefinition>() {
+                if let Some(def_id) = data.def_id {
+                    definitions.push((def_id, node));
+                }
+            } else if let Some(data) = node.cast::<FootnoteReference>() {
+                *references.entry(data.def_id).or_default() += 1;
+            }
+        });
+
+        for (def_id, node) in definitions {
+            let content = self.capture(|fmt| fmt.contents(&node.children));
+            let count = references.get(&def_id).copied().unwrap_or_default();
+            self.footnotes.insert(def_id, (content.trim().to_owned(), count));
+        }
+    }
+}
+
+impl Default for LatexRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<LatexRenderer> for String {
+    fn from(fmt: LatexRenderer) -> Self {
+        let mut result = fmt.result;
+        if !result.is_empty() && !result.ends_with('\n') {
+            result.push('\n');
+        }
+        result
+    }
+}
+
+/// Escape TeX special characters in text.
+pub fn escape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '\\' => result.push_str("\\textbackslash{}"),
+            '^' => result.push_str("\\textasciicircum{}"),
+            '~' => result.push_str("\\textasciitilde{}"),
+            // wrong glyphs in the default font encoding
+            '<' => result.push_str("\\textless{}"),
+            '>' => result.push_str("\\textgreater{}"),
+            '|' => result.push_str("\\textbar{}"),
+            '{' | '}' | '$' | '&' | '#' | '_' | '%' => {
+                result.push('\\');
+                result.push(ch);
+            }
+            _ => result.push(ch),
+        }
+    }
+    result
+}
+
+// urls are written almost verbatim by hyperref
+fn escape_url(url: &str) -> String {
+    let mut result = String::with_capacity(url.len());
+    for ch in url.chars() {
+        if matches!(ch, '\\' | '{' | '}' | '#' | '%') {
+            result.push('\\');
+        }
+        result.push(ch);
+    }
+    result
+}
+
+fn root(node: &Node, fmt: &mut LatexRenderer) {
+    if !fmt.options.standalone {
+        fmt.contents(&node.children);
+        return;
+    }
+
+    fmt.write("\\documentclass{article}\n");
+    fmt.write("\\usepackage[T1]{fontenc}\n");
+    fmt.write("\\usepackage{amsmath,amssymb}\n");
+    fmt.write("\\usepackage{graphicx}\n");
+    fmt.write("\\usepackage[normalem]{ulem}\n");
+    if fmt.options.listings {
+        fmt.write("\\usepackage{listings}\n");
+    }
+    fmt.write("\\usepackage{hyperref}\n");
+    fmt.begin("document", "");
+    fmt.contents(&node.children);
+    fmt.end("document");
+}
+
+fn heading(node: &Node, fmt: &mut LatexRenderer, level: u8) {
+    let command = match level {
+        1 => "section",
+        2 => "subsection",
+        3 => "subsubsection",
+        4 => "paragraph",
+        _ => "subparagraph",
+    };
+
+    let id = node.attrs.iter()
+        .find(|(name, _)| *name == "id")
+        .map(|(_, value)| value.clone())
+        .or_else(|| node.children.iter().find_map(|child| child.cast::<HeadingAnchor>()?.id.clone()));
+
+    fmt.block_start();
+    fmt.command(command, node);
+    if let Some(id) = id {
+        fmt.write(&format!("\\label{{{}}}", escape_url(&id)));
+    }
+    fmt.block_end();
+}
+
+fn code(fmt: &mut LatexRenderer, language: &str, content: &str) {
+    let (name, args) = if !fmt.options.listings {
+        ("verbatim", String::new())
+    } else if language.is_empty() || !language.chars().all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '+' | '#' | '-')) {
+        ("lstlisting", String::new())
+    } else {
+        ("lstlisting", format!("[language={language}]"))
+    };
+
+    // `\end{verbatim}` inside of code would close the environment,
+    // so it's split into two environments between `\` and `end`
+    let end = format!("\\end{{{name}}}");
+    let mut content = content.trim_end_matches('\n');
+
+    fmt.begin(name, &args);
+    fmt.block_start();
+    while let Some(pos) = content.find(&end) {
+        fmt.write(&content[..pos + 1]);
+        fmt.end(name);
+        fmt.begin(name, &args);
+        fmt.block_start();
+        content = &content[pos + 1..];
+    }
+    fmt.write(content);
+    fmt.end(name);
+}
+
+fn ordered_list(node: &Node, fmt: &mut LatexRenderer) {
+    let data = node.cast::<OrderedList>().unwrap();
+    fmt.enumerate_depth += 1;
+
+    fmt.begin("enumerate", "");
+    if data.start != 1 && fmt.enumerate_depth <= 4 {
+        let counter = ["enumi", "enumii", "enumiii", "enumiv"][fmt.enumerate_depth - 1];
+        fmt.block_start();
+        fmt.write(&format!("\\setcounter{{{counter}}}{{{}}}", data.start as i64 - 1));
+        fmt.pending_newlines = 1;
+    }
+    fmt.contents(&node.children);
+    fmt.end("enumerate");
+
+    fmt.enumerate_depth -= 1;
+}
+
+fn list_item(node: &Node, fmt: &mut LatexRenderer) {
+    let mut children = &node.children[..];
+
+    fmt.pending_newlines = 1;
+    fmt.block_start();
+    fmt.write("\\item");
+    if let Some(checkbox) = children.first().and_then(|child| child.cast::<TodoCheckbox>()) {
+        fmt.write(if checkbox.checked { "[$\\boxtimes$]" } else { "[$\\square$]" });
+        children = &children[1..];
+    }
+    // text after checkbox keeps its leading space
+    if !children.first().and_then(|child| child.cast::<Text>()).is_some_and(|text| text.content.starts_with(' ')) {
+        fmt.write(" ");
+    }
+    fmt.item_start = true;
+
+    fmt.contents(children);
+    fmt.item_start = false;
+    fmt.block_end();
+}
+
+fn href(node: &Node, fmt: &mut LatexRenderer, url: &str) {
+    match url.strip_prefix('#') {
+        Some(id) => fmt.write(&format!("\\hyperref[{}]{{", escape_url(id))),
+        None => fmt.write(&format!("\\href{{{}}}{{", escape_url(url))),
+    }
+    fmt.contents(&node.children);
+    fmt.write("}");
+}
+
+fn table(node: &Node, fmt: &mut LatexRenderer) {
+    let data = node.cast::<Table>().unwrap();
+    let columns = data.alignments.iter().map(|alignment| match alignment {
+        ColumnAlignment::None | ColumnAlignment::Left => 'l',
+        ColumnAlignment::Right => 'r',
+        ColumnAlignment::Center => 'c',
+    }).collect::<String>();
+
+    fmt.begin("tabular", &format!("{{{columns}}}"));
+    fmt.block_start();
+    fmt.write("\\hline");
+    for section in node.children.iter() {
+        if !section.is::<TableHead>() && !section.is::<TableBody>() { continue; }
+        for row in section.children.iter().filter(|row| row.is::<TableRow>()) {
+            let mut cells = row.children.iter()
+                .filter(|cell| cell.is::<TableCell>())
+                .map(|cell| fmt.capture(|fmt| fmt.contents(&cell.children)))
+                .collect::<Vec<_>>();
+            cells.resize(data.alignments.len(), String::new());
+
+            fmt.write(&format!("\n{} \\\\", cells.join(" & ")));
+        }
+        fmt.write("\n\\hline");
+    }
+    fmt.end("tabular");
+}
+
+fn footnote_reference(node: &Node, fmt: &mut LatexRenderer) {
+    let data = node.cast::<FootnoteReference>().unwrap();
+    let Some((content, count)) = fmt.footnotes.get(&data.def_id).cloned() else { return };
+    let label = format!("fn:{}", data.def_id);
+
+    if !fmt.written_footnotes.insert(data.def_id) {
+        // already written, refer to its number
+        fmt.write(&format!("\\textsuperscript{{\\ref{{{label}}}}}"));
+    } else if count > 1 {
+        fmt.write(&format!("\\footnote{{\\label{{{label}}}{content}}}"));
+    } else {
+        fmt.write(&format!("\\footnote{{{content}}}"));
+    }
+}
+
+fn math_block(node: &Node, fmt: &mut LatexRenderer) {
+    let data = node.cast::<MathBlock>().unwrap();
+    // macro definitions are removed by core rule and expanded in place
+    let tex = prepared_tex(node, &data.content).trim();
+    if tex.is_empty() { return; }
+
+    match node.ext.get::<EquationNumber>() {
+        Some(number) => {
+            fmt.begin("equation", "");
+            fmt.block_start();
+            fmt.write(&format!("\\label{{{}}}\n{tex}", number.label));
+            fmt.end("equation");
+        }
+        None => {
+            fmt.block_start();
+            fmt.write(&format!("\\[\n{tex}\n\\]"));
+            fmt.block_end();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::plugins::{cmark, extra, gfm, jotdown, pandoc};
+    use crate::MarkdownIt;
+
+    use super::*;
+
+    fn render(src: &str, options: LatexRendererOptions) -> String {
+        let mut md = MarkdownIt::new();
+        cmark::add(&mut md);
+        extra::tables::add(&mut md);
+        extra::strikethrough::add(&mut md);
+        gfm::tasklist::add(&mut md);
+        pandoc::add(&mut md);
+        jotdown::katex::add(&mut md);
+
+        let mut fmt = LatexRenderer::with_options(options);
+        fmt.render(&md.parse(src));
+        fmt.into()
+    }
+
+    #[rstest]
+    #[case("# A\n\n## B {#b}\n\n#### C", "\\section{A}\n\n\\subsection{B}\\label{b}\n\n\\paragraph{C}\n")]
+    #[case("a & b $5 #1 50% x_y {z} ~ ^ \\\\ <>|", "a \\& b \\$5 \\#1 50\\% x\\_y \\{z\\} \\textasciitilde{} \\textasciicircum{} \\textbackslash{} \\textless{}\\textgreater{}\\textbar{}\n")]
+    #[case("*a* **b** ~~c~~ `d_e`", "\\emph{a} \\textbf{b} \\sout{c} \\texttt{d\\_e}\n")]
+    #[case("foo  \nbar\nbaz\n\n***", "foo\\\\\nbar\nbaz\n\n\\noindent\\rule{\\linewidth}{0.4pt}\n")]
+    #[case("[a](https://x.org/#q%20) <https://y.org> [b](#sec) ![c](d.png)",
+        "\\href{https://x.org/\\#q\\%20}{a} \\href{https://y.org}{https://y.org} \\hyperref[sec]{b} \\includegraphics{d.png}\n")]
+    #[case("> quote\n>\n> more", "\\begin{quote}\nquote\n\nmore\n\\end{quote}\n")]
+    #[case("- a\n- b\n  1. c\n\n     d", "\\begin{itemize}\n\\item a\n\\item b\n\\begin{enumerate}\n\\item c\n\nd\n\\end{enumerate}\n\\end{itemize}\n")]
+    #[case("3. a\n4. b", "\\begin{enumerate}\n\\setcounter{enumi}{2}\n\\item a\n\\item b\n\\end{enumerate}\n")]
+    #[case("- [x] a\n- [ ] b", "\\begin{itemize}\n\\item[$\\boxtimes$] a\n\\item[$\\square$] b\n\\end{itemize}\n")]
+    #[case("```rust\nfn main() {}\n```", "\\begin{verbatim}\nfn main() {}\n\\end{verbatim}\n")]
+    #[case("```\na\\end{verbatim}\\input{x}\n\\end{verbatim}\n```",
+        "\\begin{verbatim}\na\\\n\\end{verbatim}\n\n\\begin{verbatim}\nend{verbatim}\\input{x}\n\\\n\\end{verbatim}\n\n\\begin{verbatim}\nend{verbatim}\n\\end{verbatim}\n")]
+    #[case("| a | b |\n|---|:-:|\n| 1 | 2 |", "\\begin{tabular}{lc}\n\\hline\na & b \\\\\n\\hline\n1 & 2 \\\\\n\\hline\n\\end{tabular}\n")]
+    #[case("term\n: def", "\\begin{description}\n\\item[{term}]\ndef\n\\end{description}\n")]
+    #[case("a[^1] b^[inline] c[^1]\n\n[^1]: note *x*",
+        "a\\footnote{\\label{fn:1}note \\emph{x}} b\\footnote{inline} c\\textsuperscript{\\ref{fn:1}}\n")]
+    #[case("$x^2$ and\n\n$$\n\\newcommand{\\R}{\\mathbb{R}}\n$$\n\n$$\nx \\in \\R \\label{eq:a}\n$$\n\nsee \\eqref{eq:a}",
+        "\\(x^2\\) and\n\n\\begin{equation}\n\\label{eq:a}\nx \\in \\mathbb{R}\n\\end{equation}\n\nsee \\eqref{eq:a}\n")]
+    fn latex(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(render(input, LatexRendererOptions::default()), expected);
+    }
+
+    #[test]
+    fn listings() {
+        let options = LatexRendererOptions { listings: true, ..Default::default() };
+        assert_eq!(render("```rust\nfn main() {}\n```", options), "\\begin{lstlisting}[language=rust]\nfn main() {}\n\\end{lstlisting}\n");
+        assert_eq!(render("    code", options), "\\begin{lstlisting}\ncode\n\\end{lstlisting}\n");
+        assert_eq!(render("```c#\nx\n```", options), "\\begin{lstlisting}[language=c#]\nx\n\\end{lstlisting}\n");
+        assert_eq!(render("```a]{x}\\input{y}\nx\n```", options), "\\begin{lstlisting}\nx\n\\end{lstlisting}\n");
+        assert_eq!(render("```\n\\end{lstlisting}\\input{x}\n```", options),
+            "\\begin{lstlisting}\n\\\n\\end{lstlisting}\n\n\\begin{lstlisting}\nend{lstlisting}\\input{x}\n\\end{lstlisting}\n");
+    }
+
+    #[test]
+    fn standalone() {
+        let options = LatexRendererOptions { standalone: true, ..Default::default() };
+        let result = render("text", options);
+        assert!(result.starts_with("\\documentclass{article}\n"));
+        assert!(result.ends_with("\\usepackage{hyperref}\n\\begin{document}\ntext\n\\end{document}\n"));
+    }
+}
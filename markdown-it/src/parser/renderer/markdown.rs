@@ -0,0 +1,1461 @@
+//! Markdown renderer, turns AST back into CommonMark.
+//!
+//! Useful when documents are edited programmatically: parse the source,
+//! change the tree and write it back. Output keeps list and fence markers
+//! of the original source, and special characters in text are escaped,
+//! so parsing the result gives an equivalent tree.
+//!
+//! ```rust
+//! use markdown_it::parser::renderer::markdown::MarkdownRenderer;
+//! use markdown_it::plugins::gfm::tasklist::TodoCheckbox;
+//!
+//! let md = &mut markdown_it::MarkdownIt::new();
+//! markdown_it::plugins::cmark::add(md);
+//! markdown_it::plugins::gfm::tasklist::add(md);
+//!
+//! let mut ast = md.parse("* [ ] write *docs*\n* [x] ship it");
+//! ast.walk_mut(|node, _| {
+//!     if let Some(checkbox) = node.cast_mut::<TodoCheckbox>() {
+//!         checkbox.checked = !checkbox.checked;
+//!     }
+//! });
+//!
+//! let mut fmt = MarkdownRenderer::new();
+//! fmt.render(&ast);
+//! assert_eq!(String::from(fmt), "* [x] write *docs*\n* [ ] ship it\n");
+//! ```
+//!
+//! Nodes without a handler are written as raw html, plugins can register
+//! their own with [MarkdownRenderer::set_handler]. Attributes of headings,
+//! code fences, links and images are written as pandoc attribute blocks
+//! (`{#id .class key="value"}`), see [attrs](crate::plugins::pandoc::attrs).
+//!
+//! The same renderer works as a formatter, normalizing style of a document
+//! according to [MarkdownRendererOptions]. Formatting is idempotent,
+//! formatting the output again doesn't change it.
+//!
+//! ```rust
+//! use markdown_it::parser::renderer::markdown::{MarkdownRenderer, MarkdownRendererOptions};
+//!
+//! let md = &mut markdown_it::MarkdownIt::new();
+//! markdown_it::plugins::cmark::add(md);
+//!
+//! let options = MarkdownRendererOptions { wrap: Some(20), ..MarkdownRendererOptions::formatter() };
+//! let mut fmt = MarkdownRenderer::with_options(options);
+//! fmt.render(&md.parse("Title\n=====\n\n+ some _text_ in a list item"));
+//! assert_eq!(String::from(fmt), "# Title\n\n- some *text* in a\n  list item\n");
+//! ```
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::common::TypeKey;
+use crate::parser::core::Root;
+use crate::parser::extset::{RenderExt, RenderExtSet};
+use crate::parser::inline::{Text, TextSpecial};
+use crate::plugins::cmark::block::blockquote::Blockquote;
+use crate::plugins::cmark::block::code::CodeBlock;
+use crate::plugins::cmark::block::fence::CodeFence;
+use crate::plugins::cmark::block::heading::ATXHeading;
+use crate::plugins::cmark::block::hr::ThematicBreak;
+use crate::plugins::cmark::block::lheading::SetextHeader;
+use crate::plugins::cmark::block::list::{BulletList, ListItem, OrderedList};
+use crate::plugins::cmark::block::paragraph::Paragraph;
+use crate::plugins::cmark::block::reference::Definition;
+use crate::plugins::cmark::inline::autolink::Autolink;
+use crate::plugins::cmark::inline::backticks::CodeInline;
+use crate::plugins::cmark::inline::emphasis::{Em, Strong};
+use crate::plugins::cmark::inline::image::Image;
+use crate::plugins::cmark::inline::link::Link;
+use crate::plugins::cmark::inline::newline::{Hardbreak, Softbreak};
+use crate::plugins::extra::strikethrough::Strikethrough;
+use crate::plugins::extra::tables::{ColumnAlignment, Table, TableBody, TableCell, TableHead, TableRow};
+use crate::plugins::gfm::heading_anchors::HeadingAnchor;
+use crate::plugins::gfm::tasklist::TodoCheckbox;
+use crate::plugins::html::html_block::HtmlBlock;
+use crate::plugins::html::html_inline::HtmlInline;
+use crate::plugins::jotdown::diagrams::Diagram;
+use crate::plugins::jotdown::katex::block::{MathBlock, MathDelimiter};
+use crate::plugins::jotdown::katex::equations::EquationRef;
+use crate::plugins::jotdown::katex::inline::{InlineMath, InlineMathDelimiter};
+use crate::plugins::pandoc::attrs::BracketedSpan;
+use crate::plugins::pandoc::deflist::{DefinitionDescription, DefinitionList, DefinitionTerm};
+use crate::plugins::pandoc::footnote::back_refs::FootnoteRefAnchor;
+use crate::plugins::pandoc::footnote::collect::FootnotesContainerNode;
+use crate::plugins::pandoc::footnote::definitions::FootnoteDefinition;
+use crate::plugins::pandoc::footnote::inline::InlineFootnote;
+use crate::plugins::pandoc::footnote::references::FootnoteReference;
+use crate::{Node, NodeValue};
+
+use super::HTMLRenderer;
+
+/// Function that writes markdown for a node.
+pub type MarkdownHandler = fn (node: &Node, fmt: &mut MarkdownRenderer);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Heading syntax, see [MarkdownRendererOptions::heading_style].
+pub enum HeadingStyle {
+    /// `# Title`
+    Atx,
+    /// `Title` underlined with `===` or `---`, only for levels 1 and 2.
+    Setext,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+/// Numbers of ordered list items, see [MarkdownRendererOptions::numbering].
+pub enum ListNumbering {
+    #[default]
+    /// `1.`, `2.`, `3.`
+    Increment,
+    /// Start number for every item, `1.`, `1.`, `1.`
+    Repeat,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+/// Options for [MarkdownRenderer].
+///
+/// Defaults keep the style of the source, [formatter](Self::formatter)
+/// returns an opinionated set for normalizing documents.
+pub struct MarkdownRendererOptions {
+    /// Heading syntax, `None` keeps the source style.
+    pub heading_style: Option<HeadingStyle>,
+    /// Bullet list markers by nesting level, the last one is used for deeper
+    /// lists. Empty keeps the source markers.
+    pub bullets: Vec<char>,
+    /// Emphasis marker (`*` or `_`), `None` keeps the source marker.
+    pub emphasis: Option<char>,
+    /// Strong emphasis marker (`*` or `_`), `None` keeps the source marker.
+    pub strong: Option<char>,
+    /// Pad table cells, so columns are aligned according to [Table::alignments].
+    pub align_tables: bool,
+    pub numbering: ListNumbering,
+    /// Reflow paragraphs to fit into this many characters, `None` keeps
+    /// the source line breaks. Long words and code spans are never split.
+    pub wrap: Option<usize>,
+}
+
+impl MarkdownRendererOptions {
+    /// ATX headings, `-` bullets, `*` emphasis and aligned tables.
+    pub fn formatter() -> Self {
+        Self {
+            heading_style: Some(HeadingStyle::Atx),
+            bullets: vec!['-'],
+            emphasis: Some('*'),
+            strong: Some('*'),
+            align_tables: true,
+            numbering: ListNumbering::Increment,
+            wrap: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+// line prefix of a block container, e.g. `> ` for blockquotes
+struct Prefix {
+    text: String,
+    // replaces `text` on the first line, e.g. list marker
+    first_line: Option<String>,
+    // length of the output when container was opened
+    start: usize,
+}
+
+#[derive(Debug)]
+/// Renderer that outputs markdown.
+pub struct MarkdownRenderer {
+    result: String,
+    ext: RenderExtSet,
+    options: MarkdownRendererOptions,
+    handlers: HashMap<TypeKey, MarkdownHandler>,
+
+    prefixes: Vec<Prefix>,
+    // newlines required before the next block
+    pending_newlines: usize,
+    line_start: bool,
+    tight: bool,
+    inline: bool,
+    // first character of the next sibling, if it is text
+    next_char: Option<char>,
+    // first character after the enclosing emphasis, if it is text
+    emphasis_next_char: Option<char>,
+    // spaces on the current line where it can be wrapped
+    breaks: Vec<usize>,
+    wrapping: bool,
+    bullet_depth: usize,
+    // end of the last bullet list and its marker, adjacent lists need different ones
+    last_bullet: Option<(usize, char)>,
+    // end of the last list, indented code right after it would continue the list
+    last_list: Option<usize>,
+}
+
+impl MarkdownRenderer {
+    pub fn new() -> Self {
+        Self::with_options(MarkdownRendererOptions::default())
+    }
+
+    pub fn with_options(options: MarkdownRendererOptions) -> Self {
+        let mut result = Self {
+            result: String::new(),
+            ext: RenderExtSet::new(),
+            wrapping: options.wrap.is_some(),
+            options,
+            handlers: HashMap::new(),
+            prefixes: Vec::new(),
+            pending_newlines: 0,
+            line_start: true,
+            tight: false,
+            inline: false,
+            next_char: None,
+            emphasis_next_char: None,
+            breaks: Vec::new(),
+            bullet_depth: 0,
+            last_bullet: None,
+            last_list: None,
+        };
+
+        result.set_handler::<Root>(|node, fmt| fmt.contents(&node.children));
+        result.set_handler::<Text>(|node, fmt| fmt.text(&node.cast::<Text>().unwrap().content));
+        result.set_handler::<TextSpecial>(text_special);
+
+        // commonmark blocks
+        result.set_handler::<Paragraph>(paragraph);
+        result.set_handler::<ATXHeading>(atx_heading);
+        result.set_handler::<SetextHeader>(setext_heading);
+        result.set_handler::<ThematicBreak>(thematic_break);
+        result.set_handler::<CodeBlock>(code_block);
+        result.set_handler::<CodeFence>(code_fence);
+        result.set_handler::<Blockquote>(blockquote);
+        result.set_handler::<BulletList>(bullet_list);
+        result.set_handler::<OrderedList>(ordered_list);
+        result.set_handler::<ListItem>(list_item);
+        result.set_handler::<Definition>(definition);
+        result.set_handler::<HtmlBlock>(html_block);
+
+        // commonmark inlines
+        result.set_handler::<Em>(|node, fmt| {
+            let marker = fmt.options.emphasis.unwrap_or(node.cast::<Em>().unwrap().marker);
+            emphasis(node, fmt, marker, 1);
+        });
+        result.set_handler::<Strong>(|node, fmt| {
+            let marker = fmt.options.strong.unwrap_or(node.cast::<Strong>().unwrap().marker);
+            emphasis(node, fmt, marker, 2);
+        });
+        result.set_handler::<CodeInline>(code_inline);
+        result.set_handler::<Link>(link);
+        result.set_handler::<Image>(image);
+        result.set_handler::<Autolink>(autolink);
+        result.set_handler::<Hardbreak>(|_, fmt| fmt.write("\\\n"));
+        result.set_handler::<Softbreak>(|_, fmt| {
+            if fmt.wrapping {
+                fmt.space();
+            } else {
+                fmt.write("\n");
+            }
+        });
+        result.set_handler::<HtmlInline>(|node, fmt| fmt.write(&node.cast::<HtmlInline>().unwrap().content));
+
+        // extensions
+        result.set_handler::<Strikethrough>(|node, fmt| emphasis(node, fmt, node.cast::<Strikethrough>().unwrap().marker, 2));
+        result.set_handler::<Table>(table);
+        result.set_handler::<TodoCheckbox>(|node, fmt| {
+            fmt.write(if node.cast::<TodoCheckbox>().unwrap().checked { "[x]" } else { "[ ]" });
+        });
+        result.set_handler::<DefinitionList>(definition_list);
+        result.set_handler::<DefinitionTerm>(definition_term);
+        result.set_handler::<DefinitionDescription>(definition_description);
+        result.set_handler::<FootnoteReference>(footnote_reference);
+        result.set_handler::<InlineFootnote>(inline_footnote);
+        result.set_handler::<FootnotesContainerNode>(|node, fmt| fmt.contents(&node.children));
+        result.set_handler::<FootnoteDefinition>(footnote_definition);
+        result.set_handler::<BracketedSpan>(bracketed_span);
+        result.set_handler::<MathBlock>(math_block);
+        result.set_handler::<InlineMath>(inline_math);
+        result.set_handler::<EquationRef>(|node, fmt| {
+            fmt.write(&format!("\\eqref{{{}}}", node.cast::<EquationRef>().unwrap().label));
+        });
+        result.set_handler::<Diagram>(|node, fmt| {
+            let data = node.cast::<Diagram>().unwrap();
+            fence(fmt, '`', 3, &data.kind, &data.source);
+        });
+        #[cfg(feature = "linkify")]
+        result.set_handler::<crate::plugins::extra::linkify::Linkified>(|node, fmt| fmt.write(&plain_text(node)));
+
+        // generated by plugins, not present in the source
+        result.set_handler::<FootnoteRefAnchor>(|_, _| {});
+        result.set_handler::<HeadingAnchor>(|_, _| {});
+
+        result
+    }
+
+    /// Write all nodes of type `T` with function `f`.
+    pub fn set_handler<T: NodeValue>(&mut self, f: MarkdownHandler) {
+        self.handlers.insert(TypeKey::of::<T>(), f);
+    }
+
+    /// Remove handler for type `T`, so its nodes are written as html.
+    pub fn remove_handler<T: NodeValue>(&mut self) {
+        self.handlers.remove(&TypeKey::of::<T>());
+    }
+
+    /// Render node (and its children) appending result to the buffer.
+    pub fn render(&mut self, node: &Node) {
+        self.collect_inline_footnotes(node);
+        self.render_node(node);
+    }
+
+    fn render_node(&mut self, node: &Node) {
+        if let Some(f) = self.handlers.get(&node.node_type).copied() {
+            f(node, self);
+        } else {
+            self.html(node);
+        }
+    }
+
+    /// Options this renderer was created with.
+    pub fn options(&self) -> &MarkdownRendererOptions {
+        &self.options
+    }
+
+    /// Render each node in the list.
+    pub fn contents(&mut self, nodes: &[Node]) {
+        for (idx, node) in nodes.iter().enumerate() {
+            if self.tight && !self.inline && self.pending_newlines > 0 {
+                // inline content of tight list items, it goes on a new line
+                // after a block (blocks start a new line themselves anyway)
+                self.block_start();
+            }
+
+            self.next_char = nodes.get(idx + 1).and_then(|next| {
+                if let Some(data) = next.cast::<Text>() {
+                    data.content.chars().next()
+                } else if let Some(data) = next.cast::<TextSpecial>() {
+                    data.markup.chars().next().or(data.content.chars().next())
+                } else {
+                    None
+                }
+            });
+            self.render_node(node);
+        }
+    }
+
+    /// Render inline content of a block (paragraph, heading, etc.).
+    pub fn inline(&mut self, nodes: &[Node]) {
+        let inline = std::mem::replace(&mut self.inline, true);
+        self.contents(nodes);
+        self.inline = inline;
+    }
+
+    /// Start a new block, separating it from previous one.
+    pub fn block_start(&mut self) {
+        let mut newlines = std::mem::take(&mut self.pending_newlines);
+        self.breaks.clear();
+        if self.result.is_empty() { return; }
+
+        if !self.line_start {
+            self.result.push('\n');
+            self.line_start = true;
+            newlines = newlines.saturating_sub(1);
+        }
+
+        for _ in 0..newlines {
+            self.write_prefix(true, false);
+            self.result.push('\n');
+        }
+    }
+
+    /// Finish a block, next one will be separated by an empty line
+    /// (or line break inside tight lists).
+    pub fn block_end(&mut self) {
+        self.pending_newlines = if self.tight { 1 } else { 2 };
+    }
+
+    /// Set whether blocks are separated by line breaks instead of empty lines,
+    /// returns previous value.
+    pub fn set_tight(&mut self, tight: bool) -> bool {
+        std::mem::replace(&mut self.tight, tight)
+    }
+
+    /// Render block container: `prefix` is written at the start of each line
+    /// inside it, `first_line` replaces it on the first line (e.g. list marker).
+    pub fn wrap(&mut self, prefix: &str, first_line: Option<&str>, f: impl FnOnce(&mut Self)) {
+        self.prefixes.push(Prefix {
+            text: prefix.to_owned(),
+            first_line: first_line.map(str::to_owned),
+            start: self.result.len(),
+        });
+
+        f(self);
+
+        if self.result.len() == self.prefixes.last().unwrap().start {
+            // empty container, but its marker is still needed
+            self.write_prefix(true, true);
+            self.line_start = false;
+        }
+        self.prefixes.pop();
+    }
+
+    /// Render something into a separate buffer and return it, e.g. to measure
+    /// its width. Container prefixes are not applied, and lines are not wrapped.
+    pub fn capture(&mut self, f: impl FnOnce(&mut Self)) -> String {
+        let result = std::mem::take(&mut self.result);
+        let prefixes = std::mem::take(&mut self.prefixes);
+        let pending_newlines = std::mem::take(&mut self.pending_newlines);
+        let line_start = std::mem::replace(&mut self.line_start, true);
+        let breaks = std::mem::take(&mut self.breaks);
+        let wrapping = std::mem::replace(&mut self.wrapping, false);
+
+        f(self);
+
+        self.prefixes = prefixes;
+        self.pending_newlines = pending_newlines;
+        self.line_start = line_start;
+        self.breaks = breaks;
+        self.wrapping = wrapping;
+        std::mem::replace(&mut self.result, result)
+    }
+
+    /// Write markdown as is, container prefixes are added to each line.
+    pub fn write(&mut self, text: &str) {
+        for (idx, line) in text.split('\n').enumerate() {
+            if idx > 0 {
+                if self.line_start {
+                    self.write_prefix(true, true);
+                }
+                self.result.push('\n');
+                self.line_start = true;
+                self.breaks.clear();
+            }
+            if line.is_empty() { continue; }
+
+            if self.line_start {
+                self.write_prefix(false, true);
+                self.line_start = false;
+            }
+            self.result.push_str(line);
+            self.wrap_line();
+        }
+    }
+
+    /// Write plain text, escaping characters that could be parsed as markup.
+    pub fn text(&mut self, text: &str) {
+        let escaped = escape_text(text, self.line_start, self.last_char());
+        if !self.wrapping {
+            self.write(&escaped);
+            return;
+        }
+
+        for (idx, word) in escaped.split(' ').enumerate() {
+            if idx > 0 {
+                self.space();
+            }
+            self.write(word);
+        }
+    }
+
+    /// Write a space, the line can be wrapped there if wrapping is enabled.
+    pub fn space(&mut self) {
+        if self.wrapping && !self.line_start && self.last_char() != Some(' ') {
+            self.breaks.push(self.result.len());
+        }
+        self.write(" ");
+    }
+
+    /// Last character written on the current line.
+    pub fn last_char(&self) -> Option<char> {
+        if self.line_start { return None; }
+        self.result.chars().next_back()
+    }
+
+    /// Extension set to store custom stuff.
+    pub fn ext(&mut self) -> &mut RenderExtSet {
+        &mut self.ext
+    }
+
+    fn write_prefix(&mut self, trim: bool, first_line: bool) {
+        let start = self.result.len();
+        for prefix in self.prefixes.iter_mut() {
+            match prefix.first_line.as_deref() {
+                Some(text) if first_line => self.result.push_str(text),
+                _ => self.result.push_str(&prefix.text),
+            }
+            if first_line {
+                prefix.first_line = None;
+            }
+        }
+
+        if trim {
+            let len = self.result[start..].trim_end().len();
+            self.result.truncate(start + len);
+        }
+    }
+
+    // move words that don't fit into the wrap width to the next line
+    fn wrap_line(&mut self) {
+        let Some(width) = self.options.wrap else { return };
+
+        while !self.breaks.is_empty() {
+            let line_start = self.result.rfind('\n').map_or(0, |idx| idx + 1);
+            // trailing space is not visible, it becomes a line break if needed
+            if self.result[line_start..].trim_end_matches(' ').chars().count() <= width { return; }
+
+            let result = &self.result;
+            let candidates = self.breaks.iter().copied().filter(|&pos| can_break(&result[pos + 1..]));
+            let fits = |pos: &usize| result[line_start..*pos].chars().count() <= width;
+            let Some(pos) = candidates.clone().rfind(fits).or(candidates.clone().next()) else { return };
+
+            let mut replacement = String::from("\n");
+            for prefix in self.prefixes.iter() {
+                replacement.push_str(&prefix.text);
+            }
+            let rest = &self.result[pos + 1..];
+            if let Some(escape) = line_start_escape(rest) {
+                let mut rest = rest.to_owned();
+                rest.insert(escape, '\\');
+                replacement.push_str(&rest);
+            } else {
+                replacement.push_str(rest);
+            }
+
+            let shift = replacement.len() - (self.result.len() - pos);
+            self.result.truncate(pos);
+            self.result.push_str(&replacement);
+            self.breaks.retain(|&idx| idx > pos);
+            for idx in self.breaks.iter_mut() {
+                *idx += shift;
+            }
+        }
+    }
+
+    // nodes without markdown syntax are written as raw html
+    fn html(&mut self, node: &Node) {
+        let mut fmt = HTMLRenderer::new();
+        fmt.render(node, &Default::default());
+        let html = String::from(fmt);
+
+        if self.inline {
+            self.write(html.trim_end());
+        } else {
+            self.block_start();
+            self.write(html.trim_end());
+            self.block_end();
+        }
+    }
+
+    // inline footnotes are stored in the footnote list at the end
+    // of the document, so their text is prepared in advance
+    fn collect_inline_footnotes(&mut self, root: &Node) {
+        let mut definitions = Vec::new();
+        root.walk(|node, _| {
+            let Some(data) = node.cast::<FootnoteDefinition>() else { return };
+            if let (true, Some(def_id)) = (data.inline, data.def_id) {
+                definitions.push((def_id, node));
+            }
+        });
+        if definitions.is_empty() { return; }
+
+        let mut footnotes = self.ext.remove::<InlineFootnotes>().unwrap_or_default();
+        for (def_id, node) in definitions {
+            let content = self.capture(|fmt| {
+                for child in node.children.iter() {
+                    fmt.inline(&child.children);
+                }
+            });
+            footnotes.0.insert(def_id, content);
+        }
+        self.ext.insert(footnotes);
+    }
+}
+
+impl Default for MarkdownRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<MarkdownRenderer> for String {
+    fn from(fmt: MarkdownRenderer) -> Self {
+        let mut result = fmt.result;
+        if !result.is_empty() {
+            result.push('\n');
+        }
+        result
+    }
+}
+
+#[derive(Debug, Default)]
+/// Marker of the next list item.
+struct ListContext {
+    marker: String,
+}
+
+impl RenderExt for ListContext {}
+
+#[derive(Debug, Default)]
+/// Content of inline footnotes, by definition id.
+struct InlineFootnotes(HashMap<usize, String>);
+
+impl RenderExt for InlineFootnotes {}
+
+static ENTITY_RE : Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?:#[0-9]{1,7}|#[xX][0-9a-fA-F]{1,6}|[A-Za-z][A-Za-z0-9]{1,31});").unwrap()
+});
+
+static ORDERED_MARKER_RE : Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^[0-9]{1,9}[.)](?:[ \t]|$)").unwrap()
+});
+
+static AUTOLINK_RE : Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?:[a-zA-Z][a-zA-Z0-9+.\-]{1,31}:[^<>\x00-\x20]*|[a-zA-Z0-9.!#$%&'*+/=?^_`{|}~\-]+@[a-zA-Z0-9.\-]+)$").unwrap()
+});
+
+fn escape_text(text: &str, line_start: bool, mut prev: Option<char>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut line_start_pos = if line_start { line_start_escape(text) } else { None };
+
+    for (idx, ch) in text.char_indices() {
+        let rest = &text[idx + ch.len_utf8()..];
+        let next = rest.chars().next();
+
+        let escape = match ch {
+            '\\' | '`' | '*' | '[' | ']' | '<' | '|' | '$' => true,
+            // `snake_case` can't be emphasis
+            '_' => !(prev.is_some_and(char::is_alphanumeric) && next.is_some_and(char::is_alphanumeric)),
+            '~' => prev == Some('~') || next == Some('~') || line_start_pos == Some(idx),
+            '&' => ENTITY_RE.is_match(rest),
+            // attributes after links and spans
+            '{' => idx == 0,
+            _ => line_start_pos == Some(idx),
+        };
+
+        if escape {
+            result.push('\\');
+        }
+        result.push(ch);
+        if ch == '\n' {
+            line_start_pos = line_start_escape(rest).map(|pos| pos + idx + 1);
+        }
+        prev = Some(ch);
+    }
+
+    result
+}
+
+// position of a character that needs escaping if `text` is at the start
+// of a line, e.g. `\# text` or `1\. text`
+fn line_start_escape(text: &str) -> Option<usize> {
+    let mut chars = text.chars();
+    let ch = chars.next()?;
+    let next = chars.next();
+    let space_after = matches!(next, None | Some(' ' | '\t'));
+
+    match ch {
+        '#' => matches!(text.trim_start_matches('#').chars().next(), None | Some(' ' | '\t')).then_some(0),
+        '-' | '=' => (space_after || next == Some(ch)).then_some(0),
+        '+' | ':' | '~' => space_after.then_some(0),
+        '>' => Some(0),
+        '0'..='9' if ORDERED_MARKER_RE.is_match(text) => text.find(['.', ')']),
+        _ => None,
+    }
+}
+
+// whether wrapped line can start with `text`, raw html, math and long code spans
+// could start a block there
+fn can_break(text: &str) -> bool {
+    !text.is_empty()
+        && !text.starts_with([' ', '<', '$'])
+        && !["```", "~~~", "[^", "\\["].iter().any(|prefix| text.starts_with(prefix))
+}
+
+// text content of a node, including escapes and entities
+fn plain_text(node: &Node) -> String {
+    let mut result = String::new();
+    node.walk(|node, _| {
+        if let Some(data) = node.cast::<Text>() {
+            result.push_str(&data.content);
+        } else if let Some(data) = node.cast::<TextSpecial>() {
+            result.push_str(&data.content);
+        }
+    });
+    result
+}
+
+fn text_special(node: &Node, fmt: &mut MarkdownRenderer) {
+    let data = node.cast::<TextSpecial>().unwrap();
+    if data.markup.is_empty() {
+        fmt.text(&data.content);
+    } else {
+        fmt.write(&data.markup);
+    }
+}
+
+fn paragraph(node: &Node, fmt: &mut MarkdownRenderer) {
+    fmt.block_start();
+    fmt.inline(&node.children);
+    fmt.block_end();
+}
+
+fn atx_heading(node: &Node, fmt: &mut MarkdownRenderer) {
+    let data = node.cast::<ATXHeading>().unwrap();
+    let mut content = fmt.capture(|fmt| fmt.inline(&node.children));
+    content.push_str(&attributes(node));
+
+    if data.level <= 2 && !content.is_empty() && fmt.options.heading_style == Some(HeadingStyle::Setext) {
+        setext(fmt, &content, if data.level == 1 { '=' } else { '-' });
+    } else {
+        atx(fmt, data.level, content);
+    }
+}
+
+fn atx(fmt: &mut MarkdownRenderer, level: u8, mut content: String) {
+    // trailing `#` would be treated as closing sequence
+    let closing = content.trim_end_matches('#').len();
+    if closing < content.len() && (closing == 0 || content[..closing].ends_with(' ')) {
+        content.insert(closing, '\\');
+    }
+
+    fmt.block_start();
+    fmt.write(&"#".repeat(level as usize));
+    if !content.is_empty() {
+        fmt.write(" ");
+        fmt.write(&content);
+    }
+    fmt.block_end();
+}
+
+fn setext_heading(node: &Node, fmt: &mut MarkdownRenderer) {
+    let data = node.cast::<SetextHeader>().unwrap();
+    let mut content = fmt.capture(|fmt| fmt.inline(&node.children));
+    content.push_str(&attributes(node));
+
+    // hard breaks can't be written in atx headings
+    if fmt.options.heading_style == Some(HeadingStyle::Atx) && !content.contains("\\\n") {
+        atx(fmt, data.level, content.replace('\n', " "));
+    } else {
+        setext(fmt, &content, data.marker);
+    }
+}
+
+fn setext(fmt: &mut MarkdownRenderer, content: &str, marker: char) {
+    let width = content.lines().map(|line| line.chars().count()).max().unwrap_or(0);
+
+    fmt.block_start();
+    fmt.write(content);
+    fmt.write("\n");
+    fmt.write(&marker.to_string().repeat(width.max(3)));
+    fmt.block_end();
+}
+
+fn thematic_break(node: &Node, fmt: &mut MarkdownRenderer) {
+    let data = node.cast::<ThematicBreak>().unwrap();
+    fmt.block_start();
+    fmt.write(&data.marker.to_string().repeat(data.marker_len.max(3)));
+    fmt.block_end();
+}
+
+fn code_block(node: &Node, fmt: &mut MarkdownRenderer) {
+    let data = node.cast::<CodeBlock>().unwrap();
+    let content = data.content.lines()
+        .map(|line| if line.is_empty() { String::new() } else { format!("    {line}") })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    // separate it from the list, so it isn't parsed as a part of the last item
+    if fmt.last_list == Some(fmt.result.len()) {
+        fmt.block_start();
+        fmt.write("<!-- -->");
+        fmt.block_end();
+    }
+
+    fmt.block_start();
+    fmt.write(&content);
+    fmt.block_end();
+}
+
+fn code_fence(node: &Node, fmt: &mut MarkdownRenderer) {
+    let data = node.cast::<CodeFence>().unwrap();
+    let info = data.info.clone() + &attributes(node);
+    fence(fmt, data.marker, data.marker_len, &info, &data.content);
+}
+
+fn fence(fmt: &mut MarkdownRenderer, mut marker: char, marker_len: usize, info: &str, content: &str) {
+    // backticks are not allowed in the info string of backtick fences
+    if marker == '`' && info.contains('`') {
+        marker = '~';
+    }
+
+    // fence must be longer than any line of markers inside
+    let longest = content.lines()
+        .map(|line| line.trim_start_matches(' '))
+        .map(|line| line.len() - line.trim_start_matches(marker).len())
+        .max()
+        .unwrap_or(0);
+    let fence = marker.to_string().repeat(marker_len.max(longest + 1).max(3));
+
+    fmt.block_start();
+    fmt.write(&fence);
+    fmt.write(info);
+    fmt.write("\n");
+    fmt.write(content);
+    if !content.is_empty() && !content.ends_with('\n') {
+        fmt.write("\n");
+    }
+    fmt.write(&fence);
+    fmt.block_end();
+}
+
+fn blockquote(node: &Node, fmt: &mut MarkdownRenderer) {
+    fmt.block_start();
+    let tight = fmt.set_tight(false);
+    fmt.wrap("> ", None, |fmt| fmt.contents(&node.children));
+    fmt.set_tight(tight);
+    fmt.block_end();
+}
+
+// items of tight lists contain inline nodes directly instead of paragraphs
+fn is_loose(node: &Node) -> bool {
+    node.children.iter().any(|item| item.children.iter().any(|child| child.is::<Paragraph>()))
+}
+
+fn list(node: &Node, fmt: &mut MarkdownRenderer, marker: impl Fn(usize) -> String) {
+    let old_context = fmt.ext.remove::<ListContext>();
+    let tight = fmt.set_tight(!is_loose(node));
+
+    fmt.block_start();
+    for (idx, item) in node.children.iter().enumerate() {
+        fmt.ext.insert(ListContext { marker: marker(idx) });
+        fmt.contents(std::slice::from_ref(item));
+    }
+
+    fmt.set_tight(tight);
+    fmt.block_end();
+    fmt.last_list = Some(fmt.result.len());
+    fmt.ext.remove::<ListContext>();
+    if let Some(ctx) = old_context {
+        fmt.ext.insert(ctx);
+    }
+}
+
+fn bullet_list(node: &Node, fmt: &mut MarkdownRenderer) {
+    let data = node.cast::<BulletList>().unwrap();
+    let bullets = &fmt.options.bullets;
+    let mut marker = match bullets.get(fmt.bullet_depth).or(bullets.last()) {
+        Some(&marker) => marker,
+        None => data.marker,
+    };
+
+    // lists with the same marker next to each other would be merged
+    if fmt.last_bullet == Some((fmt.result.len(), marker)) {
+        marker = ['-', '*', '+'].into_iter().find(|&ch| ch != marker).unwrap();
+    }
+
+    fmt.bullet_depth += 1;
+    list(node, fmt, |_| format!("{marker} "));
+    fmt.bullet_depth -= 1;
+    fmt.last_bullet = Some((fmt.result.len(), marker));
+}
+
+fn ordered_list(node: &Node, fmt: &mut MarkdownRenderer) {
+    let data = node.cast::<OrderedList>().unwrap();
+    let numbering = fmt.options.numbering;
+    list(node, fmt, |idx| {
+        let number = match numbering {
+            ListNumbering::Increment => data.start as usize + idx,
+            ListNumbering::Repeat => data.start as usize,
+        };
+        format!("{number}{} ", data.marker)
+    });
+}
+
+fn list_item(node: &Node, fmt: &mut MarkdownRenderer) {
+    let marker = match fmt.ext.get::<ListContext>() {
+        Some(ctx) => ctx.marker.clone(),
+        None => String::from("- "),
+    };
+    let indent = " ".repeat(marker.chars().count());
+
+    fmt.block_start();
+    fmt.wrap(&indent, Some(&marker), |fmt| {
+        let mut children = &node.children[..];
+
+        // checkbox goes on the same line as the text, even in loose lists
+        if let Some(checkbox) = children.first().filter(|child| child.is::<TodoCheckbox>()) {
+            fmt.contents(std::slice::from_ref(checkbox));
+            children = &children[1..];
+            if let Some(paragraph) = children.first().filter(|child| child.is::<Paragraph>()) {
+                fmt.inline(&paragraph.children);
+                fmt.block_end();
+                children = &children[1..];
+            }
+        }
+
+        fmt.contents(children);
+    });
+    fmt.block_end();
+}
+
+fn link_destination(url: &str, title: Option<&str>) -> String {
+    let mut result = String::new();
+
+    if url.is_empty() || url.contains(|c: char| c.is_whitespace() || c.is_control() || c == '<' || c == '>') {
+        result.push('<');
+        for ch in url.chars() {
+            if matches!(ch, '<' | '>' | '\\') { result.push('\\'); }
+            result.push(ch);
+        }
+        result.push('>');
+    } else {
+        for ch in url.chars() {
+            if matches!(ch, '(' | ')' | '\\') { result.push('\\'); }
+            result.push(ch);
+        }
+    }
+
+    if let Some(title) = title {
+        result.push_str(" \"");
+        for ch in title.chars() {
+            if matches!(ch, '"' | '\\') { result.push('\\'); }
+            result.push(ch);
+        }
+        result.push('"');
+    }
+
+    result
+}
+
+fn definition(node: &Node, fmt: &mut MarkdownRenderer) {
+    let data = node.cast::<Definition>().unwrap();
+    fmt.block_start();
+    fmt.write(&format!("[{}]: {}", data.label, link_destination(&data.destination, data.title.as_deref())));
+    fmt.block_end();
+}
+
+fn html_block(node: &Node, fmt: &mut MarkdownRenderer) {
+    let data = node.cast::<HtmlBlock>().unwrap();
+    fmt.block_start();
+    fmt.write(data.content.trim_end_matches('\n'));
+    fmt.block_end();
+}
+
+fn emphasis(node: &Node, fmt: &mut MarkdownRenderer, mut marker: char, len: usize) {
+    // `**` would be parsed as a single delimiter run, but it is the only way
+    // to write nested emphasis next to a word, e.g. `foo***bar***baz`
+    if marker != '~' && fmt.last_char() == Some(marker) {
+        let before = fmt.result.trim_end_matches(marker).chars().next_back();
+        let after = fmt.next_char.or(fmt.emphasis_next_char);
+        if !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric) {
+            marker = if marker == '*' { '_' } else { '*' };
+        }
+    }
+
+    // underscores don't work inside words
+    if marker == '_' && (fmt.last_char().is_some_and(char::is_alphanumeric) || fmt.next_char.is_some_and(char::is_alphanumeric)) {
+        marker = '*';
+    }
+
+    let delimiter = marker.to_string().repeat(len);
+    let next_char = std::mem::replace(&mut fmt.emphasis_next_char, fmt.next_char);
+    fmt.write(&delimiter);
+    fmt.contents(&node.children);
+    fmt.write(&delimiter);
+    fmt.emphasis_next_char = next_char;
+}
+
+fn code_inline(node: &Node, fmt: &mut MarkdownRenderer) {
+    let data = node.cast::<CodeInline>().unwrap();
+    let content = node.collect_text();
+
+    // closing sequence must not appear in the content
+    let mut runs = Vec::new();
+    let mut run = 0;
+    for ch in content.chars() {
+        if ch == data.marker {
+            run += 1;
+        } else if run > 0 {
+            runs.push(run);
+            run = 0;
+        }
+    }
+    runs.push(run);
+
+    let mut len = data.marker_len.max(1);
+    while runs.contains(&len) {
+        len += 1;
+    }
+    let delimiter = data.marker.to_string().repeat(len);
+
+    // leading and trailing space are stripped from code spans with both of them
+    let padding = content.starts_with(data.marker) || content.ends_with(data.marker)
+        || content.starts_with(' ') && content.ends_with(' ') && !content.trim().is_empty();
+    let padding = if padding { " " } else { "" };
+
+    fmt.write(&format!("{delimiter}{padding}{content}{padding}{delimiter}"));
+}
+
+fn link(node: &Node, fmt: &mut MarkdownRenderer) {
+    let data = node.cast::<Link>().unwrap();
+    fmt.write("[");
+    fmt.contents(&node.children);
+    fmt.write("](");
+    fmt.write(&link_destination(&data.url, data.title.as_deref()));
+    fmt.write(")");
+    fmt.write(attributes(node).trim_start());
+}
+
+fn image(node: &Node, fmt: &mut MarkdownRenderer) {
+    let data = node.cast::<Image>().unwrap();
+    fmt.write("![");
+    fmt.contents(&node.children);
+    fmt.write("](");
+    fmt.write(&link_destination(&data.url, data.title.as_deref()));
+    fmt.write(")");
+    fmt.write(attributes(node).trim_start());
+}
+
+fn autolink(node: &Node, fmt: &mut MarkdownRenderer) {
+    let text = plain_text(node);
+    if AUTOLINK_RE.is_match(&text) {
+        fmt.write(&format!("<{text}>"));
+    } else {
+        // `www.example.com`, recognized by gfm autolinks
+        fmt.write(&text);
+    }
+}
+
+fn table(node: &Node, fmt: &mut MarkdownRenderer) {
+    let data = node.cast::<Table>().unwrap();
+
+    let mut rows = Vec::new();
+    for section in node.children.iter() {
+        if !section.is::<TableHead>() && !section.is::<TableBody>() { continue; }
+        for row in section.children.iter().filter(|row| row.is::<TableRow>()) {
+            let cells = row.children.iter()
+                .filter(|cell| cell.is::<TableCell>())
+                .map(|cell| escape_pipes(&fmt.capture(|fmt| fmt.inline(&cell.children))))
+                .collect::<Vec<_>>();
+            rows.push(cells);
+        }
+    }
+
+    let columns = data.alignments.len();
+    let mut widths = vec![3; columns];
+    if fmt.options.align_tables {
+        for row in rows.iter() {
+            for (width, cell) in widths.iter_mut().zip(row.iter()) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+    }
+
+    let delimiters = data.alignments.iter().zip(widths.iter()).map(|(alignment, &width)| match alignment {
+        ColumnAlignment::None => "-".repeat(width),
+        ColumnAlignment::Left => format!(":{}", "-".repeat(width - 1)),
+        ColumnAlignment::Right => format!("{}:", "-".repeat(width - 1)),
+        ColumnAlignment::Center => format!(":{}:", "-".repeat(width - 2)),
+    }).collect::<Vec<_>>();
+
+    let mut lines = Vec::new();
+    for (idx, row) in rows.iter().enumerate() {
+        let mut cells = row.clone();
+        cells.resize(columns, String::new());
+        if fmt.options.align_tables {
+            for ((cell, alignment), width) in cells.iter_mut().zip(data.alignments.iter()).zip(widths.iter()) {
+                let padding = width - cell.chars().count();
+                let left = match alignment {
+                    ColumnAlignment::None | ColumnAlignment::Left => 0,
+                    ColumnAlignment::Right => padding,
+                    ColumnAlignment::Center => padding / 2,
+                };
+                *cell = format!("{}{cell}{}", " ".repeat(left), " ".repeat(padding - left));
+            }
+        }
+        lines.push(format!("| {} |", cells.join(" | ")));
+        if idx == 0 {
+            lines.push(format!("| {} |", delimiters.join(" | ")));
+        }
+    }
+
+    fmt.block_start();
+    fmt.write(&lines.join("\n"));
+    fmt.block_end();
+}
+
+// table rows are split on `|` before inline parsing, even inside code spans
+fn escape_pipes(cell: &str) -> String {
+    let mut result = String::with_capacity(cell.len());
+    let mut prev = None;
+    for ch in cell.chars() {
+        if ch == '|' && prev != Some('\\') {
+            result.push('\\');
+        }
+        result.push(ch);
+        prev = Some(ch);
+    }
+    result
+}
+
+fn definition_list(node: &Node, fmt: &mut MarkdownRenderer) {
+    let loose = node.children.iter()
+        .filter(|child| child.is::<DefinitionDescription>())
+        .any(|child| child.children.iter().any(|child| child.is::<Paragraph>()));
+
+    let tight = fmt.set_tight(!loose);
+    fmt.block_start();
+    for (idx, child) in node.children.iter().enumerate() {
+        if idx > 0 && child.is::<DefinitionTerm>() {
+            // otherwise term would continue previous description
+            fmt.pending_newlines = 2;
+        }
+        fmt.contents(std::slice::from_ref(child));
+    }
+    fmt.set_tight(tight);
+    fmt.block_end();
+}
+
+fn definition_term(node: &Node, fmt: &mut MarkdownRenderer) {
+    // terms can't span several lines, so they are never wrapped
+    let content = fmt.capture(|fmt| fmt.inline(&node.children));
+    fmt.block_start();
+    fmt.write(&content);
+    fmt.block_end();
+}
+
+fn definition_description(node: &Node, fmt: &mut MarkdownRenderer) {
+    fmt.block_start();
+    fmt.wrap("  ", Some(": "), |fmt| fmt.contents(&node.children));
+    fmt.block_end();
+}
+
+fn footnote_reference(node: &Node, fmt: &mut MarkdownRenderer) {
+    let data = node.cast::<FootnoteReference>().unwrap();
+    match &data.label {
+        Some(label) => fmt.write(&format!("[^{label}]")),
+        None => fmt.write(&format!("[^{}]", data.def_id)),
+    }
+}
+
+fn inline_footnote(node: &Node, fmt: &mut MarkdownRenderer) {
+    let def_id = node.children.iter().find_map(|child| child.cast::<FootnoteReference>()).map(|data| data.def_id);
+    let content = def_id
+        .and_then(|def_id| fmt.ext.get::<InlineFootnotes>()?.0.get(&def_id).cloned())
+        .unwrap_or_default();
+
+    fmt.write("^[");
+    fmt.write(&content);
+    fmt.write("]");
+}
+
+fn footnote_definition(node: &Node, fmt: &mut MarkdownRenderer) {
+    let data = node.cast::<FootnoteDefinition>().unwrap();
+    // written in place by `inline_footnote`
+    if data.inline { return; }
+
+    let label = match (&data.label, data.def_id) {
+        (Some(label), _) => label.clone(),
+        (None, Some(def_id)) => def_id.to_string(),
+        (None, None) => return,
+    };
+
+    fmt.block_start();
+    let tight = fmt.set_tight(false);
+    fmt.wrap("    ", Some(&format!("[^{label}]: ")), |fmt| fmt.contents(&node.children));
+    fmt.set_tight(tight);
+    fmt.block_end();
+}
+
+// ` {#id .class key="value"}`, or empty string if node has no attributes
+fn attributes(node: &Node) -> String {
+    let mut attrs = Vec::new();
+    for (name, value) in node.attrs.iter() {
+        match *name {
+            "id" => attrs.push(format!("#{value}")),
+            "class" => attrs.extend(value.split_whitespace().map(|class| format!(".{class}"))),
+            // generated from the source, not a part of it
+            "data-sourcepos" => {}
+            _ => attrs.push(format!("{name}=\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))),
+        }
+    }
+
+    if attrs.is_empty() { return String::new(); }
+    format!(" {{{}}}", attrs.join(" "))
+}
+
+fn bracketed_span(node: &Node, fmt: &mut MarkdownRenderer) {
+    fmt.write("[");
+    fmt.contents(&node.children);
+    fmt.write("]");
+    fmt.write(attributes(node).trim_start());
+    if node.attrs.is_empty() {
+        fmt.write("{}");
+    }
+}
+
+fn math_block(node: &Node, fmt: &mut MarkdownRenderer) {
+    let data = node.cast::<MathBlock>().unwrap();
+    let (open, close) = match data.delimiter {
+        MathDelimiter::Dollars => ("$$", "$$"),
+        MathDelimiter::Brackets => ("\\[", "\\]"),
+    };
+
+    fmt.block_start();
+    fmt.write(&format!("{open}\n{}\n{close}", data.content));
+    fmt.block_end();
+}
+
+fn inline_math(node: &Node, fmt: &mut MarkdownRenderer) {
+    let data = node.cast::<InlineMath>().unwrap();
+    let (open, close) = match data.delimiter {
+        InlineMathDelimiter::Dollar => ("$", "$"),
+        InlineMathDelimiter::Dollars => ("$$", "$$"),
+        InlineMathDelimiter::Parens => ("\\(", "\\)"),
+    };
+
+    fmt.write(&format!("{open}{}{close}", data.content));
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::plugins::{cmark, extra, gfm, html, jotdown, pandoc};
+    use crate::MarkdownIt;
+
+    use super::*;
+
+    fn parser() -> MarkdownIt {
+        let mut md = MarkdownIt::new();
+        cmark::add(&mut md);
+        html::add(&mut md);
+        extra::tables::add(&mut md);
+        extra::strikethrough::add(&mut md);
+        gfm::tasklist::add(&mut md);
+        pandoc::add(&mut md);
+        jotdown::katex::add(&mut md);
+        md
+    }
+
+    fn render(node: &Node) -> String {
+        let mut fmt = MarkdownRenderer::new();
+        fmt.render(node);
+        fmt.into()
+    }
+
+    #[rstest]
+    #[case("# Title\n\ntext *em* __strong__ `code`\n")]
+    #[case("Title\n=====\n\nSub *title*\n-----------\n")]
+    #[case("* a\n* b\n  + c\n  + d\n")]
+    #[case("3) a\n\n4) b\n\n   c\n")]
+    #[case("- > quote\n-\n- b\n")]
+    #[case("> a\n>\n> ```js\n> code\n> ```\n")]
+    #[case("~~~~\n```\n~~~~\n\n    indented\n\n    code\n")]
+    #[case("___\n\n<div>\nhtml\n</div>\n")]
+    #[case("[link](/url \"title\") ![img](/a%20b.png) [empty](<>) <https://example.org>\n")]
+    #[case("[ref]: /url\n\n``a ` b`` foo\\\nbar &amp; \\*\n")]
+    #[case("| a | b |\n| :-- | --: |\n| `x\\|y` | ~~z~~ |\n")]
+    #[case("- [x] done\n- [ ] todo\n")]
+    #[case("term\n: definition\n: another\n\nterm 2\n: definition\n")]
+    #[case("text[^1] and ^[inline *note*]\n\n[^1]: note\n    more\n\n    para\n")]
+    #[case("$x$ and \\(y\\)\n\n$$\nz\n$$\n")]
+    #[case("# Title {#intro .big}\n\nSub *title* {lang=\"en\"}\n-----------------------\n")]
+    #[case("```rust {#main .numberLines lang=\"a \\\"b\\\"\"}\ncode\n```\n")]
+    #[case("[link](/url){.external target=\"_blank\"} ![img](/a.png){#logo width=\"50%\"}\n")]
+    #[case("[*span*]{.smallcaps} [empty]{}\n")]
+    #[case("foo***bar***baz ***bar***baz\n")]
+    #[case("- # Foo\n- Bar\n  ---\n  baz\n")]
+    fn round_trip(#[case] input: &str) {
+        assert_eq!(render(&parser().parse(input)), input);
+    }
+
+    #[rstest]
+    #[case("# a", "\\# a")]
+    #[case("- b", "\\- b")]
+    #[case("---", "\\---")]
+    #[case("+ c", "\\+ c")]
+    #[case("12. d", "12\\. d")]
+    #[case("> e", "\\> e")]
+    #[case("===", "\\===")]
+    #[case("~~~", "\\~\\~\\~")]
+    #[case("a * b _c_ [d](e)", "a \\* b \\_c\\_ \\[d\\](e)")]
+    #[case("<f> &amp; `g` $h$", "\\<f> \\&amp; \\`g\\` \\$h\\$")]
+    #[case("~~i~~ j | k", "\\~\\~i\\~\\~ j \\| k")]
+    #[case("snake_case ~tilde #hash 1.5", "snake_case ~tilde #hash 1.5")]
+    fn escapes(#[case] text: &str, #[case] expected: &str) {
+        // text created programmatically, markup in it must not be recognized
+        let md = parser();
+        let mut ast = md.parse("");
+        let mut paragraph = Node::new(Paragraph);
+        paragraph.children.push(Node::new(Text { content: text.to_owned() }));
+        ast.children.push(paragraph);
+
+        let output = render(&ast);
+        assert_eq!(output, format!("{expected}\n"));
+        assert_eq!(md.parse(&output).render(&Default::default()), format!("<p>{}</p>\n", crate::common::utils::escape_html(text)));
+    }
+
+    #[rstest]
+    #[case("1. a\n1. b\n1. c", "1. a\n2. b\n3. c\n")]
+    #[case("- a\n\n\n- b", "- a\n\n- b\n")]
+    #[case("```\na\n```\n````\n```\n````", "```\na\n```\n\n````\n```\n````\n")]
+    #[case("foo  \nbar", "foo\\\nbar\n")]
+    #[case("# Title #", "# Title\n")]
+    #[case("[a][b]\n\n[b]: /url", "[a](/url)\n\n[b]: /url\n")]
+    fn normalized(#[case] input: &str, #[case] expected: &str) {
+        let md = parser();
+        let output = render(&md.parse(input));
+        assert_eq!(output, expected);
+        assert_eq!(md.parse(&output).render(&Default::default()), md.parse(input).render(&Default::default()));
+    }
+
+    fn format(md: &MarkdownIt, input: &str, options: MarkdownRendererOptions) -> String {
+        let mut fmt = MarkdownRenderer::with_options(options.clone());
+        fmt.render(&md.parse(input));
+        let output = String::from(fmt);
+
+        // wrapping changes whitespace only
+        let html = |src: &str| md.parse(src).render(&Default::default()).split_whitespace().collect::<Vec<_>>().join(" ");
+        assert_eq!(html(&output), html(input));
+        let mut fmt = MarkdownRenderer::with_options(options);
+        fmt.render(&md.parse(&output));
+        assert_eq!(String::from(fmt), output, "not idempotent");
+        output
+    }
+
+    #[rstest]
+    #[case("Title\n=====\n\nSub\ntitle\n---", "# Title\n\n## Sub title\n")]
+    #[case("+ a\n+ b\n  * c\n\n- d", "- a\n- b\n  - c\n\n* d\n")]
+    #[case("_a_ __b__ _c_ *__e__*", "*a* **b** *c* *__e__*\n")]
+    #[case("foo***bar***baz foo******bar*********baz", "foo***bar***baz foo******bar******\\*\\*\\*baz\n")]
+    #[case("| a | b | long |\n|:-|-:|:-:|\n| `x` | | y |", "| a   |   b | long |\n| :-- | --: | :--: |\n| `x` |     |  y   |\n")]
+    fn formatter(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(format(&parser(), input, MarkdownRendererOptions::formatter()), expected);
+    }
+
+    #[rstest]
+    #[case("# Title\n\n## Sub *title*\n\n### Small", "Title\n=====\n\nSub _title_\n-----------\n\n### Small\n")]
+    #[case("_a_ and *b*", "_a_ and _b_\n")]
+    #[case("a*b*", "a*b*\n")]
+    #[case("*a*b", "*a*b\n")]
+    fn setext_underscores(#[case] input: &str, #[case] expected: &str) {
+        let options = MarkdownRendererOptions {
+            heading_style: Some(HeadingStyle::Setext),
+            emphasis: Some('_'),
+            ..Default::default()
+        };
+        assert_eq!(format(&parser(), input, options), expected);
+    }
+
+    #[test]
+    fn repeat_numbers() {
+        let options = MarkdownRendererOptions { numbering: ListNumbering::Repeat, ..Default::default() };
+        assert_eq!(format(&parser(), "3. a\n4. b", options), "3. a\n3. b\n");
+    }
+
+    #[rstest]
+    #[case("aaa bbb ccc ddd eee", "aaa bbb\nccc ddd\neee\n")]
+    #[case("aaa\nbbb\nccc", "aaa bbb\nccc\n")]
+    #[case("verylongword a", "verylongword\na\n")]
+    #[case("aaaaaaaaa - bbbbbbb 1. c", "aaaaaaaaa\n\\- bbbbbbb\n1\\. c\n")]
+    #[case("> aaa bbb ccc", "> aaa bbb\n> ccc\n")]
+    #[case("- aaa [b c](/url) d", "- aaa [b\n  c](/url)\n  d\n")]
+    #[case("aaa `b c d e`", "aaa\n`b c d e`\n")]
+    #[case("# aaa bbb ccc", "# aaa bbb ccc\n")]
+    #[case("aaaaaaa <b>", "aaaaaaa <b>\n")]
+    fn wrap(#[case] input: &str, #[case] expected: &str) {
+        let options = MarkdownRendererOptions { wrap: Some(10), ..Default::default() };
+        assert_eq!(format(&parser(), input, options), expected);
+    }
+
+    // inputs of commonmark spec examples
+    fn spec_examples() -> Vec<(usize, String)> {
+        let fixtures = include_str!("../../../tests/fixtures/commonmark/good.txt");
+        let mut result = Vec::new();
+        let mut lines = fixtures.lines();
+        let mut src_line = 0;
+
+        while let Some(line) = lines.next() {
+            if let Some(number) = line.strip_prefix("src line: ") {
+                src_line = number.parse().unwrap();
+            } else if line == "." {
+                let input = lines.by_ref().take_while(|line| *line != ".").collect::<Vec<_>>();
+                lines.by_ref().take_while(|line| *line != ".").for_each(drop);
+                result.push((src_line, input.join("\n") + "\n"));
+            }
+        }
+
+        result
+    }
+
+    #[test]
+    fn round_trip_spec() {
+        let md = &mut MarkdownIt::new();
+        cmark::add(md);
+        html::add(md);
+
+        // comments separating lists from code blocks are the only difference allowed
+        let html = |src: &str| md.parse(src).render(&Default::default()).replace("<!-- -->\n", "");
+
+        let mut failed = Vec::new();
+        for (src_line, input) in spec_examples() {
+            let output = render(&md.parse(&input));
+            if html(&output) != html(&input) {
+                failed.push(format!("line {src_line}:\n{input}--- rendered as:\n{output}"));
+            }
+        }
+        assert!(failed.is_empty(), "spec examples changed meaning:\n{}", failed.join("\n"));
+    }
+
+    #[test]
+    fn formatter_spec_idempotent() {
+        let md = &mut MarkdownIt::new();
+        cmark::add(md);
+        html::add(md);
+
+        let format = |src: &str| {
+            let mut fmt = MarkdownRenderer::with_options(MarkdownRendererOptions::formatter());
+            fmt.render(&md.parse(src));
+            String::from(fmt)
+        };
+
+        let mut failed = Vec::new();
+        for (src_line, input) in spec_examples() {
+            let output = format(&input);
+            let again = format(&output);
+            if again != output {
+                failed.push(format!("line {src_line}:\n{input}--- formatted as:\n{output}--- and then as:\n{again}"));
+            }
+        }
+        assert!(failed.is_empty(), "formatting spec examples is not idempotent:\n{}", failed.join("\n"));
+    }
+
+    #[test]
+    fn fence_longer_than_content() {
+        let mut ast = parser().parse("```\n```");
+        ast.children[0].cast_mut::<CodeFence>().unwrap().content = "````\n".into();
+        assert_eq!(render(&ast), "`````\n````\n`````\n");
+    }
+
+    #[test]
+    fn custom_handler() {
+        let md = parser();
+        let mut fmt = MarkdownRenderer::new();
+        fmt.set_handler::<Em>(|node, fmt| {
+            fmt.write("_");
+            fmt.contents(&node.children);
+            fmt.write("_");
+        });
+        fmt.render(&md.parse("*a*"));
+        assert_eq!(String::from(fmt), "_a_\n");
+    }
+
+    #[test]
+    fn unknown_nodes_as_html() {
+        let md = parser();
+        let mut fmt = MarkdownRenderer::new();
+        fmt.remove_handler::<Strikethrough>();
+        fmt.remove_handler::<Table>();
+        fmt.render(&md.parse("~~a~~\n\n| x |\n|---|"));
+        assert_eq!(
+            String::from(fmt),
+            "<s>a</s>\n\n<table>\n<thead>\n<tr>\n<th>x</th>\n</tr>\n</thead>\n</table>\n",
+        );
+    }
+}
@@ -0,0 +1,314 @@
+//! Renderer API and default HTML renderer.
+//!
+//! Use [HTMLRenderer] directly to customize output or to render several
+//! nodes into one buffer, [Node::render] covers the common case.
+use std::collections::HashMap;
+use std::fmt::{self, Debug};
+use std::io;
+
+use crate::common::utils::escape_html;
+use crate::common::TypeKey;
+use crate::parser::extset::{RenderExtSet, RenderOptionsExtSet};
+use crate::{Node, NodeValue};
+
+pub mod ansi;
+pub mod cache;
+pub mod latex;
+pub mod markdown;
+pub mod text;
+
+/// Each node outputs its HTML using this API.
+///
+/// Renderer is a struct that walks through AST and collects HTML from each node
+/// into internal buffer.
+pub trait Renderer {
+    /// Write opening html tag with attributes, e.g. `<a href="url">`.
+    fn open(&mut self, tag: &str, attrs: &[(&str, String)]);
+    /// Write closing html tag, e.g. `</a>`.
+    fn close(&mut self, tag: &str);
+    /// Write self-closing html tag with attributes, e.g. `<img src="url"/>`.
+    fn self_close(&mut self, tag: &str, attrs: &[(&str, String)]);
+    /// Loop through child nodes and render each one.
+    fn contents(&mut self, nodes: &[Node], options: &RenderOptionsExtSet);
+    /// Write line break (`\n`). Default renderer ignores it if last char in the buffer is `\n` already.
+    fn cr(&mut self);
+    /// Write plain text with escaping, `<div>` -> `&lt;div&gt;`.
+    fn text(&mut self, text: &str);
+    /// Write plain text without escaping, `<div>` -> `<div>`.
+    fn text_raw(&mut self, text: &str);
+    /// Extension set to store custom stuff.
+    fn ext(&mut self) -> &mut RenderExtSet;
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+/// Line endings produced by [HTMLRenderer].
+pub enum NewlineStyle {
+    #[default]
+    /// `\n`
+    Lf,
+    /// `\r\n`
+    CrLf,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+/// Order of attributes in html tags produced by [HTMLRenderer].
+pub enum AttributeOrder {
+    #[default]
+    /// Same order as nodes and plugins add them.
+    Insertion,
+    /// Sorted by attribute name, useful for stable output in tests and diffs.
+    Alphabetical,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+/// Options for [HTMLRenderer].
+pub struct HTMLRendererOptions {
+    /// Add slash to self-closing tags like this: `<img />`.
+    pub xhtml: bool,
+    pub newline: NewlineStyle,
+    pub attribute_order: AttributeOrder,
+}
+
+/// Function that renders a node instead of its own [NodeValue::render](crate::NodeValue::render).
+///
+/// Call `node.node_value.render(node, fmt, options)` to fall back to default output.
+pub type RenderOverride = fn (node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet);
+
+#[derive(Debug)]
+/// Default HTML/XHTML renderer.
+///
+/// Several nodes can be rendered into the same buffer, convert renderer
+/// into `String` to get the result:
+///
+/// ```rust
+/// use markdown_it::parser::renderer::HTMLRenderer;
+///
+/// let md = &mut markdown_it::MarkdownIt::new();
+/// markdown_it::plugins::cmark::add(md);
+///
+/// let mut fmt = HTMLRenderer::new();
+/// fmt.render(&md.parse("# Title"), &Default::default());
+/// fmt.render(&md.parse("body"), &Default::default());
+/// assert_eq!(String::from(fmt), "<h1>Title</h1>\n<p>body</p>\n");
+/// ```
+///
+/// Output can also be streamed into any [fmt::Write] (or [io::Write] wrapped
+/// in [IoWriter]) with [with_writer](HTMLRenderer::with_writer), without keeping
+/// the entire document in memory.
+pub struct HTMLRenderer<W: fmt::Write = String> {
+    out: W,
+    status: fmt::Result,
+    // used to avoid duplicate newlines in `cr()`, true at the start of the output
+    line_start: bool,
+    ext: RenderExtSet,
+    options: HTMLRendererOptions,
+    overrides: HashMap<TypeKey, RenderOverride>,
+}
+
+impl HTMLRenderer {
+    pub fn new() -> Self {
+        Self::with_options(HTMLRendererOptions::default())
+    }
+
+    pub fn with_options(options: HTMLRendererOptions) -> Self {
+        Self::with_writer(String::new(), options)
+    }
+}
+
+impl Default for HTMLRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W: fmt::Write> HTMLRenderer<W> {
+    /// Create renderer that writes into `out` as it goes.
+    pub fn with_writer(out: W, options: HTMLRendererOptions) -> Self {
+        Self {
+            out,
+            status: Ok(()),
+            line_start: true,
+            ext: RenderExtSet::new(),
+            options,
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// Return the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    /// Check if any write has failed, rendering stops after the first error.
+    pub fn status(&self) -> fmt::Result {
+        self.status
+    }
+
+    /// Render all nodes of type `T` with function `f`.
+    pub fn set_override<T: NodeValue>(&mut self, f: RenderOverride) {
+        self.overrides.insert(TypeKey::of::<T>(), f);
+    }
+
+    /// Remove override previously set with [set_override](Self::set_override).
+    pub fn remove_override<T: NodeValue>(&mut self) {
+        self.overrides.remove(&TypeKey::of::<T>());
+    }
+
+    /// Render node (and its children) appending result to the output.
+    pub fn render(&mut self, node: &Node, options: &RenderOptionsExtSet) {
+        if let Some(f) = self.overrides.get(&node.node_type).copied() {
+            f(node, self, options);
+        } else {
+            node.node_value.render(node, self, options);
+        }
+    }
+
+    // all output goes through here
+    fn write(&mut self, text: &str) {
+        if text.is_empty() || self.status.is_err() { return; }
+        self.line_start = text.ends_with('\n');
+
+        if self.options.newline == NewlineStyle::CrLf && text.contains('\n') {
+            self.write_nul_safe(&text.replace('\n', "\r\n"));
+        } else {
+            self.write_nul_safe(text);
+        }
+    }
+
+    fn write_nul_safe(&mut self, text: &str) {
+        // U+0000 must be replaced with U+FFFD as per commonmark spec,
+        // we do it on output in order to avoid messing with byte offsets
+        // for source maps (since "\0".len() != "\u{FFFD}".len())
+        self.status = if text.contains('\0') {
+            self.out.write_str(&text.replace('\0', "\u{FFFD}"))
+        } else {
+            self.out.write_str(text)
+        };
+    }
+
+    fn make_attr(&mut self, name: &str, value: &str) {
+        self.write(" ");
+        self.write(&escape_html(name));
+        self.write("=\"");
+        self.write(&escape_html(value));
+        self.write("\"");
+    }
+
+    fn make_attrs(&mut self, attrs: &[(&str, String)]) {
+        let mut attr_hash = HashMap::new();
+        let mut attr_order = Vec::with_capacity(attrs.len());
+
+        for (name, value) in attrs {
+            let entry = attr_hash.entry(*name).or_insert(Vec::new());
+            entry.push(value.as_str());
+            attr_order.push(*name);
+        }
+
+        if self.options.attribute_order == AttributeOrder::Alphabetical {
+            attr_order.sort_unstable();
+        }
+
+        for name in attr_order {
+            let Some(value) = attr_hash.remove(name) else {
+                continue;
+            };
+
+            if name == "class" {
+                self.make_attr(name, &value.join(" "));
+            } else if name == "style" {
+                self.make_attr(name, &value.join(";"));
+            } else {
+                for v in value {
+                    self.make_attr(name, v);
+                }
+            }
+        }
+    }
+}
+
+impl From<HTMLRenderer> for String {
+    fn from(f: HTMLRenderer) -> Self {
+        // writing into a string never fails
+        f.out
+    }
+}
+
+impl<W: fmt::Write> Renderer for HTMLRenderer<W> {
+    fn open(&mut self, tag: &str, attrs: &[(&str, String)]) {
+        self.write("<");
+        self.write(tag);
+        self.make_attrs(attrs);
+        self.write(">");
+    }
+
+    fn close(&mut self, tag: &str) {
+        self.write("</");
+        self.write(tag);
+        self.write(">");
+    }
+
+    fn self_close(&mut self, tag: &str, attrs: &[(&str, String)]) {
+        self.write("<");
+        self.write(tag);
+        self.make_attrs(attrs);
+        if self.options.xhtml {
+            self.write(" /");
+        }
+        self.write(">");
+    }
+
+    fn contents(&mut self, nodes: &[Node], options: &RenderOptionsExtSet) {
+        for node in nodes.iter() {
+            self.render(node, options);
+        }
+    }
+
+    fn cr(&mut self) {
+        // only push '\n' if last character isn't it
+        if !self.line_start {
+            self.write("\n");
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        self.write(&escape_html(text));
+    }
+
+    fn text_raw(&mut self, text: &str) {
+        self.write(text);
+    }
+
+    fn ext(&mut self) -> &mut RenderExtSet {
+        &mut self.ext
+    }
+}
+
+#[derive(Debug)]
+/// Adapter that allows [HTMLRenderer] to write into [io::Write].
+pub struct IoWriter<W: io::Write> {
+    inner: W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> IoWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner, error: None }
+    }
+
+    /// Return the underlying writer, or the first error that occurred.
+    pub fn finish(self) -> io::Result<W> {
+        match self.error {
+            Some(err) => Err(err),
+            None => Ok(self.inner),
+        }
+    }
+}
+
+impl<W: io::Write> fmt::Write for IoWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
@@ -0,0 +1,413 @@
+//! Plain text renderer, for search indexing and previews.
+//!
+//! Nodes are rendered the usual way, and renderer translates html structure
+//! into readable text: blocks are separated by empty lines, list items get
+//! bullets, table rows become tab-separated lines, images are replaced
+//! with their alt text, and raw html is stripped of tags.
+//!
+//! ```rust
+//! use markdown_it::parser::renderer::text::TextRenderer;
+//!
+//! let md = &mut markdown_it::MarkdownIt::new();
+//! markdown_it::plugins::cmark::add(md);
+//!
+//! let mut fmt = TextRenderer::new();
+//! fmt.render(&md.parse("# Title\n\n- *one*\n- `two`"), &Default::default());
+//! assert_eq!(String::from(fmt), "Title\n\n- one\n- two\n");
+//! ```
+//!
+//! If a plugin node needs different output, register an override for it
+//! with [TextRenderer::set_override].
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::common::TypeKey;
+use crate::parser::extset::{RenderExtSet, RenderOptionsExtSet};
+use crate::plugins::pandoc::footnote::back_refs::FootnoteRefAnchor;
+use crate::{Node, NodeValue, Renderer};
+
+use super::RenderOverride;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Options for [TextRenderer].
+pub struct TextRendererOptions {
+    /// Append link target after link text, `text (https://example.org)`.
+    pub link_urls: bool,
+    /// Marker for bullet list items.
+    pub bullet: &'static str,
+}
+
+impl Default for TextRendererOptions {
+    fn default() -> Self {
+        Self { link_urls: false, bullet: "-" }
+    }
+}
+
+#[derive(Debug)]
+enum ListKind {
+    Bullet,
+    Ordered(usize),
+}
+
+#[derive(Debug)]
+/// Renderer that outputs plain text instead of html.
+pub struct TextRenderer {
+    result: String,
+    ext: RenderExtSet,
+    options: TextRendererOptions,
+    overrides: HashMap<TypeKey, RenderOverride>,
+
+    // newlines required before the next text
+    pending_newlines: usize,
+    // list marker to output before the next text
+    pending_marker: Option<String>,
+    line_start: bool,
+    indent: Vec<usize>,
+    lists: Vec<ListKind>,
+    // start of the link text in result, and link url
+    links: Vec<(usize, Option<String>)>,
+    table_cell: usize,
+    in_row: bool,
+    pre: usize,
+}
+
+impl TextRenderer {
+    pub fn new() -> Self {
+        Self::with_options(TextRendererOptions::default())
+    }
+
+    pub fn with_options(options: TextRendererOptions) -> Self {
+        let mut result = Self {
+            result: String::new(),
+            ext: RenderExtSet::new(),
+            options,
+            overrides: HashMap::new(),
+            pending_newlines: 0,
+            pending_marker: None,
+            line_start: true,
+            indent: Vec::new(),
+            lists: Vec::new(),
+            links: Vec::new(),
+            table_cell: 0,
+            in_row: false,
+            pre: 0,
+        };
+
+        // footnote back references (↩) are only useful in html
+        result.set_override::<FootnoteRefAnchor>(|_, _, _| {});
+        result
+    }
+
+    /// Render all nodes of type `T` with function `f`.
+    pub fn set_override<T: NodeValue>(&mut self, f: RenderOverride) {
+        self.overrides.insert(TypeKey::of::<T>(), f);
+    }
+
+    /// Remove override previously set with [set_override](Self::set_override).
+    pub fn remove_override<T: NodeValue>(&mut self) {
+        self.overrides.remove(&TypeKey::of::<T>());
+    }
+
+    /// Render node (and its children) appending result to the buffer.
+    pub fn render(&mut self, node: &Node, options: &RenderOptionsExtSet) {
+        if let Some(f) = self.overrides.get(&node.node_type).copied() {
+            f(node, self, options);
+        } else {
+            node.node_value.render(node, self, options);
+        }
+    }
+
+    fn block_break(&mut self, newlines: usize) {
+        self.pending_newlines = self.pending_newlines.max(newlines);
+    }
+
+    fn write(&mut self, text: &str) {
+        for (idx, line) in text.split('\n').enumerate() {
+            if idx > 0 {
+                if self.pre > 0 {
+                    self.flush();
+                    self.result.push('\n');
+                    self.line_start = true;
+                } else {
+                    self.cr();
+                }
+            }
+            if line.is_empty() { continue; }
+
+            // don't start new lines with whitespace, unless it's code
+            let at_line_start = self.line_start || (self.pending_newlines > 0 && !self.result.is_empty());
+            if at_line_start && self.pre == 0 && line.trim().is_empty() { continue; }
+
+            self.flush();
+            self.result.push_str(line);
+        }
+    }
+
+    // write pending newlines, indentation and list marker
+    fn flush(&mut self) {
+        if self.pending_newlines > 0 && !self.result.is_empty() {
+            let existing = self.result.len() - self.result.trim_end_matches('\n').len();
+            for _ in existing..self.pending_newlines {
+                self.result.push('\n');
+            }
+            self.line_start = true;
+        }
+        self.pending_newlines = 0;
+
+        if self.line_start {
+            let width = self.indent.iter().sum::<usize>();
+            if let Some(marker) = self.pending_marker.take() {
+                let width = width - marker.len() - 1;
+                self.result.push_str(&" ".repeat(width));
+                self.result.push_str(&marker);
+                self.result.push(' ');
+            } else {
+                self.result.push_str(&" ".repeat(width));
+            }
+            self.line_start = false;
+        }
+    }
+
+    fn open_list_item(&mut self) {
+        let marker = match self.lists.last_mut() {
+            Some(ListKind::Ordered(number)) => {
+                *number += 1;
+                format!("{}.", *number - 1)
+            }
+            Some(ListKind::Bullet) | None => self.options.bullet.to_owned(),
+        };
+        self.block_break(1);
+        self.indent.push(marker.len() + 1);
+        self.pending_marker = Some(match self.pending_marker.take() {
+            // nested list right at the start of another list item, `- - a`
+            Some(parent) => format!("{} {}", parent, marker),
+            None => marker,
+        });
+    }
+}
+
+impl Default for TextRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<TextRenderer> for String {
+    fn from(f: TextRenderer) -> Self {
+        let mut result = f.result.trim_end().replace('\0', "\u{FFFD}");
+        if !result.is_empty() {
+            result.push('\n');
+        }
+        result
+    }
+}
+
+fn get_attr<'a>(attrs: &'a [(&str, String)], name: &str) -> Option<&'a str> {
+    attrs.iter().find(|(n, _)| *n == name).map(|(_, value)| value.as_str())
+}
+
+impl Renderer for TextRenderer {
+    fn open(&mut self, tag: &str, attrs: &[(&str, String)]) {
+        match tag {
+            "ul" | "ol" => {
+                self.block_break(if self.lists.is_empty() { 2 } else { 1 });
+                self.lists.push(if tag == "ol" {
+                    ListKind::Ordered(get_attr(attrs, "start").and_then(|s| s.parse().ok()).unwrap_or(1))
+                } else {
+                    ListKind::Bullet
+                });
+            }
+            "li" => self.open_list_item(),
+            "dt" => self.block_break(1),
+            "dd" => {
+                self.block_break(1);
+                self.indent.push(2);
+            }
+            "tr" => {
+                self.block_break(1);
+                self.table_cell = 0;
+                self.in_row = true;
+            }
+            "td" | "th" => {
+                if self.table_cell > 0 {
+                    self.flush();
+                    self.result.push('\t');
+                }
+                self.table_cell += 1;
+            }
+            "pre" => {
+                self.block_break(2);
+                self.pre += 1;
+            }
+            "a" => {
+                self.flush();
+                self.links.push((self.result.len(), get_attr(attrs, "href").map(str::to_owned)));
+            }
+            "p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "blockquote" | "table" | "dl"
+                | "div" | "section" | "figure" | "details" | "aside" | "header" | "footer" => {
+                self.block_break(2);
+            }
+            _ => {}
+        }
+    }
+
+    fn close(&mut self, tag: &str) {
+        match tag {
+            "ul" | "ol" => {
+                self.lists.pop();
+                self.block_break(if self.lists.is_empty() { 2 } else { 1 });
+            }
+            "li" | "dd" => {
+                if self.pending_marker.is_some() {
+                    // empty list item
+                    self.flush();
+                    self.result.pop();
+                }
+                self.indent.pop();
+                self.block_break(1);
+            }
+            "dt" => self.block_break(1),
+            "tr" => {
+                self.in_row = false;
+                self.block_break(1);
+            }
+            "pre" => {
+                self.pre = self.pre.saturating_sub(1);
+                self.block_break(2);
+            }
+            "a" => {
+                let Some((start, Some(url))) = self.links.pop() else { return };
+                if !self.options.link_urls || url.starts_with('#') { return; }
+                let text = &self.result[start..];
+                if text != url && text != url.trim_start_matches("mailto:") {
+                    self.write(&format!(" ({})", url));
+                }
+            }
+            "p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "blockquote" | "table" | "dl"
+                | "div" | "section" | "figure" | "details" | "aside" | "header" | "footer" => {
+                self.block_break(2);
+            }
+            _ => {}
+        }
+    }
+
+    fn self_close(&mut self, tag: &str, attrs: &[(&str, String)]) {
+        match tag {
+            "img" => {
+                if let Some(alt) = get_attr(attrs, "alt") {
+                    self.write(alt);
+                }
+            }
+            "input" if get_attr(attrs, "type") == Some("checkbox") => {
+                let checked = get_attr(attrs, "checked").is_some();
+                self.write(if checked { "[x]" } else { "[ ]" });
+            }
+            "br" => {
+                self.write("\n");
+            }
+            "hr" => self.block_break(2),
+            _ => {}
+        }
+    }
+
+    fn contents(&mut self, nodes: &[Node], options: &RenderOptionsExtSet) {
+        for node in nodes.iter() {
+            self.render(node, options);
+        }
+    }
+
+    fn cr(&mut self) {
+        // table cells are separated by tabs instead
+        if !self.line_start && !self.in_row {
+            self.block_break(1);
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        self.write(text);
+    }
+
+    fn text_raw(&mut self, text: &str) {
+        static TAG_RE : Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<!--.*?-->|<[^>]*>").unwrap());
+
+        let text = TAG_RE.replace_all(text, "");
+        let text = html_escape::decode_html_entities(&text);
+        self.write(&text);
+    }
+
+    fn ext(&mut self) -> &mut RenderExtSet {
+        &mut self.ext
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::plugins::{cmark, extra, gfm, html, pandoc};
+    use crate::MarkdownIt;
+
+    use super::*;
+
+    fn render(src: &str, options: TextRendererOptions) -> String {
+        let md = &mut MarkdownIt::new();
+        cmark::add(md);
+        html::add(md);
+        extra::tables::add(md);
+        gfm::tasklist::add(md);
+        pandoc::add(md);
+
+        let mut fmt = TextRenderer::with_options(options);
+        fmt.render(&md.parse(src), &Default::default());
+        fmt.into()
+    }
+
+    #[rstest]
+    #[case("foo *bar*\nbaz\n\nquux", "foo bar\nbaz\n\nquux\n")]
+    #[case("# a\n\n> b\n\n---\n\nc", "a\n\nb\n\nc\n")]
+    #[case("- a\n- b\n  - c\n  - d\n- e", "- a\n- b\n  - c\n  - d\n- e\n")]
+    #[case("3. a\n4. b\n\n   c", "3. a\n\n4. b\n\n   c\n")]
+    #[case("- a\n-\n- b", "- a\n-\n- b\n")]
+    #[case("- - a\n  - b", "- - a\n  - b\n")]
+    #[case("text\n\n    code\n      indented\n\nmore", "text\n\ncode\n  indented\n\nmore\n")]
+    #[case("| a | b |\n|---|---|\n| 1 | 2 |", "a\tb\n1\t2\n")]
+    #[case("![alt *text*](/img.png) `code`", "alt text code\n")]
+    #[case("a  \nb", "a\nb\n")]
+    #[case("<div>\n<b>raw</b> &amp; html\n</div>", "raw & html\n")]
+    #[case("- [x] done\n- [ ] todo", "- [x] done\n- [ ] todo\n")]
+    #[case("term\n: definition", "term\n  definition\n")]
+    #[case("text[^1]\n\n[^1]: note", "text[1]\n\n1. note\n")]
+    #[case("[link](https://example.org)", "link\n")]
+    fn plain_text(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(render(input, TextRendererOptions::default()), expected);
+    }
+
+    #[rstest]
+    #[case("[link](https://example.org)", "link (https://example.org)\n")]
+    #[case("<https://example.org> <a@b.c>", "https://example.org a@b.c\n")]
+    #[case("- [a](/x)", "- a (/x)\n")]
+    #[case("[a](#anchor)", "a\n")]
+    fn link_urls(#[case] input: &str, #[case] expected: &str) {
+        let options = TextRendererOptions { link_urls: true, bullet: "*" };
+        assert_eq!(render(input, options), expected.replace("- ", "* "));
+    }
+
+    #[test]
+    fn override_node() {
+        use crate::plugins::cmark::inline::backticks::CodeInline;
+
+        let md = &mut MarkdownIt::new();
+        cmark::add(md);
+
+        let mut fmt = TextRenderer::new();
+        fmt.set_override::<CodeInline>(|node, fmt, _| {
+            fmt.text("'");
+            fmt.text(&node.collect_text());
+            fmt.text("'");
+        });
+        fmt.render(&md.parse("a `b` c"), &Default::default());
+        assert_eq!(String::from(fmt), "a 'b' c\n");
+    }
+}
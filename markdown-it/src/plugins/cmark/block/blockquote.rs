@@ -3,23 +3,18 @@
 //! `> looks like this`
 //!
 //! <https://spec.commonmark.org/0.30/#block-quotes>
-use std::collections::HashMap;
 
 use crate::common::utils::find_indent_of;
 use crate::parser::block::{BlockRule, BlockState};
+use crate::parser::extset::RenderOptionsExtSet;
 use crate::{MarkdownIt, Node, NodeValue, Renderer};
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub struct Blockquote;
 
 impl NodeValue for Blockquote {
-    fn render(
-        &self,
-        node: &Node,
-        fmt: &mut dyn Renderer,
-        options: &HashMap<String, String>,
-        cache: &mut HashMap<String, String>,
-    ) {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
         fmt.cr();
         fmt.open("blockquote", &node.attrs);
         fmt.cr();
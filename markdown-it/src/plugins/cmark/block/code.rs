@@ -3,20 +3,26 @@
 //! Parses anything indented with 4 spaces.
 //!
 //! <https://spec.commonmark.org/0.30/#indented-code-block>
-use std::collections::HashMap;
 
 use crate::parser::block::{BlockRule, BlockState};
+use crate::parser::extset::RenderOptionsExtSet;
 use crate::{MarkdownIt, Node, NodeValue, Renderer};
 
 const CODE_INDENT: i32 = 4;
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CodeBlock {
     pub content: String,
 }
 
 impl NodeValue for CodeBlock {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, _options: &HashMap<String, String>) {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, _options: &RenderOptionsExtSet) {
         fmt.cr();
         fmt.open("pre", &[]);
         fmt.open("code", &node.attrs);
@@ -2,15 +2,18 @@
 //!
 //! ` ```lang ` or `~~~lang`
 //!
+//! Info string may also contain line options, e.g. ` ```rust {3,5-7} linenos `,
+//! see [FenceLineOptions].
+//!
 //! <https://spec.commonmark.org/0.30/#code-fence>
-use std::collections::HashMap;
 
 use crate::common::utils::unescape_all;
 use crate::parser::block::{BlockRule, BlockState};
-use crate::parser::extset::MarkdownItExt;
+use crate::parser::extset::{MarkdownItExt, RenderOptionsExtSet};
 use crate::{MarkdownIt, Node, NodeValue, Renderer};
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct CodeFence {
     pub info: String,
     pub marker: char,
@@ -20,7 +23,12 @@ pub struct CodeFence {
 }
 
 impl NodeValue for CodeFence {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, _options: &HashMap<String, String>) {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, _options: &RenderOptionsExtSet) {
         let info = unescape_all(&self.info);
         let mut split = info.split_whitespace();
         let lang_name = split.next().unwrap_or("");
@@ -32,16 +40,108 @@ impl NodeValue for CodeFence {
             attrs.push(("class", class));
         }
 
+        let options = FenceLineOptions::parse(&info);
+
         fmt.cr();
         fmt.open("pre", &[]);
             fmt.open("code", &attrs);
-            fmt.text(&self.content);
+            if options.is_empty() {
+                fmt.text(&self.content);
+            } else {
+                for (idx, line) in self.content.split_inclusive('\n').enumerate() {
+                    let number = idx + 1;
+                    fmt.open("span", &options.line_attrs(number));
+                    if options.line_numbers {
+                        fmt.open("span", &[("class", "line-number".into())]);
+                        fmt.text(&number.to_string());
+                        fmt.close("span");
+                    }
+                    fmt.text(line);
+                    fmt.close("span");
+                }
+            }
             fmt.close("code");
         fmt.close("pre");
         fmt.cr();
     }
 }
 
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+/// Line options in fence info string, ` ```rust {3,5-7} linenos `.
+///
+/// When any of these are present, each line of the code is wrapped
+/// in `<span class="line" data-line="N">`, highlighted lines get
+/// additional `highlighted` class.
+pub struct FenceLineOptions {
+    /// Highlighted line ranges (1-based, inclusive).
+    pub highlight: Vec<(usize, usize)>,
+    /// Show line numbers (`linenos`).
+    pub line_numbers: bool,
+}
+
+impl FenceLineOptions {
+    /// Parse options from unescaped info string (first word is language, it is skipped).
+    pub fn parse(info: &str) -> Self {
+        let mut result = Self::default();
+        let rest = info.trim_start();
+        let mut rest = &rest[rest.find(char::is_whitespace).unwrap_or(rest.len())..];
+
+        loop {
+            rest = rest.trim_start();
+            if rest.is_empty() { break; }
+
+            if let Some(group) = rest.strip_prefix('{') {
+                let Some(end) = group.find('}') else { break };
+                if let Some(ranges) = Self::parse_ranges(&group[..end]) {
+                    result.highlight.extend(ranges);
+                }
+                rest = &group[end + 1..];
+                continue;
+            }
+
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            if &rest[..end] == "linenos" {
+                result.line_numbers = true;
+            }
+            rest = &rest[end..];
+        }
+
+        result
+    }
+
+    // `3,5-7`, returns None if it's something else (e.g. attributes)
+    fn parse_ranges(src: &str) -> Option<Vec<(usize, usize)>> {
+        let mut ranges = Vec::new();
+        for range in src.split(',') {
+            let range = range.trim();
+            let (start, end) = range.split_once('-').unwrap_or((range, range));
+            let start = start.trim().parse::<usize>().ok()?;
+            let end = end.trim().parse::<usize>().ok()?;
+            ranges.push((start, end));
+        }
+        Some(ranges)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.highlight.is_empty() && !self.line_numbers
+    }
+
+    /// Check whether line `number` (1-based) should be highlighted.
+    pub fn is_highlighted(&self, number: usize) -> bool {
+        self.highlight.iter().any(|(start, end)| (*start..=*end).contains(&number))
+    }
+
+    /// Attributes for wrapper of line `number` (1-based).
+    pub fn line_attrs(&self, number: usize) -> Vec<(&'static str, String)> {
+        let class = if self.is_highlighted(number) { "line highlighted" } else { "line" };
+        vec![
+            ("class", class.into()),
+            ("data-line", number.to_string()),
+        ]
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 struct FenceSettings(&'static str);
 impl MarkdownItExt for FenceSettings {}
@@ -4,18 +4,23 @@
 //!
 //! <https://spec.commonmark.org/0.30/#atx-heading>
 use crate::parser::block::{BlockRule, BlockState};
+use crate::parser::extset::RenderOptionsExtSet;
 use crate::parser::inline::InlineRoot;
 use crate::{MarkdownIt, Node, NodeValue, Renderer};
 
-use std::collections::HashMap;
-
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ATXHeading {
     pub level: u8,
 }
 
 impl NodeValue for ATXHeading {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &HashMap<String, String>) {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
         static TAG: [&str; 6] = ["h1", "h2", "h3", "h4", "h5", "h6"];
         debug_assert!(self.level >= 1 && self.level <= 6);
 
@@ -4,18 +4,23 @@
 //!
 //! <https://spec.commonmark.org/0.30/#thematic-breaks>
 use crate::parser::block::{BlockRule, BlockState};
+use crate::parser::extset::RenderOptionsExtSet;
 use crate::{MarkdownIt, Node, NodeValue, Renderer};
 
-use std::collections::HashMap;
-
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ThematicBreak {
     pub marker: char,
     pub marker_len: usize,
 }
 
 impl NodeValue for ThematicBreak {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, _options: &HashMap<String, String>) {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, _options: &RenderOptionsExtSet) {
         fmt.cr();
         fmt.self_close("hr", &node.attrs);
         fmt.cr();
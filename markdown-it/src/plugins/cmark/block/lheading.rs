@@ -3,21 +3,27 @@
 //! Paragraph underlined with `===` or `---`.
 //!
 //! <https://spec.commonmark.org/0.30/#setext-headings>
-use std::collections::HashMap;
 
 use crate::parser::block::{BlockRule, BlockState};
+use crate::parser::extset::RenderOptionsExtSet;
 use crate::parser::inline::InlineRoot;
 use crate::plugins::cmark::block::paragraph::ParagraphScanner;
 use crate::{MarkdownIt, Node, NodeValue, Renderer};
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SetextHeader {
     pub level: u8,
     pub marker: char,
 }
 
 impl NodeValue for SetextHeader {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &HashMap<String, String>) {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
         static TAG: [&str; 2] = ["h1", "h2"];
         debug_assert!(self.level >= 1 && self.level <= 2);
 
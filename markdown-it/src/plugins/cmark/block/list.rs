@@ -8,20 +8,25 @@
 //!  - <https://spec.commonmark.org/0.30/#list-items>
 use crate::common::utils::find_indent_of;
 use crate::parser::block::{BlockRule, BlockState};
+use crate::parser::extset::RenderOptionsExtSet;
 use crate::plugins::cmark::block::hr::HrScanner;
 use crate::plugins::cmark::block::paragraph::Paragraph;
 use crate::{MarkdownIt, Node, NodeValue, Renderer};
 
-use std::collections::HashMap;
-
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OrderedList {
     pub start: u32,
     pub marker: char,
 }
 
 impl NodeValue for OrderedList {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &HashMap<String, String>) {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
         let mut attrs = node.attrs.clone();
         let start;
         if self.start != 1 {
@@ -39,12 +44,18 @@ impl NodeValue for OrderedList {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BulletList {
     pub marker: char,
 }
 
 impl NodeValue for BulletList {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &HashMap<String, String>) {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
         fmt.cr();
         fmt.open("ul", &node.attrs);
         fmt.cr();
@@ -56,10 +67,11 @@ impl NodeValue for BulletList {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub struct ListItem;
 
 impl NodeValue for ListItem {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &HashMap<String, String>) {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
         fmt.open("li", &node.attrs);
         fmt.contents(&node.children, options);
         fmt.close("li");
@@ -16,7 +16,7 @@ use std::fmt::Debug;
 use crate::common::utils::normalize_reference;
 use crate::generics::inline::full_link;
 use crate::parser::block::{BlockRule, BlockState};
-use crate::parser::extset::RootExt;
+use crate::parser::extset::{RenderOptionsExtSet, RootExt};
 use crate::{MarkdownIt, Node, NodeValue};
 
 /// Storage for parsed references
@@ -63,7 +63,7 @@ use crate::{MarkdownIt, Node, NodeValue};
 /// md.add_rule::<AddCustomReferences>()
 ///     .before::<BlockParserRule>();
 ///
-/// let html = md.parse("[rust]").render();
+/// let html = md.parse("[rust]").render(&Default::default());
 /// assert_eq!(
 ///     html.trim(),
 ///     r#"<p><a href="https://www.rust-lang.org/" title="The Rust Language">rust</a></p>"#
@@ -202,13 +202,19 @@ pub fn add(md: &mut MarkdownIt) {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Definition {
     pub label: String,
     pub destination: String,
     pub title: Option<String>,
 }
 impl NodeValue for Definition {
-    fn render(&self, _: &Node, _: &mut dyn crate::Renderer, _: &HashMap<String, String>) {}
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, _: &Node, _: &mut dyn crate::Renderer, _: &RenderOptionsExtSet) {}
 }
 
 #[doc(hidden)]
@@ -3,21 +3,27 @@
 //! `<https://example.org>`
 //!
 //! <https://spec.commonmark.org/0.30/#autolinks>
-use std::collections::HashMap;
 
 use once_cell::sync::Lazy;
 use regex::Regex;
 
+use crate::parser::extset::RenderOptionsExtSet;
 use crate::parser::inline::{InlineRule, InlineState, TextSpecial};
 use crate::{MarkdownIt, Node, NodeValue, Renderer};
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Autolink {
     pub url: String,
 }
 
 impl NodeValue for Autolink {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &HashMap<String, String>) {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
         let mut attrs = node.attrs.clone();
         attrs.push(("href", self.url.clone()));
 
@@ -87,7 +93,9 @@ impl InlineRule for AutolinkScanner {
         });
         inner_node.srcmap = state.get_map(state.pos + 1, pos - 1);
 
+        let attrs = state.md.link_formatter.link_attrs(&full_url);
         let mut node = Node::new(Autolink { url: full_url });
+        node.attrs = attrs;
         node.children.push(inner_node);
 
         Some((node, pos - state.pos))
@@ -3,19 +3,25 @@
 //! `` `looks like this` ``
 //!
 //! <https://spec.commonmark.org/0.30/#code-span>
-use std::collections::HashMap;
 
 use crate::generics::inline::code_pair;
+use crate::parser::extset::RenderOptionsExtSet;
 use crate::{MarkdownIt, Node, NodeValue, Renderer};
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CodeInline {
     pub marker: char,
     pub marker_len: usize,
 }
 
 impl NodeValue for CodeInline {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &HashMap<String, String>) {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
         fmt.open("code", &node.attrs);
         fmt.contents(&node.children, options);
         fmt.close("code");
@@ -3,18 +3,24 @@
 //! looks like `*this*` or `__that__`
 //!
 //! <https://spec.commonmark.org/0.30/#emphasis-and-strong-emphasis>
-use std::collections::HashMap;
 
 use crate::generics::inline::emph_pair;
+use crate::parser::extset::RenderOptionsExtSet;
 use crate::{MarkdownIt, Node, NodeValue, Renderer};
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Em {
     pub marker: char,
 }
 
 impl NodeValue for Em {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &HashMap<String, String>) {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
         fmt.open("em", &node.attrs);
         fmt.contents(&node.children, options);
         fmt.close("em");
@@ -22,12 +28,18 @@ impl NodeValue for Em {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Strong {
     pub marker: char,
 }
 
 impl NodeValue for Strong {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &HashMap<String, String>) {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
         fmt.open("strong", &node.attrs);
         fmt.contents(&node.children, options);
         fmt.close("strong");
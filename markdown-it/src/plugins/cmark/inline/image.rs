@@ -3,19 +3,25 @@
 //! `![image](<src> "title")`
 //!
 //! <https://spec.commonmark.org/0.30/#images>
-use std::collections::HashMap;
 
 use crate::generics::inline::full_link;
+use crate::parser::extset::RenderOptionsExtSet;
 use crate::{MarkdownIt, Node, NodeValue, Renderer};
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Image {
     pub url: String,
     pub title: Option<String>,
 }
 
 impl NodeValue for Image {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, _option: &HashMap<String, String>) {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, _option: &RenderOptionsExtSet) {
         let mut attrs = node.attrs.clone();
         attrs.push(("src", self.url.clone()));
         attrs.push(("alt", node.collect_text()));
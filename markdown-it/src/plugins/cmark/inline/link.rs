@@ -3,19 +3,27 @@
 //! `![link](<to> "stuff")`
 //!
 //! <https://spec.commonmark.org/0.30/#links>
-use std::collections::HashMap;
 
 use crate::generics::inline::full_link;
+use crate::parser::core::CoreRule;
+use crate::parser::extset::RenderOptionsExtSet;
+use crate::parser::inline::builtin::InlineParserRule;
 use crate::{MarkdownIt, Node, NodeValue, Renderer};
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Link {
     pub url: String,
     pub title: Option<String>,
 }
 
 impl NodeValue for Link {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &HashMap<String, String>) {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
         let mut attrs = node.attrs.clone();
         attrs.push(("href", self.url.clone()));
 
@@ -36,4 +44,21 @@ pub fn add(md: &mut MarkdownIt) {
             title,
         })
     });
+    md.add_rule::<LinkAttrsRule>()
+        .after::<InlineParserRule>();
+}
+
+// links are created by a generic rule shared with images,
+// so attributes from link formatter are added afterwards
+#[doc(hidden)]
+pub struct LinkAttrsRule;
+impl CoreRule for LinkAttrsRule {
+    fn run(root: &mut Node, md: &MarkdownIt) {
+        root.walk_mut(|node, _| {
+            if let Some(link) = node.cast::<Link>() {
+                let attrs = md.link_formatter.link_attrs(&link.url);
+                node.attrs.extend(attrs);
+            }
+        });
+    }
 }
@@ -0,0 +1,304 @@
+//! Resolve relative links and images against a base url
+//!
+//! Useful when notes are published from nested folders: relative urls
+//! are mapped and resolved against location of the output document,
+//! links to `.md` files are rewritten to `.html`. Every rewritten url is
+//! recorded in [RewrittenUrls], so build tools know which assets to copy.
+//!
+//! ```rust
+//! use markdown_it::plugins::extra::base_url::{self, BaseUrlOptions, RewrittenUrls};
+//!
+//! let md = &mut markdown_it::MarkdownIt::new();
+//! markdown_it::plugins::cmark::add(md);
+//! base_url::add_with_options(md, BaseUrlOptions {
+//!     base: "https://example.org/notes/rust/".into(),
+//!     ..Default::default()
+//! });
+//!
+//! let ast = md.parse("[intro](../intro.md#setup) ![logo](img/logo.png)");
+//! assert_eq!(ast.render(&Default::default()).trim(), "<p>\
+//!     <a href=\"https://example.org/notes/intro.html#setup\">intro</a> \
+//!     <img src=\"https://example.org/notes/rust/img/logo.png\" alt=\"logo\"></p>");
+//!
+//! let rewritten = ast.cast::<markdown_it::parser::core::Root>().unwrap().ext.get::<RewrittenUrls>().unwrap();
+//! assert_eq!(rewritten.urls[1].original, "img/logo.png");
+//! ```
+use crate::parser::core::{CoreRule, Root};
+use crate::parser::extset::{MarkdownItExt, RootExt};
+use crate::parser::inline::builtin::InlineParserRule;
+use crate::plugins::cmark::inline::image::Image;
+use crate::plugins::cmark::inline::link::{Link, LinkAttrsRule};
+use crate::{MarkdownIt, Node};
+
+#[derive(Debug, Clone)]
+/// Options for the base url plugin.
+pub struct BaseUrlOptions {
+    /// Url or path that relative urls are resolved against, e.g.
+    /// `https://example.org/notes/` or `/notes/`. It should end with `/`,
+    /// otherwise last path segment is treated as a file name.
+    /// Empty string keeps urls relative.
+    pub base: String,
+    /// Prefix replacements applied to relative urls before resolving,
+    /// `(from, to)`, first match is used.
+    pub mappings: Vec<(String, String)>,
+    /// Rewrite links to `.md` files to `.html`.
+    pub md_to_html: bool,
+}
+
+impl Default for BaseUrlOptions {
+    fn default() -> Self {
+        Self {
+            base: String::new(),
+            mappings: Vec::new(),
+            md_to_html: true,
+        }
+    }
+}
+
+impl MarkdownItExt for BaseUrlOptions {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Url changed by the plugin.
+pub struct RewrittenUrl {
+    /// Url as written in the source (normalized by link formatter).
+    pub original: String,
+    /// Url written to the output.
+    pub url: String,
+    /// True for images, false for links.
+    pub image: bool,
+}
+
+#[derive(Debug, Default)]
+/// Urls rewritten in the document, stored in the root node.
+pub struct RewrittenUrls {
+    pub urls: Vec<RewrittenUrl>,
+}
+
+impl RootExt for RewrittenUrls {}
+
+pub fn add(md: &mut MarkdownIt) {
+    add_with_options(md, BaseUrlOptions::default());
+}
+
+pub fn add_with_options(md: &mut MarkdownIt, options: BaseUrlOptions) {
+    md.ext.insert(options);
+    // attributes for external links depend on the resolved url
+    md.add_rule::<BaseUrlRule>()
+        .after::<InlineParserRule>()
+        .before::<LinkAttrsRule>();
+}
+
+/// Change base url for documents parsed after this call,
+/// e.g. when documents are located in different folders.
+pub fn set_base(md: &mut MarkdownIt, base: impl Into<String>) {
+    md.ext.get_or_insert_default::<BaseUrlOptions>().base = base.into();
+}
+
+#[doc(hidden)]
+pub struct BaseUrlRule;
+impl CoreRule for BaseUrlRule {
+    fn run(root: &mut Node, md: &MarkdownIt) {
+        let Some(options) = md.ext.get::<BaseUrlOptions>() else { return; };
+        let mut rewritten = RewrittenUrls::default();
+
+        root.walk_mut(|node, _| {
+            let (url, image) = if let Some(link) = node.cast_mut::<Link>() {
+                (&mut link.url, false)
+            } else if let Some(image) = node.cast_mut::<Image>() {
+                (&mut image.url, true)
+            } else {
+                return;
+            };
+
+            if let Some(new_url) = rewrite_url(url, image, options) {
+                let original = std::mem::replace(url, new_url.clone());
+                rewritten.urls.push(RewrittenUrl { original, url: new_url, image });
+            }
+        });
+
+        if !rewritten.urls.is_empty() {
+            root.cast_mut::<Root>().unwrap().ext.insert(rewritten);
+        }
+    }
+}
+
+/// Returns new url if `url` is relative and needs to be changed.
+pub fn rewrite_url(url: &str, image: bool, options: &BaseUrlOptions) -> Option<String> {
+    if url.is_empty() || url.starts_with('#') || url.starts_with("//") || has_scheme(url) {
+        return None;
+    }
+
+    let mut result = url.to_owned();
+
+    if let Some((from, to)) = options.mappings.iter().find(|(from, _)| url.starts_with(from.as_str())) {
+        result = format!("{}{}", to, &url[from.len()..]);
+    }
+
+    let split = result.find(['?', '#']).unwrap_or(result.len());
+    let (path, suffix) = result.split_at(split);
+
+    let mut path = path.to_owned();
+    if !image && options.md_to_html && path.len() > 3 &&
+        path.as_bytes()[path.len() - 3..].eq_ignore_ascii_case(b".md") {
+        path.truncate(path.len() - 3);
+        path.push_str(".html");
+    }
+
+    if !options.base.is_empty() && !has_scheme(&path) && !path.starts_with("//") {
+        path = resolve(&options.base, &path);
+    }
+
+    let result = path + suffix;
+    (result != url).then_some(result)
+}
+
+fn has_scheme(url: &str) -> bool {
+    let Some(colon) = url.find(':') else { return false; };
+    let scheme = &url[..colon];
+    scheme.starts_with(|c: char| c.is_ascii_alphabetic()) &&
+        scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+// resolve relative path against base url (without query and fragment, RFC 3986 5.2)
+fn resolve(base: &str, path: &str) -> String {
+    // `scheme://host` part of the base, if any
+    let origin_len = match base.find("://") {
+        Some(idx) if has_scheme(&base[..idx + 1]) => {
+            let host_start = idx + 3;
+            base[host_start..].find('/').map(|i| host_start + i).unwrap_or(base.len())
+        }
+        _ => 0,
+    };
+    let (origin, base_path) = base.split_at(origin_len);
+    let base_path = &base_path[..base_path.find(['?', '#']).unwrap_or(base_path.len())];
+
+    let merged = if path.starts_with('/') {
+        path.to_owned()
+    } else if origin_len > 0 && base_path.is_empty() {
+        format!("/{path}")
+    } else {
+        let dir = &base_path[..base_path.rfind('/').map(|i| i + 1).unwrap_or(0)];
+        format!("{dir}{path}")
+    };
+
+    format!("{origin}{}", remove_dot_segments(&merged))
+}
+
+fn remove_dot_segments(path: &str) -> String {
+    let absolute = path.starts_with('/');
+    let segments = path.split('/').collect::<Vec<_>>();
+    let mut result: Vec<&str> = Vec::new();
+
+    for (idx, segment) in segments.iter().enumerate() {
+        let last = idx == segments.len() - 1;
+        match *segment {
+            "." => if last { result.push(""); },
+            ".." => {
+                match result.last() {
+                    Some(&"..") | None if !absolute => result.push(".."),
+                    Some(&"") if absolute && result.len() == 1 => {}
+                    _ => { result.pop(); }
+                }
+                if last { result.push(""); }
+            }
+            _ => result.push(segment),
+        }
+    }
+
+    if absolute && result.first() != Some(&"") {
+        result.insert(0, "");
+    }
+    result.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("https://example.org/a/b/", "c.png", "https://example.org/a/b/c.png")]
+    #[case("https://example.org/a/b/", "../c.png", "https://example.org/a/c.png")]
+    #[case("https://example.org/a/b/", "../../../c.png", "https://example.org/c.png")]
+    #[case("https://example.org/a/b/", "/c.png", "https://example.org/c.png")]
+    #[case("https://example.org/a/page.html", "./c/./d", "https://example.org/a/c/d")]
+    #[case("https://example.org", "c.png", "https://example.org/c.png")]
+    #[case("/notes/", "../c.png", "/c.png")]
+    #[case("/notes/", "../../c.png", "/c.png")]
+    #[case("notes/a/", "../../../c", "../c")]
+    #[case("notes/", "..", "")]
+    fn resolves(#[case] base: &str, #[case] path: &str, #[case] expected: &str) {
+        assert_eq!(resolve(base, path), expected);
+    }
+
+    #[rstest]
+    #[case("https://example.com/a.md", false, None)]
+    #[case("mailto:a@b.c", false, None)]
+    #[case("#top", false, None)]
+    #[case("", false, None)]
+    #[case("a.md", false, Some("/docs/a.html"))]
+    #[case("a.MD?x=1#y", false, Some("/docs/a.html?x=1#y"))]
+    #[case("a.md", true, Some("/docs/a.md"))]
+    #[case("assets/a.png", true, Some("https://cdn.example.org/a.png"))]
+    #[case("static/a.png", true, Some("/a.png"))]
+    #[case("x", false, Some("/éé"))]
+    #[case("éé", false, Some("/docs/éé"))]
+    fn rewrites(#[case] url: &str, #[case] image: bool, #[case] expected: Option<&str>) {
+        let options = BaseUrlOptions {
+            base: "/docs/".into(),
+            mappings: vec![
+                ("assets/".into(), "https://cdn.example.org/".into()),
+                ("static/".into(), "/".into()),
+                ("x".into(), "/éé".into()),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(rewrite_url(url, image, &options).as_deref(), expected);
+    }
+
+    #[test]
+    fn records_urls() {
+        let md = &mut MarkdownIt::new();
+        crate::plugins::cmark::add(md);
+        add(md);
+
+        let ast = md.parse("[a](a.md) [b](https://b.com) [c](c.md#x)\n\n[c]: c.md");
+        let root = ast.cast::<Root>().unwrap();
+        let urls = &root.ext.get::<RewrittenUrls>().unwrap().urls;
+        assert_eq!(urls.iter().map(|u| u.url.as_str()).collect::<Vec<_>>(), ["a.html", "c.html#x"]);
+
+        assert!(md.parse("no links").cast::<Root>().unwrap().ext.get::<RewrittenUrls>().is_none());
+    }
+
+    #[test]
+    fn changes_base() {
+        let md = &mut MarkdownIt::new();
+        crate::plugins::cmark::add(md);
+        add(md);
+
+        set_base(md, "/a/");
+        assert_eq!(md.parse("![x](x.png)").render(&Default::default()).trim(), "<p><img src=\"/a/x.png\" alt=\"x\"></p>");
+        set_base(md, "/b/");
+        assert_eq!(md.parse("![x](x.png)").render(&Default::default()).trim(), "<p><img src=\"/b/x.png\" alt=\"x\"></p>");
+    }
+
+    #[test]
+    fn external_after_rewrite() {
+        use crate::parser::linkfmt::LinkPolicy;
+
+        let md = &mut MarkdownIt::with_link_policy(LinkPolicy {
+            internal_hosts: vec!["example.org".into()],
+            external_rel: Some("nofollow".into()),
+            ..Default::default()
+        });
+        crate::plugins::cmark::add(md);
+        add_with_options(md, BaseUrlOptions {
+            mappings: vec![("ext/".into(), "https://other.org/".into())],
+            ..Default::default()
+        });
+
+        assert_eq!(md.parse("[a](ext/a) [b](b)").render(&Default::default()).trim(),
+            "<p><a rel=\"nofollow\" href=\"https://other.org/a\">a</a> <a href=\"b\">b</a></p>");
+    }
+}
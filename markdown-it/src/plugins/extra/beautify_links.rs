@@ -21,6 +21,10 @@ impl LinkFormatter for LinkBeautifier {
     fn normalize_link_text(&self, url: &str) -> String {
         mdurl::format_url_for_humans(url, self.max_length)
     }
+
+    fn link_attrs(&self, url: &str) -> Vec<(&'static str, String)> {
+        self.parent.as_ref().link_attrs(url)
+    }
 }
 
 
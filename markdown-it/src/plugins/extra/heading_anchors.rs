@@ -10,7 +10,7 @@
 //! markdown_it::plugins::extra::heading_anchors::add(md, slugify_fn);
 //!
 //! assert_eq!(
-//!     md.parse("## An example heading").render(),
+//!     md.parse("## An example heading").render(&Default::default()),
 //!     "<h2 id=\"an-example-heading\">An example heading</h2>\n",
 //! );
 //! ```
@@ -62,6 +62,7 @@ impl CoreRule for AddHeadingAnchors {
 
         root.walk_mut(|node, _| {
             if node.is::<ATXHeading>() || node.is::<SetextHeader>() {
+                if node.attrs.iter().any(|(name, _)| *name == "id") { return; }
                 node.attrs.push(("id", slugify(&node.collect_text())));
             }
         });
@@ -4,10 +4,9 @@ use linkify::{LinkFinder, LinkKind};
 use once_cell::sync::Lazy;
 use regex::Regex;
 use std::cmp::Ordering;
-use std::collections::HashMap;
 
 use crate::parser::core::{CoreRule, Root};
-use crate::parser::extset::RootExt;
+use crate::parser::extset::{RenderOptionsExtSet, RootExt};
 use crate::parser::inline::builtin::InlineParserRule;
 use crate::parser::inline::{InlineRule, InlineState, TextSpecial};
 use crate::{MarkdownIt, Node, NodeValue, Renderer};
@@ -16,12 +15,18 @@ static SCHEME_RE: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"(?i)(?:^|[^a-z0-9.+-])([a-z][a-z0-9.+-]*)$").unwrap());
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Linkified {
     pub url: String,
 }
 
 impl NodeValue for Linkified {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &HashMap<String, String>) {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
         let mut attrs = node.attrs.clone();
         attrs.push(("href", self.url.clone()));
 
@@ -139,7 +144,9 @@ impl InlineRule for LinkifyScanner {
         });
         inner_node.srcmap = state.get_map(url_start, url_end);
 
+        let attrs = state.md.link_formatter.link_attrs(&full_url);
         let mut node = Node::new(Linkified { url: full_url });
+        node.attrs = attrs;
         node.children.push(inner_node);
 
         state.trailing_text_pop(proto_size);
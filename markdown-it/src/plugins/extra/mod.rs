@@ -12,12 +12,13 @@
 //! markdown_it::plugins::cmark::add(md);
 //! markdown_it::plugins::extra::add(md);
 //!
-//! let html = md.parse("hello ~~world~~").render();
+//! let html = md.parse("hello ~~world~~").render(&Default::default());
 //! assert_eq!(html.trim(), r#"<p>hello <s>world</s></p>"#);
 //!
-//! let html = md.parse(r#"Markdown done "The Right Way(TM)""#).render();
+//! let html = md.parse(r#"Markdown done "The Right Way(TM)""#).render(&Default::default());
 //! assert_eq!(html.trim(), r#"<p>Markdown done “The Right Way™”</p>"#);
 //! ```
+pub mod base_url;
 pub mod beautify_links;
 pub mod heading_anchors;
 #[cfg(feature = "linkify")]
@@ -1,16 +1,22 @@
 //! Strikethrough syntax (like `~~this~~`)
-use std::collections::HashMap;
 
 use crate::generics::inline::emph_pair;
+use crate::parser::extset::RenderOptionsExtSet;
 use crate::{MarkdownIt, Node, NodeValue, Renderer};
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Strikethrough {
     pub marker: char,
 }
 
 impl NodeValue for Strikethrough {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &HashMap<String, String>) {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
         fmt.open("s", &node.attrs);
         fmt.contents(&node.children, options);
         fmt.close("s");
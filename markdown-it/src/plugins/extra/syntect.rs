@@ -1,89 +1,498 @@
 //! Syntax highlighting for code blocks
-use std::collections::HashMap;
+//!
+//! Diagram fences like ```` ```mermaid ```` are handled separately
+//! by [diagrams](crate::plugins::jotdown::diagrams) plugin.
+//!
+//! Syntect's default syntaxes and themes are loaded once and shared between
+//! all parsers. Use [add_syntaxes_from_folder] and [add_themes_from_folder]
+//! to highlight languages syntect does not ship.
+//!
+//! By default colors are embedded as inline styles. Call [set_class_style]
+//! to emit classes instead, and [css] to generate a matching stylesheet
+//! (optionally with a dark variant).
+//!
+//! Highlighting is done while rendering, attach a [RenderCache] to the renderer
+//! (e.g. with [Node::render_cached]) to reuse output for unchanged code.
+//! Entries are keyed by code, language and theme names, so clear cache
+//! directory after editing files loaded with [add_syntaxes_from_folder]
+//! or [add_themes_from_folder].
+use std::path::Path;
+use std::sync::Arc;
 
+use derivative::Derivative;
+use once_cell::sync::Lazy;
+use syntect::easy::HighlightLines;
 use syntect::highlighting::ThemeSet;
-use syntect::html::highlighted_html_for_string;
-use syntect::parsing::SyntaxSet;
+use syntect::html::{
+    css_for_theme_with_class_style, highlighted_html_for_string, line_tokens_to_classed_spans,
+    start_highlighted_html_snippet, styled_line_to_highlighted_html, ClassStyle, ClassedHTMLGenerator,
+    IncludeBackground,
+};
+use syntect::parsing::{ParseState, ScopeStack, ScopeStackOp, SyntaxReference, SyntaxSet};
+use syntect::util::{as_24_bit_terminal_escaped, LinesWithEndings};
+use syntect::LoadingError;
 
-use crate::common::utils::escape_html;
+use crate::common::utils::{escape_html, unescape_all};
 use crate::parser::core::CoreRule;
-use crate::parser::extset::MarkdownItExt;
+use crate::parser::extset::{MarkdownItExt, RenderOptionsExtSet};
+use crate::parser::inline::builtin::InlineParserRule;
+use crate::parser::renderer::cache::{CacheKey, RenderCache};
 use crate::plugins::cmark::block::code::CodeBlock;
-use crate::plugins::cmark::block::fence::CodeFence;
+use crate::plugins::cmark::block::fence::{CodeFence, FenceLineOptions};
 use crate::{MarkdownIt, Node, NodeValue, Renderer};
 
-#[derive(Debug)]
+// default sets are expensive to load, so they are shared between all parsers
+static DEFAULT_SYNTAXES : Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
+static DEFAULT_THEMES   : Lazy<ThemeSet>  = Lazy::new(ThemeSet::load_defaults);
+static DEFAULT_SETTINGS : Lazy<Arc<SyntectSettings>> = Lazy::new(Default::default);
+
+#[derive(Derivative)]
+#[derivative(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+/// Code block highlighted with syntect when rendered.
 pub struct SyntectSnippet {
-    pub html: String,
+    pub content: String,
+    /// Name of syntect syntax, plain text if it's not found.
+    pub syntax: String,
+    pub options: FenceLineOptions,
+    // settings of the parser that created this node
+    #[derivative(Debug="ignore")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    settings: Arc<SyntectSettings>,
+}
+
+impl SyntectSnippet {
+    /// Highlighted html, `None` if syntect fails to highlight the code.
+    pub fn highlight(&self) -> Option<String> {
+        let ss = self.settings.syntaxes();
+        let syntax = ss.find_syntax_by_name(&self.syntax).unwrap_or_else(|| ss.find_syntax_plain_text());
+        self.settings.highlight(&self.content, syntax, &self.options)
+    }
+
+    fn cache_key(&self) -> CacheKey {
+        CacheKey::new(&[
+            "syntect",
+            self.settings.theme,
+            &format!("{:?}", self.settings.class_style),
+            &self.syntax,
+            &format!("{:?}", self.options),
+            &self.content,
+        ])
+    }
 }
 
 impl NodeValue for SyntectSnippet {
-    fn render(&self, _: &Node, fmt: &mut dyn Renderer, _options: &HashMap<String, String>) {
-        fmt.text_raw(&self.html);
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, _options: &RenderOptionsExtSet) {
+        let html = match RenderCache::from_renderer(fmt) {
+            Some(cache) => cache.get_or_insert_with(self.cache_key(), || self.highlight()),
+            None => self.highlight(),
+        };
+
+        let Some(html) = html else {
+            fmt.cr();
+            fmt.open("pre", &node.attrs);
+            fmt.open("code", &[]);
+            fmt.text(&self.content);
+            fmt.close("code");
+            fmt.close("pre");
+            fmt.cr();
+            return;
+        };
+
+        // `<pre style="...">` or `<pre class="...">`, attributes of the node
+        // (e.g. from attribute blocks) are added to this tag
+        let Some((tag, html)) = html.strip_prefix("<pre").and_then(|html| html.split_once('>')) else {
+            fmt.text_raw(&html);
+            return;
+        };
+
+        let mut attrs = node.attrs.clone();
+        match tag.trim().split_once('=') {
+            Some(("class", value)) => attrs.push(("class", unescape_all(value.trim_matches('"')).into_owned())),
+            Some(("style", value)) => attrs.push(("style", unescape_all(value.trim_matches('"')).into_owned())),
+            _ => {}
+        }
+
+        fmt.open("pre", &attrs);
+        fmt.text_raw(html);
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-struct SyntectSettings(&'static str);
-impl MarkdownItExt for SyntectSettings {}
+#[derive(Debug, Clone)]
+struct SyntectSettings {
+    theme: &'static str,
+    // `None` means syntect defaults, custom sets are only built
+    // when user adds their own syntaxes or themes
+    syntaxes: Option<Arc<SyntaxSet>>,
+    themes: Option<Arc<ThemeSet>>,
+    // `None` means inline styles
+    class_style: Option<ClassStyle>,
+}
+
+// settings are shared with nodes, so they can be highlighted when rendered,
+// changing them doesn't affect nodes that are already parsed
+#[derive(Debug, Default)]
+struct SharedSettings(Arc<SyntectSettings>);
+impl MarkdownItExt for SharedSettings {}
+
+fn settings(md: &MarkdownIt) -> &Arc<SyntectSettings> {
+    md.ext.get::<SharedSettings>().map_or(&DEFAULT_SETTINGS, |settings| &settings.0)
+}
+
+fn settings_mut(md: &mut MarkdownIt) -> &mut SyntectSettings {
+    Arc::make_mut(&mut md.ext.get_or_insert_default::<SharedSettings>().0)
+}
 
 impl Default for SyntectSettings {
     fn default() -> Self {
-        Self("InspiredGitHub")
+        Self {
+            theme: "InspiredGitHub",
+            syntaxes: None,
+            themes: None,
+            class_style: None,
+        }
+    }
+}
+
+impl SyntectSettings {
+    fn syntaxes(&self) -> &SyntaxSet {
+        self.syntaxes.as_deref().unwrap_or(&DEFAULT_SYNTAXES)
+    }
+
+    fn themes(&self) -> &ThemeSet {
+        self.themes.as_deref().unwrap_or(&DEFAULT_THEMES)
+    }
+
+    fn highlight(&self, content: &str, syntax: &SyntaxReference, options: &FenceLineOptions) -> Option<String> {
+        let ss = self.syntaxes();
+
+        if !options.is_empty() {
+            return self.highlight_lines(content, syntax, options);
+        }
+
+        let Some(style) = self.class_style else {
+            let theme = &self.themes().themes[self.theme];
+            return highlighted_html_for_string(content, ss, syntax, theme).ok();
+        };
+
+        let mut generator = ClassedHTMLGenerator::new_with_class_style(syntax, ss, style);
+        for line in LinesWithEndings::from(content) {
+            generator.parse_html_for_line_which_includes_newline(line).ok()?;
+        }
+        Some(format!("<pre class=\"{}code\">\n{}</pre>\n", class_prefix(style), generator.finalize()))
+    }
+
+    // same as above, but every line is wrapped in a separate element,
+    // so spans that cross line boundaries are closed and reopened
+    fn highlight_lines(&self, content: &str, syntax: &SyntaxReference, options: &FenceLineOptions) -> Option<String> {
+        let ss = self.syntaxes();
+        let mut result;
+        let mut lines = Vec::new();
+
+        if let Some(style) = self.class_style {
+            result = format!("<pre class=\"{}code\">\n", class_prefix(style));
+            let mut state = ParseState::new(syntax);
+            let mut stack = ScopeStack::new();
+
+            for line in LinesWithEndings::from(content) {
+                let ops = state.parse_line(line, ss).ok()?;
+                let reopen = stack.as_slice().iter().map(|scope| (0, ScopeStackOp::Push(*scope))).collect::<Vec<_>>();
+                let (mut html, _) = line_tokens_to_classed_spans("", &reopen, style, &mut ScopeStack::new()).ok()?;
+                html.push_str(&line_tokens_to_classed_spans(line, &ops, style, &mut stack).ok()?.0);
+                html.push_str(&"</span>".repeat(stack.len()));
+                lines.push(html);
+            }
+        } else {
+            let theme = &self.themes().themes[self.theme];
+            let mut highlighter = HighlightLines::new(syntax, theme);
+            let (start, bg) = start_highlighted_html_snippet(theme);
+            result = start;
+
+            for line in LinesWithEndings::from(content) {
+                let regions = highlighter.highlight_line(line, ss).ok()?;
+                lines.push(styled_line_to_highlighted_html(&regions, IncludeBackground::IfDifferent(bg)).ok()?);
+            }
+        }
+
+        for (idx, html) in lines.iter().enumerate() {
+            let number = idx + 1;
+            result.push_str("<span");
+            for (name, value) in options.line_attrs(number) {
+                result.push_str(&format!(" {}=\"{}\"", name, escape_html(&value)));
+            }
+            result.push('>');
+            if options.line_numbers {
+                result.push_str(&format!("<span class=\"line-number\">{}</span>", number));
+            }
+            result.push_str(html);
+            result.push_str("</span>");
+        }
+
+        result.push_str("</pre>\n");
+        Some(result)
+    }
+}
+
+fn class_prefix(style: ClassStyle) -> &'static str {
+    match style {
+        ClassStyle::SpacedPrefixed { prefix } => prefix,
+        _ => "",
     }
 }
 
 pub fn add(md: &mut MarkdownIt) {
-    md.add_rule::<SyntectRule>();
+    md.add_rule::<SyntectRule>()
+        .after::<InlineParserRule>();
 }
 
 pub fn set_theme(md: &mut MarkdownIt, theme: &'static str) {
-    md.ext.insert(SyntectSettings(theme));
+    settings_mut(md).theme = theme;
+}
+
+/// Emit `class=` attributes instead of inline styles, so colors are defined
+/// in a stylesheet (see [css]). Theme set by [set_theme] is not used in this mode.
+pub fn set_class_style(md: &mut MarkdownIt, style: ClassStyle) {
+    settings_mut(md).class_style = Some(style);
+}
+
+/// Generate stylesheet for class-based output (see [set_class_style]).
+///
+/// Colors of `dark_theme`, if any, are applied when user prefers dark color scheme.
+/// Returns `None` if any of the themes is not known to this parser.
+pub fn css(md: &MarkdownIt, theme: &str, dark_theme: Option<&str>) -> Option<String> {
+    let settings = settings(md);
+    let style = settings.class_style.unwrap_or(ClassStyle::Spaced);
+    let themes = &settings.themes().themes;
+
+    let mut result = css_for_theme_with_class_style(themes.get(theme)?, style).ok()?;
+
+    if let Some(dark_theme) = dark_theme {
+        let dark = css_for_theme_with_class_style(themes.get(dark_theme)?, style).ok()?;
+        result.push_str("\n@media (prefers-color-scheme: dark) {\n");
+        result.push_str(&dark);
+        result.push_str("}\n");
+    }
+
+    Some(result)
+}
+
+/// Highlight `code` for terminal output with 24-bit color escapes, using
+/// default syntaxes and `theme`. Colors are reset at the end of each line.
+///
+/// Returns `None` if language or theme is not known.
+pub fn highlight_terminal(code: &str, language: &str, theme: &str) -> Option<String> {
+    let syntax = DEFAULT_SYNTAXES.find_syntax_by_token(language)?;
+    let mut highlighter = HighlightLines::new(syntax, DEFAULT_THEMES.themes.get(theme)?);
+
+    let mut lines = Vec::new();
+    for line in LinesWithEndings::from(code) {
+        let regions = highlighter.highlight_line(line, &DEFAULT_SYNTAXES).ok()?;
+        let escaped = as_24_bit_terminal_escaped(&regions, false);
+        lines.push(format!("{}\x1b[0m", escaped.trim_end_matches('\n')));
+    }
+    Some(lines.join("\n"))
+}
+
+/// Load all `.sublime-syntax` files from `folder` (recursively), in addition
+/// to syntaxes already known to this parser.
+pub fn add_syntaxes_from_folder(md: &mut MarkdownIt, folder: impl AsRef<Path>) -> Result<(), LoadingError> {
+    let settings = settings_mut(md);
+    let mut builder = settings.syntaxes().clone().into_builder();
+    builder.add_from_folder(folder, true)?;
+    settings.syntaxes = Some(Arc::new(builder.build()));
+    Ok(())
+}
+
+/// Load all `.tmTheme` files from `folder` (recursively), in addition
+/// to themes already known to this parser. Theme name is its file name
+/// without extension, use it in [set_theme].
+pub fn add_themes_from_folder(md: &mut MarkdownIt, folder: impl AsRef<Path>) -> Result<(), LoadingError> {
+    let settings = settings_mut(md);
+    let mut themes = ThemeSet::new();
+    themes.themes.clone_from(&settings.themes().themes);
+    themes.add_from_folder(folder)?;
+    settings.themes = Some(Arc::new(themes));
+    Ok(())
 }
 
 pub struct SyntectRule;
 impl CoreRule for SyntectRule {
     fn run(root: &mut Node, md: &MarkdownIt) {
-        let ss = SyntaxSet::load_defaults_newlines();
-        let ts = ThemeSet::load_defaults();
-        let theme = &ts.themes[md
-            .ext
-            .get::<SyntectSettings>()
-            .copied()
-            .unwrap_or_default()
-            .0];
+        let settings = settings(md);
+        let ss = settings.syntaxes();
 
         root.walk_mut(|node, _| {
             let mut content = None;
             let mut language = None;
+            let mut options = FenceLineOptions::default();
 
             if let Some(data) = node.cast::<CodeBlock>() {
                 content = Some(&data.content);
             } else if let Some(data) = node.cast::<CodeFence>() {
-                language = Some(data.info.clone());
+                let info = unescape_all(&data.info);
+                language = info.split_whitespace().next().map(str::to_owned);
+                options = FenceLineOptions::parse(&info);
                 content = Some(&data.content);
             }
 
             if let Some(content) = content {
                 let mut syntax = None;
                 if let Some(language) = language {
-                    if language == "mermaid" {
-                        let html = format!("<pre class=\"mermaid\">{}</pre>", 
-                            escape_html(content));
-                        (*node).replace(SyntectSnippet { html });
-                        return;
-                    }
-
                     syntax = ss.find_syntax_by_token(&language);
                 }
                 let syntax = syntax.unwrap_or_else(|| ss.find_syntax_plain_text());
 
-                let html = highlighted_html_for_string(content, &ss, syntax, theme);
-
-                if let Ok(html) = html {
-                    node.replace(SyntectSnippet { html });
-                }
+                node.replace(SyntectSnippet {
+                    content: content.clone(),
+                    syntax: syntax.name.clone(),
+                    options,
+                    settings: settings.clone(),
+                });
             }
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::plugins::cmark;
+
+    use super::*;
+
+    const SYNTAX: &str = r#"%YAML 1.2
+---
+name: Jot
+file_extensions: [jot]
+scope: source.jot
+contexts:
+  main:
+    - match: '\bjot\b'
+      scope: keyword.jot
+"#;
+
+    #[test]
+    fn custom_syntax() {
+        let dir = std::env::temp_dir().join(format!("markdown-it-syntect-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("Jot.sublime-syntax"), SYNTAX).unwrap();
+
+        let md = &mut MarkdownIt::new();
+        cmark::add(md);
+        add(md);
+        let plain = md.parse("```jot\njot\n```").render(&Default::default());
+
+        add_syntaxes_from_folder(md, &dir).unwrap();
+        let highlighted = md.parse("```jot\njot\n```").render(&Default::default());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_ne!(plain, highlighted);
+        // default syntaxes are still there
+        assert!(settings(md).syntaxes().find_syntax_by_token("rust").is_some());
+    }
+
+    #[test]
+    fn class_style() {
+        let md = &mut MarkdownIt::new();
+        cmark::add(md);
+        add(md);
+        set_class_style(md, ClassStyle::SpacedPrefixed { prefix: "hl-" });
+
+        let html = md.parse("```rust\nfn\n```").render(&Default::default());
+        assert_eq!(html, "<pre class=\"hl-code\">\n<span class=\"hl-source hl-rust\">\
+            <span class=\"hl-storage hl-type hl-function hl-rust\">fn</span>\n</span></pre>\n");
+    }
+
+    #[test]
+    fn css_dark_variant() {
+        let md = &mut MarkdownIt::new();
+        add(md);
+
+        let css = css(md, "InspiredGitHub", Some("base16-ocean.dark")).unwrap();
+        assert!(css.starts_with("/*\n * theme \"GitHub\""));
+        let (light, dark) = css.split_once("@media (prefers-color-scheme: dark) {").unwrap();
+        assert!(light.contains(".code {"));
+        assert!(dark.contains(".code {"));
+        assert!(dark.trim_end().ends_with('}'));
+
+        assert!(super::css(md, "no such theme", None).is_none());
+    }
+
+    #[test]
+    fn line_options() {
+        let md = &mut MarkdownIt::new();
+        cmark::add(md);
+        add(md);
+
+        let html = md.parse("```rust {1} linenos\nlet a;\n```").render(&Default::default());
+        assert_eq!(html, "<pre style=\"background-color:#ffffff;\">\n\
+            <span class=\"line highlighted\" data-line=\"1\"><span class=\"line-number\">1</span>\
+            <span style=\"font-weight:bold;color:#a71d5d;\">let</span><span style=\"color:#323232;\"> a;\n</span>\
+            </span></pre>\n");
+    }
+
+    #[test]
+    fn line_options_with_classes() {
+        let md = &mut MarkdownIt::new();
+        cmark::add(md);
+        add(md);
+        set_class_style(md, ClassStyle::Spaced);
+
+        // comment spans two lines, so it must be closed and reopened
+        let html = md.parse("```c {2}\n/* a\nb */\n```").render(&Default::default());
+        assert_eq!(html, "<pre class=\"code\">\n\
+            <span class=\"line\" data-line=\"1\"><span class=\"source c\"><span class=\"comment block c\">\
+            <span class=\"punctuation definition comment c\">/*</span> a\n</span></span></span>\
+            <span class=\"line highlighted\" data-line=\"2\"><span class=\"source c\"><span class=\"comment block c\">\
+            b <span class=\"punctuation definition comment c\">*/</span></span>\n</span></span>\
+            </pre>\n");
+    }
+
+    #[test]
+    fn node_attrs() {
+        let md = &mut MarkdownIt::new();
+        cmark::add(md);
+        crate::plugins::pandoc::attrs::add(md);
+        add(md);
+
+        let html = md.parse("```rust {#main .big}\nfn\n```").render(&Default::default());
+        assert!(html.starts_with("<pre id=\"main\" class=\"big\" style=\"background-color:#ffffff;\">\n"));
+
+        set_class_style(md, ClassStyle::SpacedPrefixed { prefix: "hl-" });
+        let html = md.parse("```rust {#main .big}\nfn\n```").render(&Default::default());
+        assert!(html.starts_with("<pre id=\"main\" class=\"big hl-code\">\n<span class=\"hl-source hl-rust\">"));
+    }
+
+    #[test]
+    fn missing_folder() {
+        let md = &mut MarkdownIt::new();
+        assert!(add_themes_from_folder(md, "/nonexistent/markdown-it/themes").is_err());
+    }
+
+    #[test]
+    fn cached() {
+        let md = &mut MarkdownIt::new();
+        cmark::add(md);
+        add(md);
+        let plain = md.parse("```rust\nfn main() {}\n```").render(&Default::default());
+
+        let cache = RenderCache::new();
+        let src = "```rust\nfn main() {}\n```\n\n```rust {1}\nfn main() {}\n```";
+        let first = md.parse(src).render_cached(&Default::default(), &cache);
+        assert_eq!(md.parse(src).render_cached(&Default::default(), &cache), first);
+        assert_eq!(md.parse(src).render(&Default::default()), first);
+        assert!(first.starts_with(&plain));
+        assert_eq!(cache.len(), 2);
+
+        // settings changed after parsing don't affect parsed nodes
+        let ast = md.parse(src);
+        set_class_style(md, ClassStyle::Spaced);
+        assert_eq!(ast.render_cached(&Default::default(), &cache), first);
+        assert_ne!(md.parse(src).render_cached(&Default::default(), &cache), first);
+        assert_eq!(cache.len(), 4);
+    }
+}
@@ -1,23 +1,28 @@
 //! GFM tables
 //!
 //! <https://github.github.com/gfm/#tables-extension->
-use std::collections::HashMap;
 
 use crate::common::sourcemap::SourcePos;
 use crate::parser::block::{BlockRule, BlockState};
-use crate::parser::extset::RenderExt;
+use crate::parser::extset::{RenderExt, RenderOptionsExtSet};
 use crate::parser::inline::InlineRoot;
 use crate::plugins::cmark::block::heading::HeadingScanner;
 use crate::plugins::cmark::block::list::ListScanner;
 use crate::{MarkdownIt, Node, NodeValue, Renderer};
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Table {
     pub alignments: Vec<ColumnAlignment>,
 }
 
 impl NodeValue for Table {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &HashMap<String, String>) {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
         let old_context = fmt.ext().remove::<TableRenderContext>();
         fmt.ext().insert(TableRenderContext {
             head: false,
@@ -47,10 +52,11 @@ pub struct TableRenderContext {
 impl RenderExt for TableRenderContext {}
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub struct TableHead;
 
 impl NodeValue for TableHead {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &HashMap<String, String>) {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
         let ctx = fmt.ext().get_or_insert_default::<TableRenderContext>();
         ctx.head = true;
 
@@ -68,10 +74,11 @@ impl NodeValue for TableHead {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub struct TableBody;
 
 impl NodeValue for TableBody {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &HashMap<String, String>) {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
         fmt.cr();
         fmt.open("tbody", &node.attrs);
         fmt.cr();
@@ -83,10 +90,11 @@ impl NodeValue for TableBody {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub struct TableRow;
 
 impl NodeValue for TableRow {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &HashMap<String, String>) {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
         let ctx = fmt.ext().get_or_insert_default::<TableRenderContext>();
         ctx.index = 0;
 
@@ -101,10 +109,11 @@ impl NodeValue for TableRow {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub struct TableCell;
 
 impl NodeValue for TableCell {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &HashMap<String, String>) {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
         let ctx = fmt.ext().get_or_insert_default::<TableRenderContext>();
         let tag = if ctx.head { "th" } else { "td" };
 
@@ -143,6 +152,8 @@ struct RowContent {
 }
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum ColumnAlignment {
     None,
     Left,
@@ -426,8 +437,6 @@ impl BlockRule for TableScanner {
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
-
     use super::TableScanner;
 
     #[test]
@@ -501,13 +510,13 @@ mod tests {
     fn require_pipe_or_colon_in_align_row() {
         let md = &mut crate::MarkdownIt::new();
         crate::plugins::extra::tables::add(md);
-        let html = md.parse("foo\n---\nbar").render(&HashMap::new());
+        let html = md.parse("foo\n---\nbar").render(&Default::default());
         assert_eq!(html.trim(), "foo\n---\nbar");
-        let html = md.parse("|foo\n---\nbar").render(&HashMap::new());
+        let html = md.parse("|foo\n---\nbar").render(&Default::default());
         assert_eq!(html.trim(), "|foo\n---\nbar");
-        let html = md.parse("foo\n|---\nbar").render(&HashMap::new());
+        let html = md.parse("foo\n|---\nbar").render(&Default::default());
         assert!(html.trim().starts_with("<table"));
-        let html = md.parse("foo\n:---\nbar").render(&HashMap::new());
+        let html = md.parse("foo\n:---\nbar").render(&Default::default());
         assert!(html.trim().starts_with("<table"));
     }
 }
@@ -10,7 +10,7 @@
 //! markdown_it::plugins::extra::add(md);
 //! markdown_it::plugins::extra::typographer::add(md);
 //!
-//! let html = md.parse("Hello world!.... This is the Right Way(TM) to markdown!!!!!").render();
+//! let html = md.parse("Hello world!.... This is the Right Way(TM) to markdown!!!!!").render(&Default::default());
 //! assert_eq!(html.trim(), r#"<p>Hello world!.. This is the Right Way™ to markdown!!!</p>"#);
 //! ```
 //! In summary, these are the replacements that will be made when using this:
@@ -11,10 +11,10 @@
 //! ```rust
 //! let md = &mut markdown_it::MarkdownIt::new();
 //! markdown_it::plugins::cmark::add(md);
-//! markdown_it_autolink::add(md);
+//! markdown_it::plugins::gfm::autolinks::add(md);
 //!
 //! assert_eq!(
-//!     md.parse("www.example.com").render(),
+//!     md.parse("www.example.com").render(&Default::default()),
 //!     "<p><a href=\"http://www.example.com\">www.example.com</a></p>\n",
 //! );
 //! ```
@@ -9,7 +9,7 @@
 //! Add id attribute (slug) to headings.
 //!
 //! ```rust
-//! use markdown_it_heading_anchors::{
+//! use markdown_it::plugins::gfm::heading_anchors::{
 //!     add_with_options, HeadingAnchorOptions, AnchorPosition
 //! };
 //!
@@ -21,7 +21,7 @@
 //! add_with_options(md, options);
 //!
 //! assert_eq!(
-//!     md.parse("# heading\n\n# heading").render(),
+//!     md.parse("# heading\n\n# heading").render(&Default::default()),
 //!     "<h1>\
 //!     <a aria-hidden=\"true\" class=\"anchor\" id=\"heading\" href=\"#heading\">¶</a>\
 //!     heading</h1>\n\
@@ -31,8 +31,7 @@
 //! );
 //! ```
 
-use std::collections::HashMap;
-
+use crate::parser::extset::RenderOptionsExtSet;
 use crate::{
     parser::{core::CoreRule, extset::MarkdownItExt, inline::builtin::InlineParserRule},
     plugins::{
@@ -102,17 +101,23 @@ impl Default for HeadingAnchorOptions {
 impl MarkdownItExt for HeadingAnchorOptions {}
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// AST node for a heading anchor
 pub struct HeadingAnchor {
     pub href: String,
     pub id: Option<String>,
 }
 impl NodeValue for HeadingAnchor {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
     fn render(
         &self,
         node: &Node,
         fmt: &mut dyn crate::Renderer,
-        options: &HashMap<String, String>,
+        options: &RenderOptionsExtSet,
     ) {
         let mut attrs = node.attrs.clone();
         if let Some(id) = &self.id {
@@ -145,14 +150,16 @@ impl CoreRule for AddHeadingAnchors {
             }
             if node.is::<ATXHeading>() || node.is::<SetextHeader>() {
                 // TODO strip image (alt) text
-                let id = slugger.slug(&node.collect_text());
-                if options.id_on_heading {
+                // id may already be set explicitly, e.g. by attributes plugin
+                let custom_id = node.attrs.iter().find(|(name, _)| *name == "id").map(|(_, id)| id.clone());
+                let id = custom_id.clone().unwrap_or_else(|| slugger.slug(&node.collect_text()));
+                if options.id_on_heading && custom_id.is_none() {
                     node.attrs.push(("id", id.clone()));
                 }
                 let anchor = HeadingAnchor {
                     href: id.clone(),
                     id: {
-                        if options.id_on_heading {
+                        if options.id_on_heading || custom_id.is_some() {
                             None
                         } else {
                             Some(id)
@@ -6,6 +6,7 @@
 //
 // https://www.apache.org/licenses/LICENSE-2.0
 
+use once_cell::sync::Lazy;
 use regex::Regex;
 
 use crate::{
@@ -14,13 +15,18 @@ use crate::{
     MarkdownIt, Node,
 };
 
+static TAG_FILTER_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"<(?i)(iframe|noembed|noframes|plaintext|script|title|textarea|xmp)"#).unwrap()
+});
+
 /// Implement the Disallowed Raw HTML (tagfilter) rule
+///
+/// This only follows GFM spec, use [sanitize](crate::plugins::html::sanitize)
+/// plugin for untrusted input.
 struct TagFilter;
 impl CoreRule for TagFilter {
     fn run(root: &mut Node, _md: &MarkdownIt) {
-        let regex =
-            Regex::new(r#"<(?i)(iframe|noembed|noframes|plaintext|script|title|textarea|xmp)"#)
-                .unwrap();
+        let regex = &*TAG_FILTER_RE;
         root.walk_mut(|node, _| {
             if let Some(value) = node.cast_mut::<HtmlBlock>() {
                 value.content = regex.replace_all(&value.content, "&lt;$1").to_string();
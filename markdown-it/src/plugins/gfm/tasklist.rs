@@ -11,7 +11,7 @@
 //! ```rust
 //! let parser = &mut markdown_it::MarkdownIt::new();
 //! markdown_it::plugins::cmark::add(parser);
-//! markdown_it_tasklist::add(parser);
+//! markdown_it::plugins::gfm::tasklist::add(parser);
 //! let root = parser.parse("- [x] task");
 //! let mut names = vec![];
 //! root.walk(|node,_| { names.push(node.name()); });
@@ -19,13 +19,12 @@
 //! "markdown_it::parser::core::root::Root",
 //! "markdown_it::plugins::cmark::block::list::BulletList",
 //! "markdown_it::plugins::cmark::block::list::ListItem",
-//! "markdown_it_tasklist::TodoCheckbox",
+//! "markdown_it::plugins::gfm::tasklist::TodoCheckbox",
 //! "markdown_it::parser::inline::builtin::skip_text::Text",
 //! ]);
 //! ```
 
-use std::collections::HashMap;
-
+use crate::parser::extset::RenderOptionsExtSet;
 use crate::{
     parser::{
         core::CoreRule,
@@ -53,13 +52,19 @@ pub fn add_disabled(md: &mut MarkdownIt) {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TodoCheckbox {
     pub checked: bool,
     pub disabled: bool,
 }
 
 impl NodeValue for TodoCheckbox {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, _options: &HashMap<String, String>) {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, _options: &RenderOptionsExtSet) {
         let mut attrs = node.attrs.clone();
         attrs.push(("class", "task-list-item-checkbox".into()));
         attrs.push(("type", "checkbox".into()));
@@ -1,7 +1,6 @@
 //! HTML block syntax from CommonMark
 //!
 //! <https://spec.commonmark.org/0.30/#html-blocks>
-use std::collections::HashMap;
 
 use once_cell::sync::Lazy;
 use regex::Regex;
@@ -9,15 +8,22 @@ use regex::Regex;
 use super::utils::blocks::*;
 use super::utils::regexps::*;
 use crate::parser::block::{BlockRule, BlockState};
+use crate::parser::extset::RenderOptionsExtSet;
 use crate::{MarkdownIt, Node, NodeValue, Renderer};
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HtmlBlock {
     pub content: String,
 }
 
 impl NodeValue for HtmlBlock {
-    fn render(&self, _: &Node, fmt: &mut dyn Renderer, _options: &HashMap<String, String>) {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, _: &Node, fmt: &mut dyn Renderer, _options: &RenderOptionsExtSet) {
         fmt.cr();
         fmt.text_raw(&self.content);
         fmt.cr();
@@ -1,19 +1,25 @@
 //! HTML inline syntax from CommonMark
 //!
 //! <https://spec.commonmark.org/0.30/#raw-html>
-use std::collections::HashMap;
 
 use super::utils::regexps::*;
+use crate::parser::extset::RenderOptionsExtSet;
 use crate::parser::inline::{InlineRule, InlineState};
 use crate::{MarkdownIt, Node, NodeValue, Renderer};
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HtmlInline {
     pub content: String,
 }
 
 impl NodeValue for HtmlInline {
-    fn render(&self, _: &Node, fmt: &mut dyn Renderer, _options: &HashMap<String, String>) {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, _: &Node, fmt: &mut dyn Renderer, _options: &RenderOptionsExtSet) {
         fmt.text_raw(&self.content);
     }
 }
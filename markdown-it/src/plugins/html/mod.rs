@@ -6,19 +6,20 @@
 //! You can enable it if you're:
 //!  - looking for strict CommonMark compatibility
 //!  - only have trusted input (i.e. writing markdown yourself)
-//!  - or took some care to sanitize html yourself
+//!  - or took some care to sanitize html yourself (see [sanitize] plugin)
 //!
 //! ```rust
 //! let md = &mut markdown_it::MarkdownIt::new();
 //! markdown_it::plugins::cmark::add(md);
 //! markdown_it::plugins::html::add(md);
 //!
-//! let html = md.parse("hello<br>world").render();
+//! let html = md.parse("hello<br>world").render(&Default::default());
 //! assert_eq!(html.trim(), r#"<p>hello<br>world</p>"#);
 //! ```
 
 pub mod html_block;
 pub mod html_inline;
+pub mod sanitize;
 mod utils;
 
 use crate::MarkdownIt;
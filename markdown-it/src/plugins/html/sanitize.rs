@@ -0,0 +1,424 @@
+//! Allowlist-based sanitizer for raw html.
+//!
+//! Content of [HtmlBlock] and [HtmlInline] nodes is split into tags,
+//! and only tags and attributes listed in [SanitizeOptions] are kept.
+//! Other tags are removed (with their content for `script`, `style`, etc.),
+//! comments are removed, stray `<` is escaped. Attributes that contain urls
+//! are checked with parser's [LinkFormatter](crate::parser::linkfmt::LinkFormatter),
+//! same as markdown links.
+//!
+//! Kept tags are balanced across the whole document: closing tags without
+//! an open tag are dropped, and tags left open are closed at the end.
+//!
+//! ```rust
+//! let md = &mut markdown_it::MarkdownIt::new();
+//! markdown_it::plugins::cmark::add(md);
+//! markdown_it::plugins::html::add(md);
+//! markdown_it::plugins::html::sanitize::add(md);
+//!
+//! let html = md.parse("<b onclick=\"alert(1)\">hi</b><script>alert(1)</script>").render(&Default::default());
+//! assert_eq!(html.trim(), "<p><b>hi</b></p>");
+//! ```
+use crate::common::utils::{escape_html, unescape_entities};
+use crate::parser::core::CoreRule;
+use crate::parser::extset::MarkdownItExt;
+use crate::parser::inline::builtin::InlineParserRule;
+use crate::{MarkdownIt, Node};
+
+use super::html_block::HtmlBlock;
+use super::html_inline::HtmlInline;
+
+#[derive(Debug, Clone)]
+/// Options for the sanitizer plugin, names are case-insensitive.
+pub struct SanitizeOptions {
+    /// Tags that are kept, everything else is removed (content stays).
+    pub tags: Vec<String>,
+    /// Attributes allowed on any of the kept tags.
+    pub attributes: Vec<String>,
+    /// Attributes allowed on specific tags, `(tag, attribute)`.
+    pub tag_attributes: Vec<(String, String)>,
+    /// Attributes that contain urls, removed if url isn't valid.
+    pub url_attributes: Vec<String>,
+    /// Tags that are removed together with their content, unless they are allowed.
+    pub remove_content: Vec<String>,
+}
+
+impl Default for SanitizeOptions {
+    fn default() -> Self {
+        let strings = |list: &[&str]| list.iter().map(|s| s.to_string()).collect();
+        Self {
+            tags: strings(&[
+                "a", "abbr", "b", "bdi", "bdo", "blockquote", "br", "caption", "cite", "code",
+                "col", "colgroup", "dd", "del", "details", "dfn", "div", "dl", "dt", "em",
+                "figcaption", "figure", "h1", "h2", "h3", "h4", "h5", "h6", "hr", "i", "img",
+                "ins", "kbd", "li", "mark", "ol", "p", "pre", "q", "rp", "rt", "ruby", "s",
+                "samp", "small", "span", "strong", "sub", "summary", "sup", "table", "tbody",
+                "td", "tfoot", "th", "thead", "time", "tr", "u", "ul", "var", "wbr",
+            ]),
+            attributes: strings(&["title", "lang", "dir"]),
+            tag_attributes: [
+                ("a", "href"), ("img", "src"), ("img", "alt"), ("img", "width"), ("img", "height"),
+                ("td", "colspan"), ("td", "rowspan"), ("td", "align"), ("th", "colspan"),
+                ("th", "rowspan"), ("th", "align"), ("col", "span"), ("colgroup", "span"),
+                ("ol", "start"), ("ol", "type"), ("li", "value"), ("time", "datetime"),
+                ("details", "open"), ("blockquote", "cite"), ("q", "cite"), ("del", "cite"),
+                ("ins", "cite"),
+            ].iter().map(|(tag, attr)| (tag.to_string(), attr.to_string())).collect(),
+            url_attributes: strings(&["href", "src", "cite"]),
+            remove_content: strings(&["script", "style", "template", "textarea", "title", "iframe", "noscript"]),
+        }
+    }
+}
+
+impl MarkdownItExt for SanitizeOptions {}
+
+impl SanitizeOptions {
+    fn has_tag(&self, tag: &str) -> bool {
+        contains(&self.tags, tag)
+    }
+
+    fn has_attribute(&self, tag: &str, attr: &str) -> bool {
+        contains(&self.attributes, attr) || self.tag_attributes.iter().any(|(t, a)| {
+            t.eq_ignore_ascii_case(tag) && a.eq_ignore_ascii_case(attr)
+        })
+    }
+}
+
+fn contains(list: &[String], name: &str) -> bool {
+    list.iter().any(|item| item.eq_ignore_ascii_case(name))
+}
+
+/// Add the sanitizer with default allowlist.
+pub fn add(md: &mut MarkdownIt) {
+    add_with_options(md, SanitizeOptions::default());
+}
+
+/// Add the sanitizer with custom allowlist.
+pub fn add_with_options(md: &mut MarkdownIt, options: SanitizeOptions) {
+    md.ext.insert(options);
+    md.add_rule::<SanitizeRule>()
+        .after::<InlineParserRule>();
+}
+
+#[doc(hidden)]
+pub struct SanitizeRule;
+impl CoreRule for SanitizeRule {
+    fn run(root: &mut Node, md: &MarkdownIt) {
+        let Some(options) = md.ext.get::<SanitizeOptions>() else { return; };
+
+        let mut open = Vec::new();
+        sanitize_children(root, options, md, &mut open);
+
+        // don't let tags opened in the document apply to the rest of the page
+        if !open.is_empty() {
+            root.children.push(Node::new(HtmlBlock { content: close_tags(open) + "\n" }));
+        }
+    }
+}
+
+// fragments are sanitized in document order, `open` is a stack
+// of kept tags that are not closed yet
+fn sanitize_children(node: &mut Node, options: &SanitizeOptions, md: &MarkdownIt, open: &mut Vec<String>) {
+    // `<script>` and `</script>` can be in different nodes, so
+    // everything between them is removed from the parent
+    let mut removing = None;
+    node.children.retain_mut(|child| {
+        if let Some(value) = child.cast_mut::<HtmlBlock>() {
+            value.content = sanitize_fragment(&value.content, options, md, &mut removing, open);
+        } else if let Some(value) = child.cast_mut::<HtmlInline>() {
+            value.content = sanitize_fragment(&value.content, options, md, &mut removing, open);
+        } else if removing.is_some() {
+            return false;
+        } else {
+            sanitize_children(child, options, md, open);
+        }
+        true
+    });
+}
+
+/// Remove everything not allowed by `options` from an html fragment.
+pub fn sanitize(html: &str, options: &SanitizeOptions, md: &MarkdownIt) -> String {
+    let mut open = Vec::new();
+    let result = sanitize_fragment(html, options, md, &mut None, &mut open);
+    result + &close_tags(open)
+}
+
+fn close_tags(tags: Vec<String>) -> String {
+    tags.iter().rev().map(|tag| format!("</{tag}>")).collect()
+}
+
+// `removing` is a tag whose content is being removed, it is carried over
+// between consecutive fragments
+fn sanitize_fragment(
+    html: &str,
+    options: &SanitizeOptions,
+    md: &MarkdownIt,
+    removing: &mut Option<String>,
+    open: &mut Vec<String>,
+) -> String {
+    let mut result = String::with_capacity(html.len());
+
+    for token in tokenize(html) {
+        if let Some(tag) = removing {
+            if matches!(&token, Token::Close { name } if name == tag) {
+                *removing = None;
+            }
+            continue;
+        }
+
+        match token {
+            Token::Text(text) => result.push_str(text),
+            Token::LessThan => result.push_str("&lt;"),
+            Token::Other => {}
+            Token::Open { name, attrs, self_closing } => {
+                if !options.has_tag(&name) {
+                    if !self_closing && contains(&options.remove_content, &name) {
+                        *removing = Some(name);
+                    }
+                    continue;
+                }
+
+                result.push('<');
+                result.push_str(&name);
+                for (attr, value) in attrs {
+                    if !options.has_attribute(&name, attr) { continue; }
+                    let attr = attr.to_ascii_lowercase();
+
+                    let Some(value) = value else {
+                        result.push(' ');
+                        result.push_str(&attr);
+                        continue;
+                    };
+
+                    let mut value = unescape_entities(value).into_owned();
+                    if contains(&options.url_attributes, &attr) {
+                        value = md.link_formatter.normalize_link(value.trim());
+                        if md.link_formatter.validate_link(&value).is_none() { continue; }
+                    }
+
+                    result.push(' ');
+                    result.push_str(&attr);
+                    result.push_str("=\"");
+                    result.push_str(&escape_html(&value));
+                    result.push('"');
+                }
+                result.push_str(if self_closing { " />" } else { ">" });
+
+                if !self_closing && !VOID_TAGS.contains(&name.as_str()) {
+                    open.push(name);
+                }
+            }
+            Token::Close { name } => {
+                // tags opened after this one are closed together with it,
+                // closing tags that don't match any open tag are dropped
+                if let Some(idx) = open.iter().rposition(|tag| *tag == name) {
+                    result.push_str(&close_tags(open.split_off(idx)));
+                }
+            }
+        }
+    }
+
+    result
+}
+
+// elements that never have content or closing tags
+const VOID_TAGS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source", "track", "wbr",
+];
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token<'a> {
+    Text(&'a str),
+    // `<` that doesn't start a tag
+    LessThan,
+    // comments, doctype, processing instructions, cdata
+    Other,
+    Open { name: String, attrs: Vec<(&'a str, Option<&'a str>)>, self_closing: bool },
+    Close { name: String },
+}
+
+// Lenient html tokenizer, only needs to recognize tags the same way
+// as browsers do, so nothing that looks like a tag passes through as text.
+fn tokenize(html: &str) -> Vec<Token<'_>> {
+    let bytes = html.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let Some(offset) = html[pos..].find('<') else {
+            tokens.push(Token::Text(&html[pos..]));
+            break;
+        };
+
+        if offset > 0 {
+            tokens.push(Token::Text(&html[pos..pos + offset]));
+            pos += offset;
+        }
+
+        let rest = &html[pos..];
+        let skip_to = |end: &str| rest.find(end).map(|idx| pos + idx + end.len()).unwrap_or(bytes.len());
+
+        if rest.starts_with("<!--") {
+            pos = skip_to("-->");
+            tokens.push(Token::Other);
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            pos = skip_to(">");
+            tokens.push(Token::Other);
+        } else if let Some((token, end)) = parse_tag(html, pos) {
+            tokens.push(token);
+            pos = end;
+        } else {
+            tokens.push(Token::LessThan);
+            pos += 1;
+        }
+    }
+
+    tokens
+}
+
+// parse `<tag attr="value">` or `</tag>` starting at `pos`,
+// returns token and position after it
+fn parse_tag(html: &str, pos: usize) -> Option<(Token<'_>, usize)> {
+    let bytes = html.as_bytes();
+    let mut pos = pos + 1;
+
+    let closing = bytes.get(pos) == Some(&b'/');
+    if closing { pos += 1; }
+
+    if !bytes.get(pos)?.is_ascii_alphabetic() { return None; }
+    let name_start = pos;
+    while pos < bytes.len() && !is_space(bytes[pos]) && bytes[pos] != b'/' && bytes[pos] != b'>' {
+        pos += 1;
+    }
+    let name = html[name_start..pos].to_ascii_lowercase();
+
+    if closing {
+        let end = html[pos..].find('>')?;
+        return Some((Token::Close { name }, pos + end + 1));
+    }
+
+    let mut attrs = Vec::new();
+    loop {
+        while pos < bytes.len() && (is_space(bytes[pos]) || bytes[pos] == b'/' && bytes.get(pos + 1) != Some(&b'>')) {
+            pos += 1;
+        }
+
+        match bytes.get(pos)? {
+            b'>' => return Some((Token::Open { name, attrs, self_closing: false }, pos + 1)),
+            b'/' => return Some((Token::Open { name, attrs, self_closing: true }, pos + 2)),
+            _ => {}
+        }
+
+        let attr_start = pos;
+        pos += 1;
+        while pos < bytes.len() && !is_space(bytes[pos]) && !matches!(bytes[pos], b'/' | b'>' | b'=') {
+            pos += 1;
+        }
+        let attr = &html[attr_start..pos];
+
+        while pos < bytes.len() && is_space(bytes[pos]) { pos += 1; }
+        if bytes.get(pos) != Some(&b'=') {
+            attrs.push((attr, None));
+            continue;
+        }
+        pos += 1;
+        while pos < bytes.len() && is_space(bytes[pos]) { pos += 1; }
+
+        let value = match bytes.get(pos)? {
+            quote @ (b'"' | b'\'') => {
+                let len = html[pos + 1..].find(*quote as char)?;
+                let value = &html[pos + 1..pos + 1 + len];
+                pos += len + 2;
+                value
+            }
+            _ => {
+                let start = pos;
+                while pos < bytes.len() && !is_space(bytes[pos]) && bytes[pos] != b'>' {
+                    pos += 1;
+                }
+                &html[start..pos]
+            }
+        };
+        attrs.push((attr, Some(value)));
+    }
+}
+
+fn is_space(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\n' | b'\r' | b'\x0C')
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn parser() -> MarkdownIt {
+        let mut md = MarkdownIt::new();
+        crate::plugins::cmark::add(&mut md);
+        crate::plugins::html::add(&mut md);
+        add(&mut md);
+        md
+    }
+
+    #[rstest]
+    #[case("<b>a</b>", "<p><b>a</b></p>")]
+    #[case("<B Title='x'>a</B>", "<p><b title=\"x\">a</b></p>")]
+    #[case("<img src=x onerror=alert(1)>", "<img src=\"x\">")]
+    #[case("<a href=\"javascript:alert(1)\">a</a>", "<p><a>a</a></p>")]
+    #[case("<a href=\"java&#x73;cript:alert(1)\">a</a>", "<p><a>a</a></p>")]
+    #[case("<a href='/a b' style=\"color: red\">a</a>", "<p><a href=\"/a%20b\">a</a></p>")]
+    #[case("<span class=\"&quot;x\">a</span>", "<p><span>a</span></p>")]
+    #[case("<span title=\"&quot;&lt;\">a</span>", "<p><span title=\"&quot;&lt;\">a</span></p>")]
+    #[case("<iframe src=x>\n\nok", "")]
+    #[case("a <iframe src=x></iframe> b", "<p>a  b</p>")]
+    #[case("a <style>*{}</style> <em>b</em>", "<p>a  <em>b</em></p>")]
+    #[case("<div>\n<script>alert(1)</script>\n<p>a</p>\n</div>", "<div>\n\n<p>a</p>\n</div>")]
+    #[case("<div><!-- hi --><x-y>a</x-y></div>", "<div>a</div>")]
+    #[case("<details open><summary>a</summary></details>", "<details open><summary>a</summary></details>")]
+    #[case("a</b></div> <br> <img src=x>", "<p>a <br> <img src=\"x\"></p>")]
+    #[case("<b><i>a</b> b</i>", "<p><b><i>a</i></b> b</p>")]
+    #[case("<div>\n\n*a* <b>b\n\n</div>", "<div>\n<p><em>a</em> <b>b</p>\n</b></div>")]
+    #[case("<div>\n\n<b>a</b>\n\n</div>\n\n</div>", "<div>\n<p><b>a</b></p>\n</div>")]
+    fn sanitizes(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(parser().parse(input).render(&Default::default()).trim(), expected);
+    }
+
+    #[test]
+    fn tokenizer() {
+        assert_eq!(tokenize("a < b <br/> <a/href=x>"), vec![
+            Token::Text("a "),
+            Token::LessThan,
+            Token::Text(" b "),
+            Token::Open { name: "br".into(), attrs: vec![], self_closing: true },
+            Token::Text(" "),
+            Token::Open { name: "a".into(), attrs: vec![("href", Some("x"))], self_closing: false },
+        ]);
+        assert_eq!(tokenize("<p title=\"x"), vec![Token::LessThan, Token::Text("p title=\"x")]);
+    }
+
+    #[test]
+    fn balances_fragment() {
+        let md = parser();
+        let options = SanitizeOptions::default();
+        assert_eq!(sanitize("</p><div><b>a<x-y></div>", &options, &md), "<div><b>a</b></div>");
+        assert_eq!(sanitize("<ul><li>a", &options, &md), "<ul><li>a</li></ul>");
+    }
+
+    #[test]
+    fn custom_options() {
+        let md = &mut MarkdownIt::new();
+        crate::plugins::cmark::add(md);
+        crate::plugins::html::add(md);
+        add_with_options(md, SanitizeOptions {
+            tags: vec!["span".into()],
+            attributes: vec!["class".into()],
+            tag_attributes: vec![],
+            url_attributes: vec![],
+            remove_content: vec![],
+        });
+
+        let html = md.parse("<span class=x><b>a</b></span><script>b</script>").render(&Default::default());
+        assert_eq!(html.trim(), "<p><span class=\"x\">a</span>b</p>");
+    }
+}
@@ -0,0 +1,107 @@
+//! Diagrams written in code fences (mermaid, plantuml, etc.)
+//!
+//! ```` ```mermaid ```` fences are rendered as `<pre class="mermaid">`, with source
+//! escaped, so it's picked up by mermaid.js on the client side. This works the
+//! same regardless of whether syntax highlighting is enabled.
+//!
+//! ```rust
+//! let md = &mut markdown_it::MarkdownIt::new();
+//! markdown_it::plugins::cmark::add(md);
+//! markdown_it::plugins::jotdown::diagrams::add(md);
+//!
+//! let html = md.parse("```mermaid\na-->b\n```").render(&Default::default());
+//! assert_eq!(html.trim(), "<pre class=\"mermaid\">a--&gt;b\n</pre>");
+//! ```
+
+use crate::generics::block::fence_handler;
+use crate::parser::extset::RenderOptionsExtSet;
+use crate::plugins::cmark::block::fence::CodeFence;
+use crate::{MarkdownIt, Node, NodeValue, Renderer};
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Diagram {
+    /// Diagram language, first word of the fence info string (e.g. `mermaid`).
+    pub kind: String,
+    pub source: String,
+}
+
+impl NodeValue for Diagram {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, _options: &RenderOptionsExtSet) {
+        let mut attrs = node.attrs.clone();
+        attrs.push(("class", self.kind.clone()));
+
+        fmt.cr();
+        fmt.open("pre", &attrs);
+        fmt.text(&self.source);
+        fmt.close("pre");
+        fmt.cr();
+    }
+}
+
+#[derive(Debug)]
+/// Options for the diagram plugin.
+pub struct DiagramOptions {
+    /// Fence languages that should be treated as diagrams.
+    pub kinds: Vec<String>,
+}
+
+impl Default for DiagramOptions {
+    fn default() -> Self {
+        Self { kinds: vec![String::from("mermaid")] }
+    }
+}
+
+/// Add the diagram plugin to MarkdownIt.
+pub fn add(md: &mut MarkdownIt) {
+    add_with_options(md, DiagramOptions::default());
+}
+
+/// Add the diagram plugin to MarkdownIt, with options.
+pub fn add_with_options(md: &mut MarkdownIt, options: DiagramOptions) {
+    for kind in options.kinds.iter() {
+        fence_handler::add(md, kind, render_diagram);
+    }
+}
+
+fn render_diagram(fence: &CodeFence, _: &MarkdownIt) -> Option<Node> {
+    Some(Node::new(Diagram {
+        kind: fence_handler::get_language(&fence.info),
+        source: fence.content.clone(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::plugins::cmark;
+
+    use super::*;
+
+    #[test]
+    fn custom_kinds() {
+        let md = &mut MarkdownIt::new();
+        cmark::add(md);
+        add_with_options(md, DiagramOptions { kinds: vec!["plantuml".into()] });
+
+        let html = md.parse("```plantuml\nA -> B\n```\n```mermaid\na\n```").render(&Default::default());
+        assert_eq!(html, "<pre class=\"plantuml\">A -&gt; B\n</pre>\n\
+            <pre><code class=\"language-mermaid\">a\n</code></pre>\n");
+    }
+
+    #[cfg(feature = "syntect")]
+    #[test]
+    fn with_syntect() {
+        let md = &mut MarkdownIt::new();
+        cmark::add(md);
+        crate::plugins::extra::syntect::add(md);
+        add(md);
+
+        let html = md.parse("```mermaid\n<script>\n```").render(&Default::default());
+        assert_eq!(html, "<pre class=\"mermaid\">&lt;script&gt;\n</pre>\n");
+    }
+}
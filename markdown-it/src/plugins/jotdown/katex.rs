@@ -1,11 +0,0 @@
-use crate::MarkdownIt;
-
-
-
-#[doc(hidden)]
-pub struct KatexBlockScanner;
-
-
-
-
-pub fn add(md: &mut MarkdownIt) {}
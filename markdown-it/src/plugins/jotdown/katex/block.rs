@@ -0,0 +1,218 @@
+//! Display math
+//!
+//! `$$ looks like this $$` or `\[ looks like this \]`, either on a single
+//! line or spanning multiple lines:
+//!
+//! ```text
+//! $$
+//! \int_0^1 x^2 \, dx = \frac{1}{3}
+//! $$
+//! ```
+//!
+//! Output is wrapped the same way pandoc does it (`<div class="math display">\[...\]</div>`),
+//! so KaTeX or MathJax auto-render picks it up on the client side.
+
+use crate::parser::block::{BlockRule, BlockState};
+use crate::parser::extset::RenderOptionsExtSet;
+use crate::plugins::cmark::block::paragraph::ParagraphScanner;
+use crate::{MarkdownIt, Node, NodeValue, Renderer};
+
+use super::equations::{DuplicateLabel, EquationNumber};
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MathBlock {
+    /// Raw TeX source, without delimiters.
+    pub content: String,
+    pub delimiter: MathDelimiter,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+/// Delimiters used to write display math in the source.
+pub enum MathDelimiter {
+    /// `$$ ... $$`
+    Dollars,
+    /// `\[ ... \]`
+    Brackets,
+}
+
+impl MathDelimiter {
+    pub fn open(&self) -> &'static str {
+        match self {
+            Self::Dollars => "$$",
+            Self::Brackets => "\\[",
+        }
+    }
+
+    pub fn close(&self) -> &'static str {
+        match self {
+            Self::Dollars => "$$",
+            Self::Brackets => "\\]",
+        }
+    }
+}
+
+impl NodeValue for MathBlock {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
+        let tex = super::prepared_tex(node, &self.content);
+
+        // block only contained macro definitions
+        if tex.is_empty() { return; }
+
+        let equation = node.ext.get::<EquationNumber>();
+        let mut attrs = node.attrs.clone();
+        if let Some(equation) = equation {
+            attrs.push(("id", equation.label.clone()));
+        }
+        attrs.push(("class", "math display".into()));
+
+        let mathml = super::mathml_output(tex, true, fmt, options);
+        if matches!(mathml, Some(None)) || node.ext.contains::<DuplicateLabel>() {
+            attrs.push(("class", "math-error".into()));
+        }
+
+        fmt.cr();
+        fmt.open("div", &attrs);
+        if let Some(Some(html)) = mathml {
+            fmt.text_raw(&html);
+        } else {
+            fmt.text("\\[");
+            fmt.text(tex);
+            fmt.text("\\]");
+        }
+        if let Some(equation) = equation {
+            fmt.open("span", &[("class", "math-number".into())]);
+            fmt.text(&format!("({})", equation.number));
+            fmt.close("span");
+        }
+        fmt.close("div");
+        fmt.cr();
+    }
+}
+
+pub fn add(md: &mut MarkdownIt) {
+    md.block.add_rule::<KatexBlockScanner>()
+        .before::<ParagraphScanner>();
+}
+
+#[doc(hidden)]
+pub struct KatexBlockScanner;
+
+impl KatexBlockScanner {
+    fn get_header<'a>(state: &'a mut BlockState) -> Option<(MathDelimiter, &'a str)> {
+
+        if state.line_indent(state.line) >= state.md.max_indent { return None; }
+
+        let line = state.get_line(state.line);
+
+        if let Some(rest) = line.strip_prefix("$$") {
+            Some((MathDelimiter::Dollars, rest))
+        } else if let Some(rest) = line.strip_prefix("\\[") {
+            Some((MathDelimiter::Brackets, rest))
+        } else {
+            None
+        }
+    }
+}
+
+impl BlockRule for KatexBlockScanner {
+    fn run(state: &mut BlockState) -> Option<(Node, usize)> {
+        let (delimiter, first) = Self::get_header(state)?;
+        let close = delimiter.close();
+
+        // single line, `$$ x^2 $$`
+        if let Some(body) = first.trim_end().strip_suffix(close) {
+            if body.contains(close) || body.trim().is_empty() { return None; }
+
+            let node = Node::new(MathBlock {
+                content: body.trim().to_owned(),
+                delimiter,
+            });
+            return Some((node, 1));
+        }
+
+        if first.contains(close) { return None; }
+        let first = first.trim().to_owned();
+
+        // search end of block, unlike code fences math blocks are never autoclosed
+        let mut next_line = state.line;
+        let last = loop {
+            next_line += 1;
+            if next_line >= state.line_max { return None; }
+
+            let line = state.get_line(next_line);
+
+            if !line.is_empty() && state.line_indent(next_line) < 0 {
+                // math block can't escape from its parent container
+                return None;
+            }
+
+            if let Some(body) = line.trim_end().strip_suffix(close) {
+                if body.contains(close) { return None; }
+                break body.trim_end();
+            }
+
+            if line.starts_with(delimiter.open()) {
+                // another math block can't start inside of this one, stopping here
+                // also keeps unclosed blocks from rescanning the rest of the document
+                return None;
+            }
+        };
+
+        let indent = state.line_offsets[state.line].indent_nonspace;
+        let (middle, _) = state.get_lines(state.line + 1, next_line, indent as usize, false);
+
+        let mut content = String::new();
+        for part in [first.as_str(), middle.as_str(), last] {
+            if part.trim().is_empty() { continue; }
+            if !content.is_empty() { content.push('\n'); }
+            content.push_str(part);
+        }
+
+        if content.is_empty() { return None; }
+
+        let node = Node::new(MathBlock { content, delimiter });
+        Some((node, next_line - state.line + 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::plugins::cmark;
+
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("$$x^2$$", "<div class=\"math display\">\\[x^2\\]</div>\n")]
+    #[case("$$\na < b\n$$", "<div class=\"math display\">\\[a &lt; b\\]</div>\n")]
+    #[case("\\[\n  x\n  y\n\\]", "<div class=\"math display\">\\[  x\n  y\\]</div>\n")]
+    #[case("$$ a\nb $$", "<div class=\"math display\">\\[a\nb\\]</div>\n")]
+    #[case("$$\nunclosed", "<p>$$\nunclosed</p>\n")]
+    #[case("$$a$$ and $$b$$", "<p>$$a$$ and $$b$$</p>\n")]
+    #[case("$$a\n$$b\nc$$", "<p>$$a</p>\n<div class=\"math display\">\\[b\nc\\]</div>\n")]
+    fn test_render(#[case] input: &str, #[case] expected: &str) {
+        let md = &mut MarkdownIt::new();
+        cmark::add(md);
+        add(md);
+        assert_eq!(md.parse(input).render(&Default::default()), expected);
+    }
+
+    #[test]
+    fn test_sourcepos() {
+        let md = &mut MarkdownIt::new();
+        cmark::add(md);
+        add(md);
+        let node = md.parse("para\n\n$$\nx\n$$\n");
+        let math = &node.children[1];
+        assert!(math.is::<MathBlock>());
+        assert_eq!(math.srcmap.unwrap().get_byte_offsets(), (6, 13));
+    }
+}
@@ -0,0 +1,240 @@
+//! Equation numbering and references
+//!
+//! Display math blocks containing `\label{name}` are numbered in document order,
+//! and `\eqref{name}` (written either in text or inside math) refers to them.
+//! In text it becomes a link to the equation, inside math it's replaced with
+//! the number itself. If the same label is used twice, only the first block
+//! is numbered, others are marked with `math-error` class.
+//!
+//! ```rust
+//! let md = &mut markdown_it::MarkdownIt::new();
+//! markdown_it::plugins::cmark::add(md);
+//! markdown_it::plugins::jotdown::katex::add(md);
+//!
+//! let html = md.parse("$$\nE = mc^2 \\label{eq:energy}\n$$\n\nsee \\eqref{eq:energy}").render(&Default::default());
+//! assert_eq!(html, "<div id=\"eq:energy\" class=\"math display\">\\[E = mc^2\\]\
+//!     <span class=\"math-number\">(1)</span></div>\n\
+//!     <p>see <a href=\"#eq:energy\" class=\"eqref\">(1)</a></p>\n");
+//! ```
+use std::collections::HashMap;
+
+use crate::parser::core::{CoreRule, Root};
+use crate::parser::extset::{NodeExt, RenderOptionsExtSet, RootExt};
+use crate::parser::inline::{InlineRule, InlineState};
+use crate::plugins::cmark::inline::escape::EscapeScanner;
+use crate::{MarkdownIt, Node, NodeValue, Renderer};
+
+use super::block::MathBlock;
+use super::inline::InlineMath;
+use super::macros::MathMacrosRule;
+use super::PreparedTex;
+
+#[derive(Debug, Default)]
+/// Equation labels of the document, stored in the root node.
+pub struct EquationLabels {
+    pub labels: HashMap<String, usize>,
+}
+
+impl RootExt for EquationLabels {}
+
+#[derive(Debug)]
+/// Number assigned to display math block, stored in its node.
+pub struct EquationNumber {
+    pub label: String,
+    pub number: usize,
+}
+
+impl NodeExt for EquationNumber {}
+
+#[derive(Debug)]
+/// Label of display math block that was already used by an earlier block,
+/// stored in its node. Such block isn't numbered and is rendered with
+/// `math-error` class.
+pub struct DuplicateLabel(pub String);
+
+impl NodeExt for DuplicateLabel {}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// `\eqref{label}` written in text.
+pub struct EquationRef {
+    pub label: String,
+    /// Number of referenced equation, filled in by core rule,
+    /// `None` if the label doesn't exist.
+    pub number: Option<usize>,
+}
+
+impl NodeValue for EquationRef {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, _options: &RenderOptionsExtSet) {
+        let mut attrs = node.attrs.clone();
+
+        if let Some(number) = self.number {
+            attrs.push(("href", format!("#{}", self.label)));
+            attrs.push(("class", "eqref".into()));
+            fmt.open("a", &attrs);
+            fmt.text(&format!("({})", number));
+            fmt.close("a");
+        } else {
+            // same as LaTeX does for undefined references
+            attrs.push(("class", "eqref".into()));
+            fmt.open("span", &attrs);
+            fmt.text("(??)");
+            fmt.close("span");
+        }
+    }
+}
+
+pub fn add(md: &mut MarkdownIt) {
+    md.inline.add_rule::<EquationRefScanner>()
+        .before::<EscapeScanner>();
+    md.add_rule::<EquationNumberRule>()
+        .after::<MathMacrosRule>();
+}
+
+// `\eqref{label}`, returns label and total length
+fn scan_eqref(src: &str) -> Option<(&str, usize)> {
+    let rest = src.strip_prefix("\\eqref{")?;
+    let end = rest.find(['}', '\n'])?;
+    if !rest[end..].starts_with('}') { return None; }
+
+    let label = rest[..end].trim();
+    if label.is_empty() { return None; }
+    Some((label, "\\eqref{".len() + end + 1))
+}
+
+#[doc(hidden)]
+pub struct EquationRefScanner;
+impl InlineRule for EquationRefScanner {
+    const MARKER: char = '\\';
+
+    fn check(state: &mut InlineState) -> Option<usize> {
+        scan_eqref(&state.src[state.pos..state.pos_max]).map(|(_, len)| len)
+    }
+
+    fn run(state: &mut InlineState) -> Option<(Node, usize)> {
+        let (label, len) = scan_eqref(&state.src[state.pos..state.pos_max])?;
+        let node = Node::new(EquationRef { label: label.to_owned(), number: None });
+        Some((node, len))
+    }
+}
+
+// find `\command{argument}`, returns (start, end, argument)
+fn find_command<'a>(tex: &'a str, command: &str) -> Option<(usize, usize, &'a str)> {
+    let mut offset = 0;
+
+    while let Some(pos) = tex[offset..].find(command) {
+        let start = offset + pos;
+        let rest = &tex[start + command.len()..];
+        offset = start + command.len();
+
+        // `\labelfoo` is a different command
+        if rest.starts_with(|c: char| c.is_ascii_alphabetic()) { continue; }
+
+        let Some(arg) = rest.trim_start().strip_prefix('{') else { continue };
+        let Some(len) = arg.find('}') else { continue };
+        let arg_start = tex.len() - arg.len();
+        return Some((start, arg_start + len + 1, arg[..len].trim()));
+    }
+
+    None
+}
+
+#[doc(hidden)]
+pub struct EquationNumberRule;
+impl CoreRule for EquationNumberRule {
+    fn run(root: &mut Node, _: &MarkdownIt) {
+        let mut labels = EquationLabels::default();
+
+        // assign numbers and remove `\label` from display math
+        root.walk_mut(|node, _| {
+            let Some(data) = node.cast::<MathBlock>() else { return };
+            let tex = super::prepared_tex(node, &data.content);
+            let Some((start, end, label)) = find_command(tex, "\\label") else { return };
+
+            let label = label.to_owned();
+            let tex = format!("{}{}", tex[..start].trim_end(), &tex[end..]).trim().to_owned();
+
+            if !labels.labels.contains_key(&label) {
+                let number = labels.labels.len() + 1;
+                labels.labels.insert(label.clone(), number);
+                node.ext.insert(EquationNumber { label, number });
+            } else {
+                node.ext.insert(DuplicateLabel(label));
+            }
+
+            node.ext.insert(PreparedTex(tex));
+        });
+
+        // resolve references
+        root.walk_mut(|node, _| {
+            if let Some(data) = node.cast_mut::<EquationRef>() {
+                data.number = labels.labels.get(&data.label).copied();
+                return;
+            }
+
+            let content = if let Some(data) = node.cast::<MathBlock>() {
+                &data.content
+            } else if let Some(data) = node.cast::<InlineMath>() {
+                &data.content
+            } else {
+                return;
+            };
+
+            let mut tex = super::prepared_tex(node, content).to_owned();
+            let mut changed = false;
+            let mut offset = 0;
+
+            while let Some((start, end, label)) = find_command(&tex[offset..], "\\eqref") {
+                let (start, end) = (offset + start, offset + end);
+                let Some(number) = labels.labels.get(label).copied() else {
+                    offset = end;
+                    continue;
+                };
+                let replacement = format!("\\text{{({})}}", number);
+                tex.replace_range(start..end, &replacement);
+                offset = start + replacement.len();
+                changed = true;
+            }
+
+            if changed {
+                node.ext.insert(PreparedTex(tex));
+            }
+        });
+
+        root.cast_mut::<Root>().unwrap().ext.insert(labels);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::plugins::cmark;
+    use crate::plugins::jotdown::katex;
+
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("\\eqref{missing}", "<p><span class=\"eqref\">(??)</span></p>\n")]
+    #[case("$$a \\label{x}$$\n$$b \\label{y}$$\n$\\eqref{y}$",
+        "<div id=\"x\" class=\"math display\">\\[a\\]<span class=\"math-number\">(1)</span></div>\n\
+        <div id=\"y\" class=\"math display\">\\[b\\]<span class=\"math-number\">(2)</span></div>\n\
+        <p><span class=\"math inline\">\\(\\text{(2)}\\)</span></p>\n")]
+    #[case("$$\\newcommand{\\lbl}{\\label{x}} a \\lbl$$\nsee \\eqref{x}",
+        "<div id=\"x\" class=\"math display\">\\[a\\]<span class=\"math-number\">(1)</span></div>\n\
+        <p>see <a href=\"#x\" class=\"eqref\">(1)</a></p>\n")]
+    #[case("$$a \\label{x}$$\n$$b \\label{x}$$\n\\eqref{x}",
+        "<div id=\"x\" class=\"math display\">\\[a\\]<span class=\"math-number\">(1)</span></div>\n\
+        <div class=\"math display math-error\">\\[b\\]</div>\n\
+        <p><a href=\"#x\" class=\"eqref\">(1)</a></p>\n")]
+    fn test_render(#[case] input: &str, #[case] expected: &str) {
+        let md = &mut MarkdownIt::new();
+        cmark::add(md);
+        katex::add(md);
+        assert_eq!(md.parse(input).render(&Default::default()), expected);
+    }
+}
@@ -0,0 +1,272 @@
+//! Inline math
+//!
+//! `$looks like this$`, `\(looks like this\)` or `$$looks like this$$`
+//! (the last one is display math placed inside a paragraph).
+//!
+//! Follows pandoc rules for `$`: opening `$` must have a non-space character
+//! immediately to its right, closing `$` must have a non-space character
+//! immediately to its left and must not be followed by a digit (so `$20 and $30`
+//! is plain text). Backslash escapes are respected both outside (`\$` is a literal
+//! dollar sign) and inside of math (`$\$$` is a valid formula).
+//!
+//! Math is scanned as a single token, so emphasis markers, underscores or brackets
+//! inside of TeX never interact with the rest of the paragraph.
+
+use std::collections::HashMap;
+
+use crate::parser::extset::{InlineRootExt, RenderOptionsExtSet};
+use crate::parser::inline::{InlineRule, InlineState};
+use crate::plugins::cmark::inline::escape::EscapeScanner;
+use crate::{MarkdownIt, Node, NodeValue, Renderer};
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InlineMath {
+    /// Raw TeX source, without delimiters.
+    pub content: String,
+    pub delimiter: InlineMathDelimiter,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+/// Delimiters used to write inline math in the source.
+pub enum InlineMathDelimiter {
+    /// `$ ... $`
+    Dollar,
+    /// `$$ ... $$`, rendered as display math
+    Dollars,
+    /// `\( ... \)`
+    Parens,
+}
+
+impl InlineMathDelimiter {
+    pub fn open(&self) -> &'static str {
+        match self {
+            Self::Dollar => "$",
+            Self::Dollars => "$$",
+            Self::Parens => "\\(",
+        }
+    }
+
+    pub fn close(&self) -> &'static str {
+        match self {
+            Self::Dollar => "$",
+            Self::Dollars => "$$",
+            Self::Parens => "\\)",
+        }
+    }
+}
+
+impl InlineMath {
+    /// Returns true if this formula should be typeset in display style.
+    pub fn is_display(&self) -> bool {
+        self.delimiter == InlineMathDelimiter::Dollars
+    }
+}
+
+impl NodeValue for InlineMath {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
+        let tex = super::prepared_tex(node, &self.content);
+
+        // formula only contained macro definitions
+        if tex.is_empty() { return; }
+
+        let mut attrs = node.attrs.clone();
+        let class = if self.is_display() { "math display" } else { "math inline" };
+        attrs.push(("class", class.into()));
+
+        let mathml = super::mathml_output(tex, self.is_display(), fmt, options);
+        if let Some(None) = mathml {
+            attrs.push(("class", "math-error".into()));
+        }
+
+        fmt.open("span", &attrs);
+        if let Some(Some(html)) = mathml {
+            fmt.text_raw(&html);
+        } else if self.is_display() {
+            fmt.text("\\[");
+            fmt.text(tex);
+            fmt.text("\\]");
+        } else {
+            fmt.text("\\(");
+            fmt.text(tex);
+            fmt.text("\\)");
+        }
+        fmt.close("span");
+    }
+}
+
+pub fn add(md: &mut MarkdownIt) {
+    md.inline.add_rule::<InlineMathScanner>();
+    md.inline.add_rule::<InlineMathParensScanner>()
+        .before::<EscapeScanner>();
+}
+
+// first closer found for each delimiter kind and `pos_max`, together with
+// the position scanning started from; escaped characters only depend
+// on the preceding run of backslashes, and an opener is never a backslash,
+// so the same closer is valid for every later opener in front of it
+#[derive(Debug, Default)]
+struct CloserCache {
+    closers: HashMap<(InlineMathDelimiter, usize), (usize, Option<usize>)>,
+}
+impl InlineRootExt for CloserCache {}
+
+// returns delimiter, content range and total length of the formula
+fn scan_dollars(state: &mut InlineState) -> Option<(InlineMathDelimiter, usize, usize, usize)> {
+    let src = &state.src[state.pos..state.pos_max];
+
+    if src.starts_with("$$") {
+        let start = state.pos + 2;
+        let end = find_cached_closer(state, start, InlineMathDelimiter::Dollars, |rest, _| {
+            rest.starts_with("$$")
+        })?;
+        if state.src[start..start + end].trim().is_empty() { return None; }
+        return Some((InlineMathDelimiter::Dollars, 2, 2 + end, 2 + end + 2));
+    }
+
+    let rest = src.strip_prefix('$')?;
+
+    // opening `$` must have a non-space character immediately to its right
+    if rest.starts_with(char::is_whitespace) { return None; }
+
+    let end = find_cached_closer(state, state.pos + 1, InlineMathDelimiter::Dollar, |rest, prev| {
+        // closing `$` must have a non-space character immediately to its left,
+        // and must not be followed immediately by a digit
+        rest.starts_with('$') &&
+            !prev.is_some_and(char::is_whitespace) &&
+            !rest[1..].starts_with(|c: char| c.is_ascii_digit())
+    })?;
+
+    if end == 0 { return None; }
+    Some((InlineMathDelimiter::Dollar, 1, 1 + end, 1 + end + 1))
+}
+
+fn scan_parens(state: &mut InlineState) -> Option<(InlineMathDelimiter, usize, usize, usize)> {
+    if !state.src[state.pos..state.pos_max].starts_with("\\(") { return None; }
+    let start = state.pos + 2;
+    let end = find_cached_closer(state, start, InlineMathDelimiter::Parens, |rest, _| {
+        rest.starts_with("\\)")
+    })?;
+    if state.src[start..start + end].trim().is_empty() { return None; }
+    Some((InlineMathDelimiter::Parens, 2, 2 + end, 2 + end + 2))
+}
+
+// same as `find_closer` on `state.src[start..state.pos_max]`, but reuses
+// the result of a previous scan if it started before `start` and the closer
+// it found (if any) is not behind it, so unclosed openers don't rescan the
+// rest of the line over and over
+fn find_cached_closer(
+    state: &mut InlineState,
+    start: usize,
+    delimiter: InlineMathDelimiter,
+    is_closer: impl Fn(&str, Option<char>) -> bool,
+) -> Option<usize> {
+    let key = (delimiter, state.pos_max);
+    let cache = state.inline_ext.get_or_insert_default::<CloserCache>();
+
+    if let Some(&(from, closer)) = cache.closers.get(&key) {
+        if from <= start && closer.is_none_or(|closer| closer >= start) {
+            return closer.map(|closer| closer - start);
+        }
+    }
+
+    let closer = find_closer(&state.src[start..state.pos_max], is_closer).map(|pos| start + pos);
+    let cache = state.inline_ext.get_mut::<CloserCache>().unwrap();
+    cache.closers.insert(key, (start, closer));
+    closer.map(|closer| closer - start)
+}
+
+// find first position in `src` where `is_closer` matches, skipping over
+// backslash escapes (but still giving `is_closer` a chance to match on `\)`)
+fn find_closer(src: &str, is_closer: impl Fn(&str, Option<char>) -> bool) -> Option<usize> {
+    let mut chars = src.char_indices();
+    let mut prev = None;
+
+    while let Some((pos, ch)) = chars.next() {
+        if is_closer(&src[pos..], prev) { return Some(pos); }
+
+        if ch == '\\' {
+            // escaped character can't be a closer
+            prev = chars.next().map(|(_, ch)| ch);
+            continue;
+        }
+
+        prev = Some(ch);
+    }
+
+    None
+}
+
+fn make_node(state: &InlineState, scanned: (InlineMathDelimiter, usize, usize, usize)) -> (Node, usize) {
+    let (delimiter, start, end, len) = scanned;
+    let content = state.src[state.pos + start..state.pos + end].to_owned();
+    (Node::new(InlineMath { content, delimiter }), len)
+}
+
+#[doc(hidden)]
+pub struct InlineMathScanner;
+impl InlineRule for InlineMathScanner {
+    const MARKER: char = '$';
+
+    fn check(state: &mut InlineState) -> Option<usize> {
+        scan_dollars(state).map(|(_, _, _, len)| len)
+    }
+
+    fn run(state: &mut InlineState) -> Option<(Node, usize)> {
+        let scanned = scan_dollars(state)?;
+        Some(make_node(state, scanned))
+    }
+}
+
+#[doc(hidden)]
+pub struct InlineMathParensScanner;
+impl InlineRule for InlineMathParensScanner {
+    const MARKER: char = '\\';
+
+    fn check(state: &mut InlineState) -> Option<usize> {
+        scan_parens(state).map(|(_, _, _, len)| len)
+    }
+
+    fn run(state: &mut InlineState) -> Option<(Node, usize)> {
+        let scanned = scan_parens(state)?;
+        Some(make_node(state, scanned))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::plugins::cmark;
+
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("$x^2$", "<p><span class=\"math inline\">\\(x^2\\)</span></p>\n")]
+    #[case("\\(a_1 * b_1 * c\\)", "<p><span class=\"math inline\">\\(a_1 * b_1 * c\\)</span></p>\n")]
+    #[case("$$a < b$$", "<p><span class=\"math display\">\\[a &lt; b\\]</span></p>\n")]
+    #[case("$20 and $30", "<p>$20 and $30</p>\n")]
+    #[case("$ x$", "<p>$ x$</p>\n")]
+    #[case("$x $", "<p>$x $</p>\n")]
+    #[case("\\$x$", "<p>$x$</p>\n")]
+    #[case("$\\$$", "<p><span class=\"math inline\">\\(\\$\\)</span></p>\n")]
+    #[case("$a$1 $b$", "<p><span class=\"math inline\">\\(a$1 $b\\)</span></p>\n")]
+    #[case("\\(a\\\\b\\)", "<p><span class=\"math inline\">\\(a\\\\b\\)</span></p>\n")]
+    #[case("*$a*b$*", "<p><em><span class=\"math inline\">\\(a*b\\)</span></em></p>\n")]
+    #[case("[$a]$](/url)", "<p><a href=\"/url\"><span class=\"math inline\">\\(a]\\)</span></a></p>\n")]
+    #[case("$$ $$a$$", "<p>$$ <span class=\"math display\">\\[a\\]</span></p>\n")]
+    #[case("$a $b $c$", "<p><span class=\"math inline\">\\(a $b $c\\)</span></p>\n")]
+    #[case("\\(a \\(b\\)", "<p><span class=\"math inline\">\\(a \\(b\\)</span></p>\n")]
+    fn test_render(#[case] input: &str, #[case] expected: &str) {
+        let md = &mut MarkdownIt::new();
+        cmark::add(md);
+        add(md);
+        assert_eq!(md.parse(input).render(&Default::default()), expected);
+    }
+}
@@ -0,0 +1,382 @@
+//! Document-wide TeX macros
+//!
+//! Macros defined with `\newcommand`, `\renewcommand` or `\def` in any math node
+//! are available in every math node of the same document. Definitions are removed
+//! from rendered output, so a display math block that only contains definitions
+//! is not rendered at all.
+//!
+//! ```rust
+//! let md = &mut markdown_it::MarkdownIt::new();
+//! markdown_it::plugins::cmark::add(md);
+//! markdown_it::plugins::jotdown::katex::add(md);
+//!
+//! let src = "$$\\newcommand{\\R}{\\mathbb{R}}$$\n\n$f: \\R \\to \\R$";
+//! let html = md.parse(src).render(&Default::default());
+//! assert_eq!(html.trim(), r#"<p><span class="math inline">\(f: \mathbb{R} \to \mathbb{R}\)</span></p>"#);
+//! ```
+//!
+//! Macros are expanded on the server side, so it works the same way
+//! for KaTeX and MathML output.
+use std::collections::HashMap;
+
+use crate::parser::core::{CoreRule, Root};
+use crate::parser::extset::RootExt;
+use crate::parser::inline::builtin::InlineParserRule;
+use crate::{MarkdownIt, Node};
+
+use super::block::MathBlock;
+use super::inline::InlineMath;
+use super::PreparedTex;
+
+/// Prevents infinite recursion in definitions like `\def\a{\a}`.
+const MAX_EXPANSION_DEPTH: usize = 32;
+
+/// Expansion may add this many times the length of the source
+/// (but at least [MIN_EXPANSION_LIMIT] bytes), so definitions like
+/// `\def\b{\a\a} \def\c{\b\b}` can't grow it exponentially.
+/// The limit applies to all math of a document together.
+const MAX_EXPANSION_FACTOR: usize = 16;
+const MIN_EXPANSION_LIMIT: usize = 16 * 1024;
+
+#[derive(Debug, Default)]
+/// Macro table of the document, stored in the root node.
+pub struct MathMacros {
+    pub macros: HashMap<String, MathMacro>,
+}
+
+impl RootExt for MathMacros {}
+
+#[derive(Debug, Clone)]
+/// Single macro definition, `\newcommand{\name}[args]{body}`.
+pub struct MathMacro {
+    pub args: usize,
+    pub body: String,
+}
+
+impl MathMacros {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace macro `name` (without leading backslash).
+    pub fn define(&mut self, name: &str, args: usize, body: &str) {
+        self.macros.insert(name.to_owned(), MathMacro { args, body: body.to_owned() });
+    }
+
+    /// Find all macro definitions in `tex`, add them to the table
+    /// and return the rest of the source with definitions removed.
+    pub fn collect(&mut self, tex: &str) -> String {
+        let mut result = String::new();
+        let mut pos = 0;
+
+        while pos < tex.len() {
+            let Some((command, end)) = read_command(tex, pos) else {
+                let ch = tex[pos..].chars().next().unwrap();
+                result.push(ch);
+                pos += ch.len_utf8();
+                continue;
+            };
+
+            let definition = match command {
+                "newcommand" | "renewcommand" | "providecommand" => parse_newcommand(tex, end),
+                "def" => parse_def(tex, end),
+                _ => None,
+            };
+
+            if let Some((name, args, body, end)) = definition {
+                if command != "providecommand" || !self.macros.contains_key(name) {
+                    self.define(name, args, body);
+                }
+                pos = end;
+            } else {
+                result.push_str(&tex[pos..end]);
+                pos = end;
+            }
+        }
+
+        result.trim().to_owned()
+    }
+
+    /// Replace all known macros in `tex` with their definitions.
+    ///
+    /// Macros that would make the result too long are left as is.
+    pub fn expand(&self, tex: &str) -> String {
+        let mut budget = expansion_budget(tex.len());
+        self.expand_with_budget(tex, &mut budget)
+    }
+
+    /// Same as [expand](Self::expand), but result can only be up to `budget`
+    /// bytes longer than `tex`, and `budget` is reduced by the added length.
+    /// Used to limit expansion of all math in a document together.
+    pub fn expand_with_budget(&self, tex: &str, budget: &mut usize) -> String {
+        let mut result = String::new();
+        self.expand_into(tex, 0, tex.len() + *budget, &mut result);
+        *budget = budget.saturating_sub(result.len().saturating_sub(tex.len()));
+        result
+    }
+
+    fn expand_into(&self, tex: &str, depth: usize, limit: usize, result: &mut String) {
+        let mut pos = 0;
+
+        while pos < tex.len() {
+            let Some((name, end)) = read_command(tex, pos) else {
+                let ch = tex[pos..].chars().next().unwrap();
+                result.push(ch);
+                pos += ch.len_utf8();
+                continue;
+            };
+
+            let Some(definition) = self.macros.get(name).filter(|_| depth < MAX_EXPANSION_DEPTH) else {
+                result.push_str(&tex[pos..end]);
+                pos = end;
+                continue;
+            };
+
+            let mut args = Vec::with_capacity(definition.args);
+            let mut args_end = end;
+            for _ in 0..definition.args {
+                let Some((arg, next)) = read_argument(tex, args_end) else { break };
+                args.push(arg);
+                args_end = next;
+            }
+
+            if args.len() < definition.args {
+                // not enough arguments, leave it as is for KaTeX to report
+                result.push_str(&tex[pos..end]);
+                pos = end;
+                continue;
+            }
+
+            let body = substitute_args(&definition.body, &args);
+            if result.len() + body.len() > limit {
+                result.push_str(&tex[pos..args_end]);
+                pos = args_end;
+                continue;
+            }
+
+            self.expand_into(&body, depth + 1, limit, result);
+            pos = args_end;
+        }
+    }
+}
+
+// how much expansion can add to math source of length `len`
+fn expansion_budget(len: usize) -> usize {
+    (len * MAX_EXPANSION_FACTOR).max(MIN_EXPANSION_LIMIT)
+}
+
+// `\name` or `\{`, returns command name and position after it
+fn read_command(tex: &str, pos: usize) -> Option<(&str, usize)> {
+    let rest = tex[pos..].strip_prefix('\\')?;
+    let len = rest.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(rest.len());
+
+    if len > 0 {
+        Some((&rest[..len], pos + 1 + len))
+    } else {
+        let ch = rest.chars().next()?;
+        Some((&rest[..ch.len_utf8()], pos + 1 + ch.len_utf8()))
+    }
+}
+
+// `{group}`, `\command` or a single character (skipping leading whitespace),
+// returns argument without braces and position after it
+fn read_argument(tex: &str, pos: usize) -> Option<(&str, usize)> {
+    let start = pos + tex[pos..].len() - tex[pos..].trim_start().len();
+    let rest = &tex[start..];
+
+    if rest.starts_with('{') {
+        let mut level = 0;
+        let mut chars = rest.char_indices();
+        while let Some((idx, ch)) = chars.next() {
+            match ch {
+                '\\' => { chars.next(); }
+                '{' => level += 1,
+                '}' => {
+                    level -= 1;
+                    if level == 0 {
+                        return Some((&rest[1..idx], start + idx + 1));
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    } else if rest.starts_with('\\') {
+        let (_, end) = read_command(tex, start)?;
+        Some((&tex[start..end], end))
+    } else {
+        let ch = rest.chars().next()?;
+        if ch == '}' { return None; }
+        Some((&rest[..ch.len_utf8()], start + ch.len_utf8()))
+    }
+}
+
+// `{\name}[2]{body}` or `\name{body}` after `\newcommand`
+fn parse_newcommand(tex: &str, pos: usize) -> Option<(&str, usize, &str, usize)> {
+    let (name, mut pos) = read_argument(tex, pos)?;
+    let (name, name_end) = read_command(name.trim(), 0)?;
+    if name_end != name.len() + 1 { return None; }
+
+    let mut args = 0;
+    let rest = tex[pos..].trim_start();
+    if let Some(rest) = rest.strip_prefix('[') {
+        let end = rest.find(']')?;
+        args = rest[..end].trim().parse().ok().filter(|n| *n <= 9)?;
+        pos = tex.len() - rest.len() + end + 1;
+    }
+
+    let (body, end) = read_argument(tex, pos)?;
+    Some((name, args, body, end))
+}
+
+// `\name#1#2{body}` after `\def`
+fn parse_def(tex: &str, pos: usize) -> Option<(&str, usize, &str, usize)> {
+    let start = pos + tex[pos..].len() - tex[pos..].trim_start().len();
+    let (name, mut pos) = read_command(tex, start)?;
+
+    let mut args = 0;
+    while let Some(rest) = tex[pos..].strip_prefix('#') {
+        let digit = rest.chars().next()?.to_digit(10)? as usize;
+        if digit != args + 1 { return None; }
+        args = digit;
+        pos += 2;
+    }
+
+    if !tex[pos..].starts_with('{') { return None; }
+    let (body, end) = read_argument(tex, pos)?;
+    Some((name, args, body, end))
+}
+
+// replace `#1`..`#9` with arguments, `##` with `#`
+fn substitute_args(body: &str, args: &[&str]) -> String {
+    let mut result = String::with_capacity(body.len());
+    let mut chars = body.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if ch != '#' {
+            result.push(ch);
+            continue;
+        }
+
+        match chars.peek().copied() {
+            Some('#') => {
+                chars.next();
+                result.push('#');
+            }
+            Some(digit @ '1'..='9') => {
+                chars.next();
+                let idx = digit as usize - '1' as usize;
+                result.push_str(args.get(idx).copied().unwrap_or_default());
+            }
+            _ => result.push('#'),
+        }
+    }
+
+    result
+}
+
+pub fn add(md: &mut MarkdownIt) {
+    md.add_rule::<MathMacrosRule>()
+        .after::<InlineParserRule>();
+}
+
+#[doc(hidden)]
+pub struct MathMacrosRule;
+impl CoreRule for MathMacrosRule {
+    fn run(root: &mut Node, _: &MarkdownIt) {
+        let mut macros = root.cast_mut::<Root>().unwrap().ext.remove::<MathMacros>().unwrap_or_default();
+
+        // definitions apply to the entire document, so collect them first
+        let mut source_len = 0;
+        root.walk_mut(|node, _| {
+            let content = if let Some(data) = node.cast::<MathBlock>() {
+                &data.content
+            } else if let Some(data) = node.cast::<InlineMath>() {
+                &data.content
+            } else {
+                return;
+            };
+
+            source_len += content.len();
+            let prepared = macros.collect(super::prepared_tex(node, content));
+            node.ext.insert(PreparedTex(prepared));
+        });
+
+        if !macros.macros.is_empty() {
+            // shared by all nodes, so many small nodes can't add up to a huge document
+            let mut budget = expansion_budget(source_len);
+            root.walk_mut(|node, _| {
+                if let Some(data) = node.ext.get_mut::<PreparedTex>() {
+                    data.0 = macros.expand_with_budget(&data.0, &mut budget);
+                }
+            });
+        }
+
+        root.cast_mut::<Root>().unwrap().ext.insert(macros);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("\\newcommand{\\R}{\\mathbb{R}} x \\in \\R", "x \\in \\mathbb{R}")]
+    #[case("\\newcommand\\R{\\mathbb{R}}\\R^n", "\\mathbb{R}^n")]
+    #[case("\\newcommand{\\pair}[2]{(#1, #2)} \\pair{a}{\\frac{1}{2}}", "(a, \\frac{1}{2})")]
+    #[case("\\newcommand{\\pair}[2]{(#1, #2)} \\pair ab", "(a, b)")]
+    #[case("\\def\\sq#1{#1^2} \\sq{x}", "x^2")]
+    #[case("\\def\\a{\\b}\\def\\b{c} \\a", "c")]
+    #[case("\\def\\a{\\a} \\a", "\\a")]
+    #[case("\\newcommand{\\pair}[2]{(#1, #2)} \\pair{a}", "\\pair{a}")]
+    #[case("\\Rx \\\\ \\{", "\\Rx \\\\ \\{")]
+    fn test_expand(#[case] input: &str, #[case] expected: &str) {
+        let mut macros = MathMacros::new();
+        let tex = macros.collect(input);
+        assert_eq!(macros.expand(&tex), expected);
+    }
+
+    #[test]
+    fn expansion_limit() {
+        let mut macros = MathMacros::new();
+        macros.define("a", 0, "xxxx");
+        for (name, prev) in ('b'..='z').zip('a'..='y') {
+            macros.define(&name.to_string(), 0, &format!("\\{prev}\\{prev}"));
+        }
+
+        let tex = macros.expand("\\z");
+        assert!(tex.len() <= MIN_EXPANSION_LIMIT + 1024);
+        assert!(tex.starts_with("xxxx"));
+        assert!(tex.contains('\\'));
+
+        let tex = macros.expand("\\d ".repeat(2000).trim());
+        assert!(tex.len() <= 2000 * 4 * MAX_EXPANSION_FACTOR);
+        assert!(!tex.contains('\\'));
+    }
+
+    #[test]
+    fn document_expansion_limit() {
+        let md = &mut MarkdownIt::new();
+        crate::plugins::cmark::add(md);
+        crate::plugins::jotdown::katex::add(md);
+
+        let mut src = String::from("$\\def\\a{xxxx}");
+        for (name, prev) in ('b'..='z').zip('a'..='y') {
+            src.push_str(&format!("\\def\\{name}{{\\{prev}\\{prev}}}"));
+        }
+        src.push_str("$\n\n");
+        src.push_str(&"$\\z$ ".repeat(2000));
+
+        let mut expanded = 0;
+        let mut nodes = 0;
+        md.parse(&src).walk(|node, _| {
+            if let Some(tex) = node.ext.get::<PreparedTex>() {
+                expanded += tex.0.len();
+                nodes += 1;
+            }
+        });
+        assert_eq!(nodes, 2001);
+        assert!(expanded <= src.len() * (MAX_EXPANSION_FACTOR + 1), "{expanded}");
+    }
+}
@@ -0,0 +1,130 @@
+//! Server-side TeX to MathML conversion (requires `latex2mathml` feature).
+//!
+//! By default math nodes are rendered as TeX source for KaTeX to typeset
+//! in the browser. Add [MathOutput::MathML] to render options to get MathML
+//! instead, which works without any javascript (static exports, RSS feeds):
+//!
+//! ```rust
+//! use markdown_it::parser::extset::RenderOptionsExtSet;
+//! use markdown_it::plugins::jotdown::katex::mathml::MathOutput;
+//!
+//! let md = &mut markdown_it::MarkdownIt::new();
+//! markdown_it::plugins::cmark::add(md);
+//! markdown_it::plugins::jotdown::katex::add(md);
+//!
+//! let mut options = RenderOptionsExtSet::new();
+//! options.insert(MathOutput::MathML);
+//! let html = md.parse("$x^2$").render(&options);
+//! assert_eq!(html.trim(), "<p><span class=\"math inline\">\
+//!     <math xmlns=\"http://www.w3.org/1998/Math/MathML\" display=\"inline\">\
+//!     <msup><mi>x</mi><mn>2</mn></msup></math></span></p>");
+//! ```
+//!
+//! If TeX can't be converted (unsupported command or syntax error), the node
+//! is rendered as usual with an extra `math-error` class.
+use latex2mathml::{latex_to_mathml, DisplayStyle};
+use crate::parser::extset::{RenderOptionsExt, RenderOptionsExtSet};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+/// Render option that selects math output mode.
+pub enum MathOutput {
+    #[default]
+    /// TeX source, typeset in the browser.
+    Tex,
+    /// MathML converted on the server.
+    MathML,
+}
+
+impl RenderOptionsExt for MathOutput {}
+
+/// Returns true if user requested MathML output in render options.
+pub fn is_enabled(options: &RenderOptionsExtSet) -> bool {
+    options.get::<MathOutput>() == Some(&MathOutput::MathML)
+}
+
+/// Convert TeX into `<math>` element, returns `None` if TeX isn't supported.
+pub fn tex_to_mathml(tex: &str, display: bool) -> Option<String> {
+    let style = if display { DisplayStyle::Block } else { DisplayStyle::Inline };
+
+    // latex2mathml panics on some malformed input (e.g. `\middle\frac`),
+    // and this is user-controlled text, so we treat it as any other error
+    let result = std::panic::catch_unwind(|| latex_to_mathml(tex, style)).ok()?.ok()?;
+
+    // unknown commands are not reported as errors, they are rendered inline instead
+    if result.contains("[PARSE ERROR:") { return None; }
+
+    Some(escape_text(&result))
+}
+
+// latex2mathml doesn't escape text content, so `a<b` gets rendered as `<mo><</mo>`;
+// tags it generates always start with a lowercase letter or slash, anything else is text
+fn escape_text(mathml: &str) -> String {
+    let mut result = String::with_capacity(mathml.len());
+    let mut chars = mathml.chars().peekable();
+    let mut in_tag = false;
+
+    while let Some(ch) = chars.next() {
+        if in_tag {
+            result.push(ch);
+            if ch == '>' { in_tag = false; }
+            continue;
+        }
+
+        match ch {
+            '<' if matches!(chars.peek(), Some('a'..='z' | '/')) => {
+                in_tag = true;
+                result.push(ch);
+            }
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '&' => result.push_str("&amp;"),
+            _ => result.push(ch),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_operators() {
+        let html = tex_to_mathml("a<b", false).unwrap();
+        assert!(html.contains("<mo>&lt;</mo>"));
+    }
+
+    #[test]
+    fn unsupported_tex() {
+        assert_eq!(tex_to_mathml("\\foo", false), None);
+        assert_eq!(tex_to_mathml("\\frac{1}{2", true), None);
+        assert_eq!(tex_to_mathml("\\middle\\frac{1}{2}", true), None);
+    }
+
+    #[test]
+    fn output_option() {
+        let mut options = RenderOptionsExtSet::new();
+        assert!(!is_enabled(&options));
+        options.insert(MathOutput::Tex);
+        assert!(!is_enabled(&options));
+        options.insert(MathOutput::MathML);
+        assert!(is_enabled(&options));
+    }
+
+    #[test]
+    fn cached_output() {
+        use crate::parser::renderer::cache::RenderCache;
+
+        let md = &mut crate::MarkdownIt::new();
+        crate::plugins::cmark::add(md);
+        crate::plugins::jotdown::katex::add(md);
+
+        let mut options = RenderOptionsExtSet::new();
+        options.insert(MathOutput::MathML);
+        let cache = RenderCache::new();
+        let ast = md.parse("$x$ $x$ $$x$$ $\\foo$");
+        assert_eq!(ast.render_cached(&options, &cache), ast.render(&options));
+        assert_eq!(cache.len(), 2);
+    }
+}
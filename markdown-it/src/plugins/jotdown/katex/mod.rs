@@ -0,0 +1,68 @@
+//! TeX math, rendered on the client side by KaTeX (or MathJax).
+//!
+//!  - display math, `$$ ... $$` and `\[ ... \]` on their own lines ([block])
+//!  - inline math, `$ ... $`, `$$ ... $$` and `\( ... \)` inside paragraphs ([inline])
+//!  - document-wide `\newcommand` definitions ([macros])
+//!  - equation numbering with `\label` and `\eqref` ([equations])
+//!
+//! With `latex2mathml` feature enabled, math can be converted to MathML on the
+//! server side instead, see [mathml](self::mathml) module.
+//!
+//! ```rust
+//! let md = &mut markdown_it::MarkdownIt::new();
+//! markdown_it::plugins::cmark::add(md);
+//! markdown_it::plugins::jotdown::katex::add(md);
+//!
+//! let html = md.parse("area is $\\pi r^2$").render(&Default::default());
+//! assert_eq!(html.trim(), r#"<p>area is <span class="math inline">\(\pi r^2\)</span></p>"#);
+//! ```
+
+use crate::parser::extset::{NodeExt, RenderOptionsExtSet};
+use crate::{MarkdownIt, Node, Renderer};
+
+pub mod block;
+pub mod equations;
+pub mod inline;
+pub mod macros;
+#[cfg(feature = "latex2mathml")]
+pub mod mathml;
+
+pub fn add(md: &mut MarkdownIt) {
+    block::add(md);
+    inline::add(md);
+    macros::add(md);
+    equations::add(md);
+}
+
+#[derive(Debug)]
+/// TeX source of a math node prepared for rendering by core rules
+/// (macros expanded, labels removed). Math nodes render their `content`
+/// as is if this extension is missing.
+pub struct PreparedTex(pub String);
+impl NodeExt for PreparedTex {}
+
+/// Returns TeX that should be rendered for math node.
+pub fn prepared_tex<'a>(node: &'a Node, content: &'a str) -> &'a str {
+    node.ext.get::<PreparedTex>().map(|tex| tex.0.as_str()).unwrap_or(content)
+}
+
+// MathML for the formula if it's requested in render options:
+// `None` - not requested, `Some(None)` - TeX isn't supported
+fn mathml_output(tex: &str, display: bool, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) -> Option<Option<String>> {
+    #[cfg(feature = "latex2mathml")]
+    if mathml::is_enabled(options) {
+        use crate::parser::renderer::cache::{CacheKey, RenderCache};
+
+        let convert = || mathml::tex_to_mathml(tex, display);
+        return Some(match RenderCache::from_renderer(fmt) {
+            Some(cache) => {
+                let key = CacheKey::new(&["mathml", if display { "display" } else { "inline" }, tex]);
+                cache.get_or_insert_with(key, convert)
+            }
+            None => convert(),
+        });
+    }
+
+    let _ = (tex, display, fmt, options);
+    None
+}
@@ -1,7 +1,9 @@
 use crate::MarkdownIt;
 
+pub mod diagrams;
 pub mod katex;
 
 pub fn add(md: &mut MarkdownIt) {
+    diagrams::add(md);
     katex::add(md);
 }
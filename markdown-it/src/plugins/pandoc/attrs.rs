@@ -0,0 +1,473 @@
+//! Attribute blocks, `{#id .class key="value"}`
+//!
+//! Pandoc-style attributes are recognized in following places:
+//!  - after fence info string, ` ```rust {#main .numberLines} ` or ` ```{.rust} `
+//!  - at the end of ATX and setext headings, `# Heading {#custom-id}`
+//!  - right after links and images, `[link](url){target="_blank"}`
+//!  - after bracketed spans, `[text]{.smallcaps}`
+//!
+//! Parsed attributes are added to [Node::attrs], so they are picked up
+//! by the HTML renderer.
+//!
+//! ```rust
+//! let md = &mut markdown_it::MarkdownIt::new();
+//! markdown_it::plugins::cmark::add(md);
+//! markdown_it::plugins::pandoc::attrs::add(md);
+//!
+//! let html = md.parse("# Intro {#start .title}").render(&Default::default());
+//! assert_eq!(html, "<h1 id=\"start\" class=\"title\">Intro</h1>\n");
+//! ```
+//!
+//! Only `#id`, `.class` and names listed in [AttributesOptions] are added,
+//! other attributes are dropped. Event handlers (`on*`), `style` and attributes
+//! set by the node itself (`href`, `src`, `alt`, `id`, `class`) are never allowed,
+//! values of url attributes (like `cite`) are checked by link formatter.
+//!
+//! ```rust
+//! let md = &mut markdown_it::MarkdownIt::new();
+//! markdown_it::plugins::cmark::add(md);
+//! markdown_it::plugins::pandoc::attrs::add(md);
+//!
+//! let html = md.parse("[a](/x){href=\"javascript:alert(1)\" onclick=\"alert(1)\" lang=en}").render(&Default::default());
+//! assert_eq!(html, "<p><a lang=\"en\" href=\"/x\">a</a></p>\n");
+//! ```
+
+use once_cell::sync::Lazy;
+
+use crate::common::sourcemap::SourcePos;
+use crate::generics::block::fence_handler::FenceHandlerRule;
+use crate::generics::inline::full_link::{self, LinkScanner};
+use crate::parser::block::builtin::BlockParserRule;
+use crate::parser::core::CoreRule;
+use crate::parser::extset::{MarkdownItExt, RenderOptionsExtSet};
+use crate::parser::inline::builtin::InlineParserRule;
+use crate::parser::inline::{InlineRoot, InlineRule, InlineState};
+use crate::plugins::cmark::block::fence::CodeFence;
+use crate::plugins::cmark::block::heading::ATXHeading;
+use crate::plugins::cmark::block::lheading::SetextHeader;
+use crate::plugins::cmark::inline::image::Image;
+use crate::plugins::cmark::inline::link::Link;
+use crate::{MarkdownIt, Node, NodeValue, Renderer};
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+/// Parsed attribute block.
+pub struct Attributes {
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+    pub pairs: Vec<(String, String)>,
+}
+
+impl Attributes {
+    /// Convert to the format used in [Node::attrs], dropping attributes
+    /// that are not allowed by [AttributesOptions] of the parser.
+    pub fn to_node_attrs(&self, md: &MarkdownIt) -> Vec<(&'static str, String)> {
+        let options = md.ext.get::<AttributesOptions>().unwrap_or(&DEFAULT_OPTIONS);
+
+        let mut result = Vec::new();
+        if let Some(id) = &self.id {
+            result.push(("id", id.clone()));
+        }
+        for class in self.classes.iter() {
+            result.push(("class", class.clone()));
+        }
+        for (key, value) in self.pairs.iter() {
+            let Some(name) = options.allowed_name(key) else { continue };
+            if URL_ATTRIBUTES.contains(&name) {
+                if md.link_formatter.validate_link(value).is_none() { continue; }
+                result.push((name, md.link_formatter.normalize_link(value)));
+            } else {
+                result.push((name, value.clone()));
+            }
+        }
+        result
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Attribute names accepted in attribute blocks (besides `#id` and `.class`).
+pub struct AttributesOptions {
+    /// Allowed names, matched case-insensitively. Names containing
+    /// anything other than ASCII letters, digits and `-`, names
+    /// starting with `on` and names in [RESERVED_ATTRIBUTES] are ignored.
+    pub allowed: Vec<&'static str>,
+}
+
+impl AttributesOptions {
+    /// Returns allowed name matching `key`.
+    pub fn allowed_name(&self, key: &str) -> Option<&'static str> {
+        let name = self.allowed.iter().find(|name| name.eq_ignore_ascii_case(key)).copied()?;
+
+        let safe = name.starts_with(|c: char| c.is_ascii_alphabetic()) &&
+            name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+        let event = name.get(..2).is_some_and(|prefix| prefix.eq_ignore_ascii_case("on"));
+        let reserved = RESERVED_ATTRIBUTES.iter().any(|reserved| reserved.eq_ignore_ascii_case(name));
+
+        (safe && !event && !reserved).then_some(name)
+    }
+}
+
+impl Default for AttributesOptions {
+    fn default() -> Self {
+        Self { allowed: DEFAULT_ALLOWED.to_vec() }
+    }
+}
+
+impl MarkdownItExt for AttributesOptions {}
+
+static DEFAULT_OPTIONS: Lazy<AttributesOptions> = Lazy::new(AttributesOptions::default);
+
+/// Names allowed by default.
+pub const DEFAULT_ALLOWED: &[&str] = &[
+    "aria-describedby", "aria-hidden", "aria-label", "cite", "dir", "height",
+    "hreflang", "lang", "loading", "rel", "role", "target", "translate", "width",
+];
+
+/// Names that are never allowed, mostly because nodes set them themselves
+/// (`#id` and `.class` should be used instead of `id` and `class` keys).
+pub const RESERVED_ATTRIBUTES: &[&str] = &["alt", "class", "href", "id", "src", "srcset", "style"];
+
+/// Names with url values, they are checked by [LinkFormatter::validate_link](crate::parser::linkfmt::LinkFormatter::validate_link).
+pub const URL_ATTRIBUTES: &[&str] = &[
+    "action", "background", "cite", "data", "formaction", "longdesc", "ping", "poster", "xlink:href",
+];
+
+/// Parse attribute block at the start of `src`, returns attributes
+/// and length of the block (including braces).
+pub fn parse_attributes(src: &str) -> Option<(Attributes, usize)> {
+    let mut result = Attributes::default();
+    let mut pos = src.strip_prefix('{').map(|_| 1)?;
+
+    loop {
+        pos += src[pos..].len() - src[pos..].trim_start().len();
+        let rest = &src[pos..];
+
+        if rest.starts_with('}') {
+            break;
+        } else if let Some(rest) = rest.strip_prefix('#') {
+            let len = word_len(rest);
+            if len == 0 { return None; }
+            result.id = Some(rest[..len].to_owned());
+            pos += 1 + len;
+        } else if let Some(rest) = rest.strip_prefix('.') {
+            let len = word_len(rest);
+            if len == 0 { return None; }
+            result.classes.push(rest[..len].to_owned());
+            pos += 1 + len;
+        } else {
+            let key_len = rest.find(|c: char| !is_name_char(c)).unwrap_or(rest.len());
+            if key_len == 0 || !rest[..1].chars().all(|c| c.is_ascii_alphabetic() || c == '_' || c == ':') {
+                return None;
+            }
+            let key = &rest[..key_len];
+            let rest = rest[key_len..].strip_prefix('=')?;
+            let (value, len) = parse_value(rest)?;
+            result.pairs.push((key.to_owned(), value));
+            pos += key_len + 1 + len;
+        }
+
+        // attributes must be separated by whitespace
+        if !src[pos..].starts_with(|c: char| c.is_whitespace() || c == '}') {
+            return None;
+        }
+    }
+
+    Some((result, pos + 1))
+}
+
+fn word_len(src: &str) -> usize {
+    src.find(|c: char| c.is_whitespace() || c == '}' || c == '{').unwrap_or(src.len())
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | ':')
+}
+
+// `"quoted \" value"`, `'quoted'` or `bare`
+fn parse_value(src: &str) -> Option<(String, usize)> {
+    let quote = src.chars().next()?;
+    if quote != '"' && quote != '\'' {
+        let len = word_len(src);
+        if len == 0 { return None; }
+        return Some((src[..len].to_owned(), len));
+    }
+
+    let mut value = String::new();
+    let mut chars = src.char_indices().skip(1);
+    while let Some((idx, ch)) = chars.next() {
+        match ch {
+            '\\' => {
+                let (_, next) = chars.next()?;
+                if next != quote && next != '\\' { value.push('\\'); }
+                value.push(next);
+            }
+            _ if ch == quote => return Some((value, idx + 1)),
+            _ => value.push(ch),
+        }
+    }
+    None
+}
+
+// find attribute block at the end of `src`, returns its start position
+fn find_trailing_attributes(src: &str) -> Option<(Attributes, usize)> {
+    let trimmed = src.trim_end();
+    if !trimmed.ends_with('}') { return None; }
+
+    let mut start = trimmed.len();
+    while let Some(idx) = trimmed[..start].rfind('{') {
+        if let Some((attrs, len)) = parse_attributes(&trimmed[idx..]) {
+            if idx + len == trimmed.len() {
+                return Some((attrs, idx));
+            }
+        }
+        start = idx;
+    }
+    None
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+/// Bracketed span, `[text]{.class}`.
+pub struct BracketedSpan;
+
+impl NodeValue for BracketedSpan {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
+        fmt.open("span", &node.attrs);
+        fmt.contents(&node.children, options);
+        fmt.close("span");
+    }
+}
+
+pub fn add(md: &mut MarkdownIt) {
+    add_with_options(md, AttributesOptions::default());
+}
+
+pub fn add_with_options(md: &mut MarkdownIt, options: AttributesOptions) {
+    md.ext.insert(options);
+    md.add_rule::<BlockAttributesRule>()
+        .after::<BlockParserRule>()
+        .before::<FenceHandlerRule>()
+        .before::<InlineParserRule>();
+
+    md.inline.add_rule::<BracketedSpanScanner>()
+        .before::<LinkScanner<false>>();
+    md.inline.add_rule::<LinkAttributesScanner>();
+}
+
+#[doc(hidden)]
+pub struct BlockAttributesRule;
+impl CoreRule for BlockAttributesRule {
+    fn run(root: &mut Node, md: &MarkdownIt) {
+        root.walk_mut(|node, _| {
+            if let Some(data) = node.cast_mut::<CodeFence>() {
+                let Some((idx, (mut attrs, len))) = data.info.match_indices('{')
+                    .find_map(|(idx, _)| Some((idx, parse_attributes(&data.info[idx..])?))) else { return };
+
+                let mut info = data.info[..idx].trim_end().to_owned();
+                // ```{.rust} - first class is language
+                if info.is_empty() && !attrs.classes.is_empty() {
+                    info = attrs.classes.remove(0);
+                }
+                let rest = data.info[idx + len..].trim();
+                if !rest.is_empty() {
+                    info.push(' ');
+                    info.push_str(rest);
+                }
+
+                data.info = info;
+                node.attrs.extend(attrs.to_node_attrs(md));
+            } else if node.is::<ATXHeading>() || node.is::<SetextHeader>() {
+                let Some(inline) = node.children.first_mut() else { return };
+                let Some(data) = inline.cast_mut::<InlineRoot>() else { return };
+                let Some((attrs, idx)) = find_trailing_attributes(&data.content) else { return };
+
+                data.content.truncate(data.content[..idx].trim_end().len());
+                node.attrs.extend(attrs.to_node_attrs(md));
+            }
+        });
+    }
+}
+
+#[doc(hidden)]
+pub struct BracketedSpanScanner;
+impl InlineRule for BracketedSpanScanner {
+    const MARKER: char = '[';
+
+    fn check(state: &mut InlineState) -> Option<usize> {
+        let (_, _, end) = Self::parse(state)?;
+        Some(end - state.pos)
+    }
+
+    fn run(state: &mut InlineState) -> Option<(Node, usize)> {
+        let (attrs, label_end, end) = Self::parse(state)?;
+
+        let mut node = Node::new(BracketedSpan);
+        node.attrs = attrs.to_node_attrs(state.md);
+
+        let start = state.pos;
+        let max = state.pos_max;
+        let old_node = std::mem::replace(&mut state.node, node);
+        state.pos = start + 1;
+        state.pos_max = label_end;
+        state.md.inline.tokenize(state);
+        state.pos = start;
+        state.pos_max = max;
+        let node = std::mem::replace(&mut state.node, old_node);
+
+        Some((node, end - start))
+    }
+}
+
+impl BracketedSpanScanner {
+    fn parse(state: &mut InlineState) -> Option<(Attributes, usize, usize)> {
+        if !state.src[state.pos..state.pos_max].starts_with('[') { return None; }
+        let label_end = full_link::parse_link_label(state, state.pos, true)?;
+        let (attrs, len) = parse_attributes(&state.src[label_end + 1..state.pos_max])?;
+        Some((attrs, label_end, label_end + 1 + len))
+    }
+}
+
+#[doc(hidden)]
+pub struct LinkAttributesScanner;
+impl InlineRule for LinkAttributesScanner {
+    const MARKER: char = '{';
+
+    fn check(state: &mut InlineState) -> Option<usize> {
+        Self::parse(state).map(|(_, len)| len)
+    }
+
+    fn run(state: &mut InlineState) -> Option<(Node, usize)> {
+        let (attrs, len) = Self::parse(state)?;
+        let end = state.get_map(state.pos + len, state.pos + len)?.get_byte_offsets().0;
+
+        let attrs = attrs.to_node_attrs(state.md);
+        let last = state.node.children.last_mut()?;
+        last.attrs.extend(attrs);
+        if let Some(map) = last.srcmap {
+            last.srcmap = Some(SourcePos::new(map.get_byte_offsets().0, end));
+        }
+
+        // attributes are consumed without producing a node
+        Some((Node::default(), len))
+    }
+}
+
+impl LinkAttributesScanner {
+    fn parse(state: &mut InlineState) -> Option<(Attributes, usize)> {
+        // attributes must immediately follow a link or an image
+        let last = state.node.children.last()?;
+        if !last.is::<Link>() && !last.is::<Image>() { return None; }
+        let last_end = last.srcmap?.get_byte_offsets().1;
+        if state.get_map(state.pos, state.pos)?.get_byte_offsets().0 != last_end { return None; }
+
+        parse_attributes(&state.src[state.pos..state.pos_max])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::plugins::cmark;
+
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let (attrs, len) = parse_attributes(r#"{#id .a .b key=value data-x="a \"b\"" k='v'} rest"#).unwrap();
+        assert_eq!(len, 44);
+        assert_eq!(attrs, Attributes {
+            id: Some("id".into()),
+            classes: vec!["a".into(), "b".into()],
+            pairs: vec![
+                ("key".into(), "value".into()),
+                ("data-x".into(), "a \"b\"".into()),
+                ("k".into(), "v".into()),
+            ],
+        });
+
+        assert!(parse_attributes("{3,5-7}").is_none());
+        assert!(parse_attributes("{.a").is_none());
+        assert!(parse_attributes("{# .a}").is_none());
+        assert!(parse_attributes("{.a.b}").is_some());
+        assert!(parse_attributes("{k=\"v\".a}").is_none());
+        assert_eq!(parse_attributes("{}"), Some((Attributes::default(), 2)));
+    }
+
+    #[rstest]
+    #[case("# Heading {#id .a}", "<h1 id=\"id\" class=\"a\">Heading</h1>\n")]
+    #[case("Heading {lang=en}\n===", "<h1 lang=\"en\">Heading</h1>\n")]
+    #[case("# Heading {not attrs}", "<h1>Heading {not attrs}</h1>\n")]
+    #[case("```rust {#main .big}\nx\n```", "<pre><code id=\"main\" class=\"big language-rust\">x\n</code></pre>\n")]
+    #[case("```{.rust}\nx\n```", "<pre><code class=\"language-rust\">x\n</code></pre>\n")]
+    #[case("```rust {1} {.a}\nx\n```", "<pre><code class=\"a language-rust\"><span class=\"line highlighted\" data-line=\"1\">x\n</span></code></pre>\n")]
+    #[case("[a](/url){target=_blank}", "<p><a target=\"_blank\" href=\"/url\">a</a></p>\n")]
+    #[case("![a](/img.png){width=50%}", "<p><img width=\"50%\" src=\"/img.png\" alt=\"a\"></p>\n")]
+    #[case("[a](/url) {.x}", "<p><a href=\"/url\">a</a> {.x}</p>\n")]
+    #[case("[*span*]{.smallcaps}", "<p><span class=\"smallcaps\"><em>span</em></span></p>\n")]
+    #[case("[span] {.x}", "<p>[span] {.x}</p>\n")]
+    #[case("text {.x}", "<p>text {.x}</p>\n")]
+    fn render(#[case] input: &str, #[case] expected: &str) {
+        let md = &mut MarkdownIt::new();
+        cmark::add(md);
+        add(md);
+        assert_eq!(md.parse(input).render(&Default::default()), expected);
+    }
+
+    #[rstest]
+    // set by the node itself
+    #[case("[a](/x){href=\"javascript:alert(1)\"}", "<p><a href=\"/x\">a</a></p>\n")]
+    #[case("![a](/x.png){src=/y.png alt=b}", "<p><img src=\"/x.png\" alt=\"a\"></p>\n")]
+    #[case("# H {id=x class=y}", "<h1>H</h1>\n")]
+    // event handlers, styles
+    #[case("[a](/x){onclick=\"alert(1)\"}", "<p><a href=\"/x\">a</a></p>\n")]
+    #[case("# H {onmouseover=\"alert(1)\" OnFocus=x}", "<h1>H</h1>\n")]
+    #[case("[a]{style=\"position:fixed\"}", "<p><span>a</span></p>\n")]
+    // not in the allowlist
+    #[case("[a]{data-x=1 foo=bar}", "<p><span>a</span></p>\n")]
+    #[case("[a]{LANG=en}", "<p><span lang=\"en\">a</span></p>\n")]
+    // urls
+    #[case("[a]{cite=\"javascript:alert(1)\"}", "<p><span>a</span></p>\n")]
+    #[case("[a]{cite=\"https://example.org/a b\"}", "<p><span cite=\"https://example.org/a%20b\">a</span></p>\n")]
+    // names must be made of safe characters
+    #[case("[a]{x\"=1}", "<p>[a]{x&quot;=1}</p>\n")]
+    #[case("[a]{x<y=1}", "<p>[a]{x&lt;y=1}</p>\n")]
+    #[case("[a]{x/y=1}", "<p>[a]{x/y=1}</p>\n")]
+    fn unsafe_attributes(#[case] input: &str, #[case] expected: &str) {
+        let md = &mut MarkdownIt::new();
+        cmark::add(md);
+        add(md);
+        assert_eq!(md.parse(input).render(&Default::default()), expected);
+    }
+
+    #[test]
+    fn allowed_names() {
+        let options = AttributesOptions {
+            allowed: vec!["data-x", "onclick", "style", "Href", "x y", "x:y", "poster"],
+        };
+        assert_eq!(options.allowed_name("DATA-X"), Some("data-x"));
+        assert_eq!(options.allowed_name("onclick"), None);
+        assert_eq!(options.allowed_name("style"), None);
+        assert_eq!(options.allowed_name("href"), None);
+        assert_eq!(options.allowed_name("x y"), None);
+        assert_eq!(options.allowed_name("x:y"), None);
+        assert_eq!(options.allowed_name("lang"), None);
+
+        let md = &mut MarkdownIt::new();
+        cmark::add(md);
+        add_with_options(md, options);
+        assert_eq!(md.parse("![a](/x.png){data-x=1 lang=en poster=\"vbscript:x\"}").render(&Default::default()),
+            "<p><img data-x=\"1\" src=\"/x.png\" alt=\"a\"></p>\n");
+    }
+
+    #[test]
+    fn srcmap() {
+        let md = &mut MarkdownIt::new();
+        cmark::add(md);
+        add(md);
+
+        let ast = md.parse("x [a](/url){.b} y");
+        let link = &ast.children[0].children[1];
+        assert!(link.is::<Link>());
+        assert_eq!(link.srcmap.unwrap().get_byte_offsets(), (2, 15));
+    }
+}
@@ -11,15 +11,14 @@
 //! ```rust
 //! let md = &mut markdown_it::MarkdownIt::new();
 //! markdown_it::plugins::cmark::add(md);
-//! markdown_it_deflist::add(md);
+//! markdown_it::plugins::pandoc::deflist::add(md);
 //! assert_eq!(
-//!     md.parse("term\n: definition").render(),
+//!     md.parse("term\n: definition").render(&Default::default()),
 //!     "<dl>\n<dt>term</dt>\n<dd>definition</dd>\n</dl>\n"
 //! );
 //! ```
 
-use std::collections::HashMap;
-
+use crate::parser::extset::RenderOptionsExtSet;
 use crate::{
     parser::{
         block::{BlockRule, BlockState},
@@ -38,9 +37,10 @@ pub fn add(md: &mut MarkdownIt) {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub struct DefinitionList;
 impl NodeValue for DefinitionList {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &HashMap<String, String>) {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
         fmt.cr();
         fmt.open("dl", &node.attrs);
         fmt.cr();
@@ -52,9 +52,10 @@ impl NodeValue for DefinitionList {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub struct DefinitionTerm;
 impl NodeValue for DefinitionTerm {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &HashMap<String, String>) {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
         fmt.cr();
         fmt.open("dt", &node.attrs);
         fmt.contents(&node.children, options);
@@ -64,9 +65,10 @@ impl NodeValue for DefinitionTerm {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub struct DefinitionDescription;
 impl NodeValue for DefinitionDescription {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &HashMap<String, String>) {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
         fmt.cr();
         fmt.open("dd", &node.attrs);
         fmt.contents(&node.children, options);
@@ -296,8 +298,6 @@ fn mark_tight_paragraphs(nodes: &mut Vec<Node>) {
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
-
     use crate::plugins::cmark;
 
     use super::*;
@@ -311,7 +311,7 @@ mod tests {
         println!("test\n  : foo\n      : bar\n");
         let ast = md.parse("test\n  : foo\n     : bar\n");
         // println!("{:?}", ast);
-        println!("{}", ast.render(&HashMap::new()));
+        println!("{}", ast.render(&Default::default()));
         // panic!("TODO")
     }
 }
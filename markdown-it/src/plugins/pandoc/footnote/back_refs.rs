@@ -4,24 +4,25 @@
 //! ```rust
 //! let parser = &mut markdown_it::MarkdownIt::new();
 //! markdown_it::plugins::cmark::add(parser);
-//! markdown_it_footnote::references::add(parser);
-//! markdown_it_footnote::definitions::add(parser);
-//! markdown_it_footnote::back_refs::add(parser);
+//! markdown_it::plugins::pandoc::footnote::references::add(parser);
+//! markdown_it::plugins::pandoc::footnote::definitions::add(parser);
+//! markdown_it::plugins::pandoc::footnote::back_refs::add(parser);
 //! let root = parser.parse("[^label]\n\n[^label]: This is a footnote");
 //! let mut names = vec![];
 //! root.walk(|node,_| { names.push(node.name()); });
 //! assert_eq!(names, vec![
 //! "markdown_it::parser::core::root::Root",
 //! "markdown_it::plugins::cmark::block::paragraph::Paragraph",
-//! "markdown_it_footnote::references::FootnoteReference",
-//! "markdown_it_footnote::definitions::FootnoteDefinition",
+//! "markdown_it::plugins::pandoc::footnote::references::FootnoteReference",
+//! "markdown_it::plugins::pandoc::footnote::definitions::FootnoteDefinition",
 //! "markdown_it::plugins::cmark::block::paragraph::Paragraph",
 //! "markdown_it::parser::inline::builtin::skip_text::Text",
-//! "markdown_it_footnote::back_refs::FootnoteRefAnchor",
+//! "markdown_it::plugins::pandoc::footnote::back_refs::FootnoteRefAnchor",
 //! ]);
 //! ```
-use std::collections::HashMap;
 
+use crate::parser::extset::RenderOptionsExtSet;
+use crate::parser::inline::builtin::InlineParserRule;
 use crate::{
     parser::core::{CoreRule, Root},
     plugins::cmark::block::paragraph::Paragraph,
@@ -31,16 +32,23 @@ use crate::{
 use super::{definitions::FootnoteDefinition, FootnoteMap};
 
 pub fn add(md: &mut MarkdownIt) {
-    // insert this rule into parser
-    md.add_rule::<FootnoteBackrefRule>();
+    // insert this rule into parser, references must be parsed already
+    md.add_rule::<FootnoteBackrefRule>()
+        .after::<InlineParserRule>();
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FootnoteRefAnchor {
     pub ref_ids: Vec<usize>,
 }
 impl NodeValue for FootnoteRefAnchor {
-    fn render(&self, _: &Node, fmt: &mut dyn crate::Renderer, _options: &HashMap<String, String>) {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, _: &Node, fmt: &mut dyn crate::Renderer, _options: &RenderOptionsExtSet) {
         for ref_id in self.ref_ids.iter() {
             fmt.text(" ");
             fmt.open(
@@ -62,14 +70,9 @@ struct FootnoteBackrefRule;
 
 impl CoreRule for FootnoteBackrefRule {
     fn run(root: &mut Node, _: &MarkdownIt) {
-        // TODO this seems very cumbersome
-        // but it is also how the markdown_it::InlineParserRule works
+        // map is taken out while the tree is modified, other extensions stay in place
         let data = root.cast_mut::<Root>().unwrap();
-        let root_ext = std::mem::take(&mut data.ext);
-        let map = match root_ext.get::<FootnoteMap>() {
-            Some(map) => map,
-            None => return,
-        };
+        let Some(map) = data.ext.remove::<FootnoteMap>() else { return };
 
         // walk through the AST and add backref anchors to footnote definitions
         root.walk_mut(|node, _| {
@@ -100,7 +103,6 @@ impl CoreRule for FootnoteBackrefRule {
             }
         });
 
-        let data = root.cast_mut::<Root>().unwrap();
-        data.ext = root_ext;
+        root.cast_mut::<Root>().unwrap().ext.insert(map);
     }
 }
@@ -5,27 +5,28 @@
 //! ```rust
 //! let parser = &mut markdown_it::MarkdownIt::new();
 //! markdown_it::plugins::cmark::add(parser);
-//! markdown_it_footnote::references::add(parser);
-//! markdown_it_footnote::definitions::add(parser);
-//! markdown_it_footnote::collect::add(parser);
+//! markdown_it::plugins::pandoc::footnote::references::add(parser);
+//! markdown_it::plugins::pandoc::footnote::definitions::add(parser);
+//! markdown_it::plugins::pandoc::footnote::collect::add(parser);
 //! let root = parser.parse("[^label]\n\n[^label]: This is a footnote\n\n> quote");
 //! let mut names = vec![];
 //! root.walk(|node,_| { names.push(node.name()); });
 //! assert_eq!(names, vec![
 //! "markdown_it::parser::core::root::Root",
 //! "markdown_it::plugins::cmark::block::paragraph::Paragraph",
-//! "markdown_it_footnote::references::FootnoteReference",
+//! "markdown_it::plugins::pandoc::footnote::references::FootnoteReference",
 //! "markdown_it::plugins::cmark::block::blockquote::Blockquote",
 //! "markdown_it::plugins::cmark::block::paragraph::Paragraph",
 //! "markdown_it::parser::inline::builtin::skip_text::Text",
-//! "markdown_it_footnote::collect::FootnotesContainerNode",
-//! "markdown_it_footnote::definitions::FootnoteDefinition",
+//! "markdown_it::plugins::pandoc::footnote::collect::FootnotesContainerNode",
+//! "markdown_it::plugins::pandoc::footnote::definitions::FootnoteDefinition",
 //! "markdown_it::plugins::cmark::block::paragraph::Paragraph",
 //! "markdown_it::parser::inline::builtin::skip_text::Text",
 //! ]);
 //! ```
-use std::collections::HashMap;
 
+use crate::parser::extset::RenderOptionsExtSet;
+use crate::parser::inline::builtin::InlineParserRule;
 use crate::{
     parser::core::{CoreRule, Root},
     plugins::cmark::block::paragraph::Paragraph,
@@ -35,8 +36,9 @@ use crate::{
 use super::{definitions::FootnoteDefinition, FootnoteMap};
 
 pub fn add(md: &mut MarkdownIt) {
-    // insert this rule into parser
-    md.add_rule::<FootnoteCollectRule>();
+    // insert this rule into parser, references must be parsed already
+    md.add_rule::<FootnoteCollectRule>()
+        .after::<InlineParserRule>();
 }
 
 #[derive(Debug)]
@@ -44,13 +46,14 @@ struct PlaceholderNode;
 impl NodeValue for PlaceholderNode {}
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub struct FootnotesContainerNode;
 impl NodeValue for FootnotesContainerNode {
     fn render(
         &self,
         node: &Node,
         fmt: &mut dyn crate::Renderer,
-        options: &HashMap<String, String>,
+        options: &RenderOptionsExtSet,
     ) {
         let mut attrs = node.attrs.clone();
         attrs.push(("class", "footnotes".into()));
@@ -80,14 +83,9 @@ impl CoreRule for FootnoteCollectRule {
     // contents as you like.
     //
     fn run(root: &mut Node, _: &MarkdownIt) {
-        // TODO this seems very cumbersome
-        // but it is also how the markdown_it::InlineParserRule works
+        // map is taken out while the tree is modified, other extensions stay in place
         let data = root.cast_mut::<Root>().unwrap();
-        let root_ext = std::mem::take(&mut data.ext);
-        let map = match root_ext.get::<FootnoteMap>() {
-            Some(map) => map,
-            None => return,
-        };
+        let Some(map) = data.ext.remove::<FootnoteMap>() else { return };
 
         // walk through the AST and extract all footnote definitions
         let mut defs = vec![];
@@ -128,6 +126,8 @@ impl CoreRule for FootnoteCollectRule {
             }
             node.children.retain(|child| !child.is::<PlaceholderNode>());
         });
+        root.cast_mut::<Root>().unwrap().ext.insert(map);
+
         if defs.is_empty() {
             return;
         }
@@ -136,8 +136,5 @@ impl CoreRule for FootnoteCollectRule {
         let mut wrapper = Node::new(FootnotesContainerNode);
         wrapper.children = defs;
         root.children.push(wrapper);
-
-        let data = root.cast_mut::<Root>().unwrap();
-        data.ext = root_ext;
     }
 }
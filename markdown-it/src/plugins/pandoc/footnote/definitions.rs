@@ -3,21 +3,20 @@
 //! ```rust
 //! let parser = &mut markdown_it::MarkdownIt::new();
 //! markdown_it::plugins::cmark::add(parser);
-//! markdown_it_footnote::definitions::add(parser);
+//! markdown_it::plugins::pandoc::footnote::definitions::add(parser);
 //! let root = parser.parse("[^label]: This is a footnote");
 //! let mut names = vec![];
 //! root.walk(|node,_| { names.push(node.name()); });
 //! assert_eq!(names, vec![
 //! "markdown_it::parser::core::root::Root",
-//! "markdown_it_footnote::definitions::FootnoteDefinition",
+//! "markdown_it::plugins::pandoc::footnote::definitions::FootnoteDefinition",
 //! "markdown_it::plugins::cmark::block::paragraph::Paragraph",
 //! "markdown_it::parser::inline::builtin::skip_text::Text",
 //! ]);
 //! ```
 
-use std::collections::HashMap;
-
 use crate::parser::block::{BlockRule, BlockState};
+use crate::parser::extset::RenderOptionsExtSet;
 use crate::plugins::cmark::block::reference::ReferenceScanner;
 use crate::{MarkdownIt, Node, NodeValue, Renderer};
 
@@ -32,6 +31,7 @@ pub fn add(md: &mut MarkdownIt) {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// AST node for footnote definition
 pub struct FootnoteDefinition {
     pub label: Option<String>,
@@ -40,7 +40,12 @@ pub struct FootnoteDefinition {
 }
 
 impl NodeValue for FootnoteDefinition {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &HashMap<String, String>) {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
         let mut attrs = node.attrs.clone();
         if let Some(def_id) = self.def_id {
             attrs.push(("id", format!("fn{}", def_id)));
@@ -173,7 +178,8 @@ mod tests {
         let parser = &mut MarkdownIt::new();
         cmark::add(parser);
         sourcepos::add(parser);
-        add(parser);
+        // full plugin would move unreferenced definition out (see `collect`)
+        definitions::add(parser);
         let node = parser.parse("[^note]: a\n\nhallo\nthere\n");
         // println!("{:#?}", node);
         assert!(node.children.first().unwrap().is::<FootnoteDefinition>());
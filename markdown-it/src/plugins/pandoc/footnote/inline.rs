@@ -3,7 +3,7 @@
 //! ```rust
 //! let parser = &mut markdown_it::MarkdownIt::new();
 //! markdown_it::plugins::cmark::add(parser);
-//! markdown_it_footnote::inline::add(parser);
+//! markdown_it::plugins::pandoc::footnote::inline::add(parser);
 //! let root = parser.parse("Example^[This is a footnote]");
 //! let mut names = vec![];
 //! root.walk(|node,_| { names.push(node.name()); });
@@ -11,13 +11,12 @@
 //! "markdown_it::parser::core::root::Root",
 //! "markdown_it::plugins::cmark::block::paragraph::Paragraph",
 //! "markdown_it::parser::inline::builtin::skip_text::Text",
-//! "markdown_it_footnote::inline::InlineFootnote",
-//! "markdown_it_footnote::definitions::FootnoteDefinition",
+//! "markdown_it::plugins::pandoc::footnote::inline::InlineFootnote",
+//! "markdown_it::plugins::pandoc::footnote::definitions::FootnoteDefinition",
 //! "markdown_it::parser::inline::builtin::skip_text::Text",
-//! "markdown_it_footnote::references::FootnoteReference"
+//! "markdown_it::plugins::pandoc::footnote::references::FootnoteReference"
 //! ]);
 //! ```
-use std::collections::HashMap;
 
 use crate::{
     parser::inline::{InlineRule, InlineState},
@@ -25,6 +24,7 @@ use crate::{
 };
 
 use super::definitions::FootnoteDefinition;
+use crate::parser::extset::RenderOptionsExtSet;
 use crate::plugins::pandoc::footnote::FootnoteMap;
 
 /// Add the inline footnote plugin to the parser
@@ -34,13 +34,14 @@ pub fn add(md: &mut MarkdownIt) {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub struct InlineFootnote;
 impl NodeValue for InlineFootnote {
     fn render(
         &self,
         node: &Node,
         fmt: &mut dyn crate::Renderer,
-        options: &HashMap<String, String>,
+        options: &RenderOptionsExtSet,
     ) {
         // simply pass-through to children
         fmt.contents(&node.children, options);
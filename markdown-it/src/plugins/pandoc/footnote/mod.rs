@@ -2,7 +2,7 @@
 //!
 //! ```
 //! let parser = &mut markdown_it::MarkdownIt::new();
-//! markdown_it_footnote::add(parser);
+//! markdown_it::plugins::pandoc::footnote::add(parser);
 //! let node = parser.parse("[^note]\n\n[^note]: A footnote\n");
 //! ```
 use std::collections::HashMap;
@@ -3,22 +3,22 @@
 //! ```rust
 //! let parser = &mut markdown_it::MarkdownIt::new();
 //! markdown_it::plugins::cmark::add(parser);
-//! markdown_it_footnote::references::add(parser);
-//! markdown_it_footnote::definitions::add(parser);
+//! markdown_it::plugins::pandoc::footnote::references::add(parser);
+//! markdown_it::plugins::pandoc::footnote::definitions::add(parser);
 //! let root = parser.parse("[^label]\n\n[^label]: This is a footnote");
 //! let mut names = vec![];
 //! root.walk(|node,_| { names.push(node.name()); });
 //! assert_eq!(names, vec![
 //! "markdown_it::parser::core::root::Root",
 //! "markdown_it::plugins::cmark::block::paragraph::Paragraph",
-//! "markdown_it_footnote::references::FootnoteReference",
-//! "markdown_it_footnote::definitions::FootnoteDefinition",
+//! "markdown_it::plugins::pandoc::footnote::references::FootnoteReference",
+//! "markdown_it::plugins::pandoc::footnote::definitions::FootnoteDefinition",
 //! "markdown_it::plugins::cmark::block::paragraph::Paragraph",
 //! "markdown_it::parser::inline::builtin::skip_text::Text"
 //! ]);
 //! ```
-use std::collections::HashMap;
 
+use crate::parser::extset::RenderOptionsExtSet;
 use crate::parser::inline::{InlineRule, InlineState};
 use crate::{MarkdownIt, Node, NodeValue, Renderer};
 
@@ -31,6 +31,7 @@ pub fn add(md: &mut MarkdownIt) {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// AST node for footnote reference
 pub struct FootnoteReference {
     pub label: Option<String>,
@@ -39,7 +40,12 @@ pub struct FootnoteReference {
 }
 
 impl NodeValue for FootnoteReference {
-    fn render(&self, node: &Node, fmt: &mut dyn Renderer, _options: &HashMap<String, String>) {
+    #[cfg(feature = "serde")]
+    fn to_json_data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer, _options: &RenderOptionsExtSet) {
         let mut attrs = node.attrs.clone();
         attrs.push(("class", "footnote-ref".into()));
 
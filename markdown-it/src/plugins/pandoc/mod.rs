@@ -1,9 +1,11 @@
 use crate::MarkdownIt;
 
+pub mod attrs;
 pub mod deflist;
 pub mod footnote;
 
 pub fn add(md: &mut MarkdownIt) {
+    attrs::add(md);
     deflist::add(md);
     footnote::add(md);
 }
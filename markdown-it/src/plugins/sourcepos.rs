@@ -4,10 +4,10 @@
 //! markdown_it::plugins::cmark::add(md);
 //! markdown_it::plugins::sourcepos::add(md);
 //!
-//! let html = md.parse("# hello").render();
+//! let html = md.parse("# hello").render(&Default::default());
 //! assert_eq!(html.trim(), r#"<h1 data-sourcepos="1:1-1:7">hello</h1>"#);
 //! ```
-use crate::common::sourcemap::SourceWithLineStarts;
+use crate::common::sourcemap::{SourcePos, SourceWithLineStarts};
 use crate::parser::block::builtin::BlockParserRule;
 use crate::parser::core::{CoreRule, Root};
 use crate::parser::inline::builtin::InlineParserRule;
@@ -28,18 +28,38 @@ impl CoreRule for SyntaxPosRule {
 
         root.walk_mut(|node, _| {
             if let Some(map) = node.srcmap {
-                let ((startline, startcol), (endline, endcol)) = map.get_positions(&mapping);
-                node.attrs.push(("data-sourcepos", format!("{}:{}-{}:{}", startline, startcol, endline, endcol)));
+                node.attrs.push(("data-sourcepos", format_pos(map, &mapping)));
             }
         });
     }
 }
 
+/// Recalculate positions of the nodes moved by incremental parsing.
+pub(crate) fn update_moved(nodes: &mut [Node], source: &str) {
+    let has_attrs = |node: &Node| node.attrs.iter().any(|(name, _)| *name == "data-sourcepos");
+    if !nodes.first().is_some_and(has_attrs) { return; }
+
+    let mapping = SourceWithLineStarts::new(source);
+    for node in nodes.iter_mut() {
+        node.walk_mut(|node, _| {
+            let Some(map) = node.srcmap else { return; };
+            for (name, value) in node.attrs.iter_mut() {
+                if *name == "data-sourcepos" {
+                    *value = format_pos(map, &mapping);
+                }
+            }
+        });
+    }
+}
+
+fn format_pos(map: SourcePos, mapping: &SourceWithLineStarts) -> String {
+    let ((startline, startcol), (endline, endcol)) = map.get_positions(mapping);
+    format!("{}:{}-{}:{}", startline, startcol, endline, endcol)
+}
+
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
-
     #[test]
     fn header_test() {
         // same as doctest, keep in sync!
@@ -48,7 +68,7 @@ mod tests {
         crate::plugins::cmark::add(md);
         crate::plugins::sourcepos::add(md);
 
-        let html = md.parse("# hello").render(&HashMap::new());
+        let html = md.parse("# hello").render(&Default::default());
         assert_eq!(html.trim(), r#"<h1 data-sourcepos="1:1-1:7">hello</h1>"#);
     }
 }
@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 use once_cell::sync::Lazy;
 
 #[test]
@@ -8,7 +6,7 @@ fn title_example() {
     markdown_it::plugins::cmark::add(parser);
 
     let ast = parser.parse("Hello **world**!");
-    let html = ast.render(&HashMap::new());
+    let html = ast.render(&Default::default());
 
     assert_eq!(html, "<p>Hello <strong>world</strong>!</p>\n");
 }
@@ -22,7 +20,7 @@ fn lazy_singleton() {
     });
 
     let ast = MD.parse("Hello **world**!");
-    let html = ast.render(&HashMap::new());
+    let html = ast.render(&Default::default());
 
     assert_eq!(html, "<p>Hello <strong>world</strong>!</p>\n");
 }
@@ -31,7 +29,7 @@ fn lazy_singleton() {
 fn no_plugins() {
     let md = &mut markdown_it::MarkdownIt::new();
     let node = md.parse("hello\nworld");
-    let result = node.render(&HashMap::new());
+    let result = node.render(&Default::default());
     assert_eq!(result, "hello\nworld\n");
 }
 
@@ -42,10 +40,32 @@ fn no_max_indent() {
     markdown_it::plugins::cmark::block::list::add(md);
     md.max_indent = i32::MAX;
     let node = md.parse("        paragraph\n      - item");
-    let result = node.render(&HashMap::new());
+    let result = node.render(&Default::default());
     assert_eq!(result, "<p>paragraph</p>\n<ul>\n<li>item</li>\n</ul>\n");
 }
 
+#[test]
+fn footnotes_with_any_plugin_order() {
+    use markdown_it::plugins::{cmark, jotdown, pandoc};
+
+    let orders: [&[fn(&mut markdown_it::MarkdownIt)]; 4] = [
+        &[pandoc::add, jotdown::add],
+        &[jotdown::add, pandoc::add],
+        &[pandoc::footnote::add, pandoc::attrs::add],
+        &[pandoc::attrs::add, pandoc::footnote::add],
+    ];
+
+    for plugins in orders {
+        let md = &mut markdown_it::MarkdownIt::new();
+        cmark::add(md);
+        for add in plugins {
+            add(md);
+        }
+        let html = md.parse("text[^1]\n\n[^1]: x").render(&Default::default());
+        assert!(html.contains("<section class=\"footnotes\">"), "{html}");
+    }
+}
+
 /*#[test]
 fn no_block_parser() {
     let md = &mut markdown_it::MarkdownIt::new();
@@ -68,7 +88,7 @@ fn run(input: &str, output: &str) {
     markdown_it::plugins::extra::beautify_links::add(md);
     let node = md.parse(&(input.to_owned() + "\n"));
     node.walk(|node, _| assert!(node.srcmap.is_some()));
-    let result = node.render(&HashMap::new());
+    let result = node.render(&Default::default());
     assert_eq!(result, output);
 }
 
@@ -133,6 +153,42 @@ mod markdown_it_rs_extras {
             "<p>There is a newline in this image  <img src=\"https://github.com/executablebooks/\" alt=\"here\nit is\"></p>");
     }
 
+    #[test]
+    fn escapes_and_entities_in_image_alt() {
+        run("![a\\*b &amp; \\[c\\]](/img.png)",
+            "<p><img src=\"/img.png\" alt=\"a*b &amp; [c]\"></p>");
+    }
+
+    #[test]
+    fn fence_line_options() {
+        run("```rust {2,4-5} linenos\na\nb\nc\nd\ne\n```",
+            "<pre><code class=\"language-rust\">\
+            <span class=\"line\" data-line=\"1\"><span class=\"line-number\">1</span>a\n</span>\
+            <span class=\"line highlighted\" data-line=\"2\"><span class=\"line-number\">2</span>b\n</span>\
+            <span class=\"line\" data-line=\"3\"><span class=\"line-number\">3</span>c\n</span>\
+            <span class=\"line highlighted\" data-line=\"4\"><span class=\"line-number\">4</span>d\n</span>\
+            <span class=\"line highlighted\" data-line=\"5\"><span class=\"line-number\">5</span>e\n</span>\
+            </code></pre>");
+        run("```html {1}\n<a>\n```",
+            "<pre><code class=\"language-html\"><span class=\"line highlighted\" data-line=\"1\">&lt;a&gt;\n</span></code></pre>");
+        // not line ranges, ignored
+        run("```js {.foo}\na\n```", "<pre><code class=\"language-js\">a\n</code></pre>");
+    }
+
+    #[test]
+    fn fence_line_options_parse() {
+        use markdown_it::plugins::cmark::block::fence::FenceLineOptions;
+
+        let options = FenceLineOptions::parse("rust { 3, 5 - 7 } linenos");
+        assert_eq!(options.highlight, vec![(3, 3), (5, 7)]);
+        assert!(options.line_numbers);
+        assert!(options.is_highlighted(6));
+        assert!(!options.is_highlighted(4));
+
+        assert!(FenceLineOptions::parse("linenos").is_empty());
+        assert!(FenceLineOptions::parse("rust {3,x}").is_empty());
+    }
+
     #[test]
     fn test_node_ext_propagation() {
         use markdown_it::parser::block::{BlockRule, BlockState};
@@ -194,6 +250,155 @@ mod markdown_it_rs_extras {
     }
 }
 
+mod html_renderer {
+    use markdown_it::parser::extset::RenderOptionsExtSet;
+    use markdown_it::parser::renderer::{AttributeOrder, HTMLRenderer, HTMLRendererOptions, NewlineStyle};
+    use markdown_it::plugins::cmark::block::heading::ATXHeading;
+    use markdown_it::{MarkdownIt, Node, Renderer};
+
+    fn parser() -> MarkdownIt {
+        let mut md = MarkdownIt::new();
+        markdown_it::plugins::cmark::add(&mut md);
+        md
+    }
+
+    fn render(options: HTMLRendererOptions, src: &str) -> String {
+        let mut fmt = HTMLRenderer::with_options(options);
+        fmt.render(&parser().parse(src), &Default::default());
+        fmt.into()
+    }
+
+    #[test]
+    fn xhtml() {
+        let options = HTMLRendererOptions { xhtml: true, ..Default::default() };
+        assert_eq!(render(options, "![a](b)"), "<p><img src=\"b\" alt=\"a\" /></p>\n");
+    }
+
+    #[test]
+    fn crlf() {
+        let options = HTMLRendererOptions { newline: NewlineStyle::CrLf, ..Default::default() };
+        assert_eq!(render(options, "a\nb\n\n    c\n    d"), "<p>a\r\nb</p>\r\n<pre><code>c\r\nd\r\n</code></pre>\r\n");
+    }
+
+    #[test]
+    fn attribute_order() {
+        let options = HTMLRendererOptions { attribute_order: AttributeOrder::Alphabetical, ..Default::default() };
+        assert_eq!(render(options, "[a](b \"c\")"), "<p><a href=\"b\" title=\"c\">a</a></p>\n");
+        assert_eq!(render(options, "![a](b \"c\")"), "<p><img alt=\"a\" src=\"b\" title=\"c\"></p>\n");
+    }
+
+    #[test]
+    fn node_override() {
+        fn heading(node: &Node, fmt: &mut dyn Renderer, options: &RenderOptionsExtSet) {
+            fmt.open("header", &[]);
+            node.node_value.render(node, fmt, options);
+            fmt.close("header");
+            fmt.cr();
+        }
+
+        let mut fmt = HTMLRenderer::new();
+        fmt.set_override::<ATXHeading>(heading);
+        fmt.render(&parser().parse("# a\n\n> # b"), &Default::default());
+        assert_eq!(String::from(fmt), "<header>\n<h1>a</h1>\n</header>\n<blockquote>\n<header>\n<h1>b</h1>\n</header>\n</blockquote>\n");
+    }
+
+    #[test]
+    fn several_subtrees() {
+        let md = parser();
+        let mut fmt = HTMLRenderer::new();
+        fmt.open("title", &[]);
+        fmt.contents(&md.parse("*Title*").children[0].children, &Default::default());
+        fmt.close("title");
+        fmt.cr();
+        fmt.render(&md.parse("text\0"), &Default::default());
+        assert_eq!(String::from(fmt), "<title><em>Title</em></title>\n<p>text\u{FFFD}</p>\n");
+    }
+}
+
+mod streaming {
+    use std::fmt::Write;
+    use std::io;
+
+    use markdown_it::parser::renderer::{HTMLRenderer, HTMLRendererOptions};
+
+    fn parser() -> markdown_it::MarkdownIt {
+        let mut md = markdown_it::MarkdownIt::new();
+        markdown_it::plugins::cmark::add(&mut md);
+        md
+    }
+
+    #[test]
+    fn render_to_io() {
+        let ast = parser().parse("# a\0\n\n    b\0");
+        let mut out = Vec::new();
+        ast.render_to(&mut out, &Default::default()).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), ast.render(&Default::default()));
+        assert_eq!(ast.render(&Default::default()), "<h1>a\u{FFFD}</h1>\n<pre><code>b\u{FFFD}\n</code></pre>\n");
+    }
+
+    #[test]
+    fn render_to_io_error() {
+        struct Broken;
+        impl io::Write for Broken {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::BrokenPipe, "broken"))
+            }
+            fn flush(&mut self) -> io::Result<()> { Ok(()) }
+        }
+
+        let err = parser().parse("hello").render_to(&mut Broken, &Default::default()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    }
+
+    #[test]
+    fn render_to_fmt() {
+        let mut out = String::from("<!-- header -->");
+        out.write_str("\n").unwrap();
+        let mut fmt = HTMLRenderer::with_writer(&mut out, HTMLRendererOptions::default());
+        fmt.render(&parser().parse("*a*"), &Default::default());
+        assert!(fmt.status().is_ok());
+        assert_eq!(out, "<!-- header -->\n<p><em>a</em></p>\n");
+    }
+}
+
+mod link_policy {
+    use markdown_it::parser::linkfmt::LinkPolicy;
+    use markdown_it::MarkdownIt;
+
+    fn parser(policy: LinkPolicy) -> MarkdownIt {
+        let mut md = MarkdownIt::with_link_policy(policy);
+        markdown_it::plugins::cmark::add(&mut md);
+        markdown_it::plugins::extra::linkify::add(&mut md);
+        md
+    }
+
+    #[test]
+    fn external_links() {
+        let md = parser(LinkPolicy { internal_hosts: vec!["example.org".into()], ..LinkPolicy::strict() });
+        let html = md.parse("[a](https://a.com) [b][] <https://b.com> https://c.com/x [d](/d) [e](https://example.org)\n\n[b]: //b.com").render(&Default::default());
+        assert_eq!(html.trim(), "<p>\
+            <a rel=\"noopener noreferrer nofollow\" target=\"_blank\" href=\"https://a.com\">a</a> \
+            <a rel=\"noopener noreferrer nofollow\" target=\"_blank\" href=\"//b.com\">b</a> \
+            <a rel=\"noopener noreferrer nofollow\" target=\"_blank\" href=\"https://b.com\">https://b.com</a> \
+            <a rel=\"noopener noreferrer nofollow\" target=\"_blank\" href=\"https://c.com/x\">https://c.com/x</a> \
+            <a href=\"/d\">d</a> <a href=\"https://example.org\">e</a></p>");
+    }
+
+    #[test]
+    fn images_are_not_marked() {
+        let md = parser(LinkPolicy::strict());
+        let html = md.parse("![a](https://a.com/a.png)").render(&Default::default());
+        assert_eq!(html.trim(), "<p><img src=\"https://a.com/a.png\" alt=\"a\"></p>");
+    }
+
+    #[test]
+    fn denied_hosts() {
+        let md = parser(LinkPolicy { denied_hosts: vec!["a.com".into()], ..Default::default() });
+        let html = md.parse("[a](https://www.a.com) <https://a.com> a.com").render(&Default::default());
+        assert_eq!(html.trim(), "<p>[a](https://www.a.com) &lt;https://a.com&gt; a.com</p>");
+    }
+}
+
 mod examples {
     include!("../examples/ferris/main.rs");
 
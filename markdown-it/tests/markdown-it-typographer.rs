@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 fn run(input: &str, output: &str) {
     let output = if output.is_empty() {
         "".to_owned()
@@ -16,7 +14,7 @@ fn run(input: &str, output: &str) {
     // make sure we have sourcemaps for everything
     node.walk(|node, _| assert!(node.srcmap.is_some()));
 
-    let result = node.render(&HashMap::new());
+    let result = node.render(&Default::default());
     assert_eq!(result, output);
 
     // make sure it doesn't crash without trailing \n
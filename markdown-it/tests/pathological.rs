@@ -9,6 +9,7 @@ static MD : Lazy<MarkdownIt> = Lazy::new(|| {
     markdown_it::plugins::cmark::add(&mut parser);
     markdown_it::plugins::html::add(&mut parser);
     markdown_it::plugins::extra::add(&mut parser);
+    markdown_it::plugins::jotdown::katex::add(&mut parser);
     parser
 });
 
@@ -138,3 +139,32 @@ mod markdownit {
     }
 }
 
+
+mod math {
+    use super::run;
+
+    #[test]
+    fn unclosed_dollars() {
+        run(&"$a ".repeat(50000));
+    }
+
+    #[test]
+    fn unclosed_double_dollars() {
+        run(&"$$a ".repeat(50000));
+    }
+
+    #[test]
+    fn unclosed_parens() {
+        run(&"\\(a ".repeat(50000));
+    }
+
+    #[test]
+    fn unclosed_blocks() {
+        run(&"$$a\n".repeat(50000));
+    }
+
+    #[test]
+    fn unclosed_bracket_blocks() {
+        run(&"\\[a\n".repeat(50000));
+    }
+}